@@ -0,0 +1,26 @@
+use dapnet_api::{OutgoingCallBuilder, pocsag};
+
+fn main() {
+    let ric = std::env::var("POCSAG_RIC").unwrap().parse().unwrap();
+    let text = std::env::var("POCSAG_TEXT").unwrap();
+    let output = std::env::var("POCSAG_OUTPUT").unwrap();
+
+    let call = OutgoingCallBuilder::default()
+        .text(text)
+        .recipients(vec![])
+        .transmitter_groups(vec![])
+        .build()
+        .unwrap();
+
+    let options = pocsag::WaveformOptions::default();
+    let samples =
+        pocsag::render_call(&call, ric, pocsag::Function::Alphanumeric, &options).unwrap();
+
+    let file = std::fs::File::create(output).unwrap();
+    pocsag::write_wav(
+        &samples,
+        options.sample_rate(),
+        std::io::BufWriter::new(file),
+    )
+    .unwrap();
+}
@@ -1,4 +1,4 @@
-use dapnet_api::{Client, OutgoingNewsBuilder};
+use dapnet_api::{Client, OutgoingNewsBuilder, SenderPrefix, SenderPrefixMode};
 
 #[tokio::main]
 async fn main() {
@@ -6,13 +6,14 @@ async fn main() {
     let password = std::env::var("DAPNET_PASSWORD").unwrap();
     let rubric_name = std::env::var("DAPNET_RUBRIC").unwrap();
 
-    let client = Client::new(&username, &password);
+    let client = Client::new(&username, &password)
+        .with_sender_prefix(SenderPrefix::new(&username, SenderPrefixMode::Insert));
 
     client
         .new_news(
             &OutgoingNewsBuilder::default()
                 .rubric(rubric_name.clone())
-                .text(format!("{username}: this is a test"))
+                .text("this is a test".to_string())
                 .build()
                 .unwrap(),
         )
@@ -1,16 +1,17 @@
-use dapnet_api::{Client, OutgoingCallBuilder};
+use dapnet_api::{Client, OutgoingCallBuilder, SenderPrefix, SenderPrefixMode};
 
 #[tokio::main]
 async fn main() {
     let username = std::env::var("DAPNET_USERNAME").unwrap();
     let password = std::env::var("DAPNET_PASSWORD").unwrap();
 
-    let client = Client::new(&username, &password);
+    let client = Client::new(&username, &password)
+        .with_sender_prefix(SenderPrefix::new(&username, SenderPrefixMode::Insert));
 
     client
         .new_call(
             &OutgoingCallBuilder::default()
-                .text(format!("{username}: this is a test"))
+                .text("this is a test".to_string())
                 .recipients(vec![username.clone()])
                 .transmitter_groups(vec!["uk-all".to_string()])
                 .build()
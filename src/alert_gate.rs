@@ -0,0 +1,807 @@
+use crate::{
+    Client, OutgoingCall, OutgoingCallBuilder, message_sanitization::truncate, outbox::is_transient,
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use derive_builder::Builder;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    path::PathBuf,
+    sync::Mutex,
+    time::Duration,
+};
+
+/// How messages are compared to decide if they are repeats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertSimilarity {
+    /// Only identical text is a repeat.
+    Identical,
+
+    /// Text that differs only in case, whitespace or numbers is a repeat, e.g. "CPU at 91%" and
+    /// "cpu at 93%".
+    IgnoreNumbers,
+}
+
+impl AlertSimilarity {
+    fn normalize(&self, text: &str) -> String {
+        match self {
+            Self::Identical => text.to_string(),
+            Self::IgnoreNumbers => {
+                let mut normalized = String::new();
+                for word in text.split_whitespace() {
+                    if !normalized.is_empty() {
+                        normalized.push(' ');
+                    }
+                    for c in word.chars().flat_map(char::to_lowercase) {
+                        if !(c.is_ascii_digit() && normalized.ends_with('#')) {
+                            normalized.push(if c.is_ascii_digit() { '#' } else { c });
+                        }
+                    }
+                }
+                normalized
+            }
+        }
+    }
+}
+
+/// Options that control which calls an [`AlertGate`] holds back.
+#[derive(Debug, Clone, PartialEq, Eq, Builder)]
+#[builder(build_fn(validate = "Self::validate"))]
+pub struct AlertGateOptions {
+    /// How long after a call is sent that repeats of it are suppressed.
+    #[builder(default = "Duration::from_secs(5 * 60)")]
+    window: Duration,
+
+    #[builder(default = "AlertSimilarity::IgnoreNumbers")]
+    similarity: AlertSimilarity,
+
+    /// Period over which changes of an alert's state are counted.
+    #[builder(default = "Duration::from_secs(10 * 60)")]
+    flap_window: Duration,
+
+    /// Number of state changes within `flap_window` at which an alert is considered to be
+    /// flapping.
+    #[builder(default = "3")]
+    flap_threshold: usize,
+
+    /// How long a flapping alert must stay in one state before it is sent again.
+    #[builder(default = "Duration::from_secs(10 * 60)")]
+    hold_down: Duration,
+}
+
+impl AlertGateOptionsBuilder {
+    fn validate(&self) -> Result<(), String> {
+        if self.flap_threshold == Some(0) {
+            Err("Flap threshold must be at least 1".to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for AlertGateOptions {
+    fn default() -> Self {
+        AlertGateOptionsBuilder::default().build().unwrap()
+    }
+}
+
+fn delta(duration: Duration) -> TimeDelta {
+    TimeDelta::from_std(duration).unwrap_or(TimeDelta::MAX)
+}
+
+/// State of an alert that can flap, e.g. from Alertmanager.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AlertState {
+    Firing,
+    Resolved,
+}
+
+/// What an [`AlertGate`] did with a call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertGateDecision {
+    Sent,
+
+    /// The call repeats one sent recently, `repeats` have been suppressed so far.
+    Suppressed {
+        repeats: u32,
+    },
+
+    /// The alert is flapping and held down, `changes` have been suppressed so far.
+    HeldDown {
+        changes: u32,
+    },
+}
+
+/// Where to send a page.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Target {
+    recipients: Vec<String>,
+    transmitter_groups: Vec<String>,
+    emergency: bool,
+}
+
+impl Target {
+    fn of(call: &OutgoingCall) -> Self {
+        Self {
+            recipients: call.recipients.clone(),
+            transmitter_groups: call.transmitter_groups.clone(),
+            emergency: call.emergency,
+        }
+    }
+
+    fn call(&self, text: String) -> crate::Result<OutgoingCall> {
+        Ok(OutgoingCallBuilder::default()
+            .text(truncate(text, 80, "..."))
+            .recipients(self.recipients.clone())
+            .transmitter_groups(self.transmitter_groups.clone())
+            .emergency(self.emergency)
+            .build()?)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Repeats {
+    sent: DateTime<Utc>,
+    text: String,
+    target: Target,
+    suppressed: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Flapping {
+    /// Last state that was paged.
+    sent_state: AlertState,
+    state: AlertState,
+    text: String,
+    target: Target,
+    changes: VecDeque<DateTime<Utc>>,
+    held_down: bool,
+    suppressed: u32,
+}
+
+/// State of an [`AlertGate`], stored by an [`AlertGateStore`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AlertGateState {
+    repeats: BTreeMap<String, Repeats>,
+    alerts: BTreeMap<String, Flapping>,
+
+    /// Summary pages that have not been sent yet.
+    #[serde(default)]
+    summaries: VecDeque<(Target, String)>,
+}
+
+impl AlertGateState {
+    fn repeat_key(options: &AlertGateOptions, call: &OutgoingCall) -> String {
+        let mut recipients = call
+            .recipients
+            .iter()
+            .map(|r| r.to_lowercase())
+            .collect::<Vec<_>>();
+        recipients.sort();
+        format!(
+            "{}|{}",
+            recipients.join(","),
+            options.similarity.normalize(&call.text)
+        )
+    }
+
+    /// Decides what to do with a call, when it should be sent it is recorded as sent.
+    fn on_call(
+        &mut self,
+        options: &AlertGateOptions,
+        now: DateTime<Utc>,
+        call: &OutgoingCall,
+    ) -> AlertGateDecision {
+        let key = Self::repeat_key(options, call);
+        match self.repeats.get_mut(&key) {
+            Some(repeats) if now < repeats.sent + delta(options.window) => {
+                repeats.suppressed += 1;
+                repeats.text = call.text.clone();
+                AlertGateDecision::Suppressed {
+                    repeats: repeats.suppressed,
+                }
+            }
+            _ => {
+                self.repeats.insert(
+                    key,
+                    Repeats {
+                        sent: now,
+                        text: call.text.clone(),
+                        target: Target::of(call),
+                        suppressed: 0,
+                    },
+                );
+                AlertGateDecision::Sent
+            }
+        }
+    }
+
+    fn on_alert(
+        &mut self,
+        options: &AlertGateOptions,
+        now: DateTime<Utc>,
+        alert: &str,
+        state: AlertState,
+        call: &OutgoingCall,
+    ) -> AlertGateDecision {
+        let Some(flapping) = self.alerts.get_mut(alert) else {
+            self.alerts.insert(
+                alert.to_string(),
+                Flapping {
+                    sent_state: state,
+                    state,
+                    text: call.text.clone(),
+                    target: Target::of(call),
+                    changes: VecDeque::new(),
+                    held_down: false,
+                    suppressed: 0,
+                },
+            );
+            return self.on_call(options, now, call);
+        };
+
+        if state == flapping.state && !flapping.held_down {
+            return self.on_call(options, now, call);
+        }
+
+        if state != flapping.state {
+            flapping.changes.push_back(now);
+        }
+        while flapping
+            .changes
+            .front()
+            .is_some_and(|&change| change + delta(options.flap_window) <= now)
+        {
+            flapping.changes.pop_front();
+        }
+        flapping.state = state;
+        flapping.text = call.text.clone();
+        flapping.target = Target::of(call);
+
+        if flapping.held_down || flapping.changes.len() >= options.flap_threshold {
+            flapping.held_down = true;
+            flapping.suppressed += 1;
+            AlertGateDecision::HeldDown {
+                changes: flapping.suppressed,
+            }
+        } else {
+            // A change of state is never a repeat, even of a page sent before the last change
+            flapping.sent_state = state;
+            self.repeats.remove(&Self::repeat_key(options, call));
+            self.on_call(options, now, call)
+        }
+    }
+
+    /// Removes windows and hold-downs that have ended, returning the summary pages to send.
+    fn expire(&mut self, options: &AlertGateOptions, now: DateTime<Utc>) -> Vec<(Target, String)> {
+        let mut pages = Vec::new();
+
+        self.repeats.retain(|_, repeats| {
+            if now < repeats.sent + delta(options.window) {
+                return true;
+            }
+            if repeats.suppressed > 0 {
+                let s = if repeats.suppressed == 1 { "" } else { "s" };
+                pages.push((
+                    repeats.target.clone(),
+                    format!(
+                        "{} repeat{s} suppressed: {}",
+                        repeats.suppressed, repeats.text
+                    ),
+                ));
+            }
+            false
+        });
+
+        for flapping in self.alerts.values_mut() {
+            let quiet = flapping
+                .changes
+                .back()
+                .is_none_or(|&change| change + delta(options.hold_down) <= now);
+            if flapping.held_down && quiet {
+                let s = if flapping.suppressed == 1 { "" } else { "s" };
+                pages.push((
+                    flapping.target.clone(),
+                    format!(
+                        "{} change{s} suppressed while flapping: {}",
+                        flapping.suppressed, flapping.text
+                    ),
+                ));
+                flapping.held_down = false;
+                flapping.suppressed = 0;
+                flapping.changes.clear();
+                flapping.sent_state = flapping.state;
+            }
+        }
+        self.alerts.retain(|_, flapping| {
+            flapping.held_down
+                || flapping
+                    .changes
+                    .back()
+                    .is_some_and(|&change| now < change + delta(options.flap_window))
+                || flapping.sent_state != AlertState::Resolved
+        });
+
+        pages
+    }
+}
+
+/// Storage for the state of an [`AlertGate`], so that it survives restarts.
+pub trait AlertGateStore: Send + Sync {
+    fn load(&self) -> crate::Result<AlertGateState>;
+    fn save(&self, state: &AlertGateState) -> crate::Result<()>;
+}
+
+/// Keeps state in memory only.
+#[derive(Debug, Default)]
+pub struct MemoryAlertGateStore(Mutex<AlertGateState>);
+
+impl AlertGateStore for MemoryAlertGateStore {
+    fn load(&self) -> crate::Result<AlertGateState> {
+        Ok(self.0.lock().unwrap().clone())
+    }
+
+    fn save(&self, state: &AlertGateState) -> crate::Result<()> {
+        *self.0.lock().unwrap() = state.clone();
+        Ok(())
+    }
+}
+
+/// Keeps state in a JSON file, which is created if it does not exist.
+#[derive(Debug, Clone)]
+pub struct FileAlertGateStore {
+    path: PathBuf,
+}
+
+impl FileAlertGateStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl AlertGateStore for FileAlertGateStore {
+    fn load(&self) -> crate::Result<AlertGateState> {
+        match std::fs::read(&self.path) {
+            Ok(contents) => serde_json::from_slice(&contents).map_err(|e| {
+                crate::Error::ConfigError(format!(
+                    "invalid alert gate state in {}: {e}",
+                    self.path.display()
+                ))
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(AlertGateState::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&self, state: &AlertGateState) -> crate::Result<()> {
+        crate::fs::write_atomically(&self.path, &serde_json::to_vec(state).unwrap())?;
+        Ok(())
+    }
+}
+
+/// Sends calls through a [`Client`], holding back repeated and flapping alerts.
+///
+/// Calls to the same recipients with similar text (see [`AlertSimilarity`]) are only sent once
+/// per window.
+/// Alerts that change state too often are held down until they settle.
+/// When a window or hold-down ends a single summary page is sent, e.g. "5 repeats suppressed:
+/// disk full", this happens on the next call or when [`AlertGate::flush`] is called, so `flush`
+/// should be called periodically.
+///
+/// Example:
+/// ```no_run
+/// # use dapnet_api::{AlertGate, AlertGateOptions, AlertState, Client, MemoryAlertGateStore,
+/// #     OutgoingCallBuilder};
+/// # use std::time::Duration;
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let client = Client::new("m0nxn", "my_super_secret_password");
+/// let gate = AlertGate::new(
+///     client,
+///     AlertGateOptions::default(),
+///     MemoryAlertGateStore::default(),
+/// )
+/// .unwrap();
+///
+/// let call = OutgoingCallBuilder::default()
+///     .text("M0NXN: disk full on db1".to_string())
+///     .recipients(vec!["m0nxn".to_string()])
+///     .transmitter_groups(vec!["uk-all".to_string()])
+///     .build()
+///     .unwrap();
+/// gate.new_alert_call("disk_full/db1", AlertState::Firing, &call)
+///     .await
+///     .unwrap();
+///
+/// let mut interval = tokio::time::interval(Duration::from_secs(30));
+/// loop {
+///     interval.tick().await;
+///     gate.flush().await.unwrap();
+/// }
+/// # }
+/// ```
+pub struct AlertGate<S: AlertGateStore> {
+    client: Client,
+    options: AlertGateOptions,
+    store: S,
+    state: tokio::sync::Mutex<AlertGateState>,
+}
+
+impl<S: AlertGateStore> std::fmt::Debug for AlertGate<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AlertGate")
+            .field("client", &self.client)
+            .field("options", &self.options)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: AlertGateStore> AlertGate<S> {
+    /// Creates a gate, loading any existing state from `store`.
+    pub fn new(client: Client, options: AlertGateOptions, store: S) -> crate::Result<Self> {
+        let state = store.load()?;
+        Ok(Self {
+            client,
+            options,
+            store,
+            state: tokio::sync::Mutex::new(state),
+        })
+    }
+
+    /// Sends a call unless it repeats one sent within the window.
+    pub async fn new_call(&self, call: &OutgoingCall) -> crate::Result<AlertGateDecision> {
+        self.gate(call, |state, options, now| {
+            state.on_call(options, now, call)
+        })
+        .await
+    }
+
+    /// Sends a call for an alert identified by `alert`, unless it is a repeat or the alert is
+    /// flapping.
+    pub async fn new_alert_call(
+        &self,
+        alert: &str,
+        state: AlertState,
+        call: &OutgoingCall,
+    ) -> crate::Result<AlertGateDecision> {
+        self.gate(call, |gate_state, options, now| {
+            gate_state.on_alert(options, now, alert, state, call)
+        })
+        .await
+    }
+
+    /// Sends summaries for windows and hold-downs that have ended, returning how many were sent.
+    ///
+    /// A summary that fails with a transient error, e.g. the API is unreachable, is kept for the
+    /// next flush, any other failure drops it.
+    /// Either way the first error is returned.
+    pub async fn flush(&self) -> crate::Result<usize> {
+        let mut state = self.state.lock().await;
+        self.flush_locked(&mut state, Utc::now()).await
+    }
+
+    async fn flush_locked(
+        &self,
+        state: &mut AlertGateState,
+        now: DateTime<Utc>,
+    ) -> crate::Result<usize> {
+        let pages = state.expire(&self.options, now);
+        if !pages.is_empty() {
+            state.summaries.extend(pages);
+            self.store.save(state)?;
+        }
+
+        let mut sent = 0;
+        let mut dropped = None;
+        while let Some((target, text)) = state.summaries.front() {
+            let result = match target.call(text.clone()) {
+                Ok(call) => self.client.new_call(&call).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => sent += 1,
+                // Kept with those after it, to be sent in order by the next flush
+                Err(e) if is_transient(&e) => return Err(e),
+                // Would fail every time, so it must not hold up the others
+                Err(e) => {
+                    dropped.get_or_insert(e);
+                }
+            }
+            state.summaries.pop_front();
+            self.store.save(state)?;
+        }
+
+        match dropped {
+            Some(e) => Err(e),
+            None => Ok(sent),
+        }
+    }
+
+    async fn gate(
+        &self,
+        call: &OutgoingCall,
+        decide: impl FnOnce(&mut AlertGateState, &AlertGateOptions, DateTime<Utc>) -> AlertGateDecision,
+    ) -> crate::Result<AlertGateDecision> {
+        let mut state = self.state.lock().await;
+        let now = Utc::now();
+        // Summaries are best effort, failing to send them must not stop the new call
+        let _ = self.flush_locked(&mut state, now).await;
+
+        // Decided on a copy so that a call that fails to send is not recorded as sent
+        let mut next = state.clone();
+        let decision = decide(&mut next, &self.options, now);
+        if decision == AlertGateDecision::Sent {
+            self.client.new_call(call).await?;
+        }
+        self.store.save(&next)?;
+        *state = next;
+        Ok(decision)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{SenderPrefix, SenderPrefixMode, test_util::unreachable_client};
+
+    fn call(text: &str) -> OutgoingCall {
+        OutgoingCallBuilder::default()
+            .text(text.to_string())
+            .recipients(vec!["m0nxn".to_string(), "m0abc".to_string()])
+            .transmitter_groups(vec!["uk-all".to_string()])
+            .build()
+            .unwrap()
+    }
+
+    fn time(minutes: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(0, 0).unwrap() + TimeDelta::minutes(minutes)
+    }
+
+    fn texts(pages: Vec<(Target, String)>) -> Vec<String> {
+        pages.into_iter().map(|(_, text)| text).collect()
+    }
+
+    #[test]
+    fn similarity() {
+        assert_eq!(
+            AlertSimilarity::IgnoreNumbers.normalize("CPU  at 91.5% on Host2"),
+            AlertSimilarity::IgnoreNumbers.normalize("cpu at 93.25% on host7")
+        );
+        assert_ne!(
+            AlertSimilarity::Identical.normalize("CPU at 91%"),
+            AlertSimilarity::Identical.normalize("CPU at 93%")
+        );
+    }
+
+    #[test]
+    fn repeats_are_suppressed_and_summarized() {
+        let options = AlertGateOptions::default();
+        let mut state = AlertGateState::default();
+
+        assert_eq!(
+            state.on_call(&options, time(0), &call("CPU at 91%")),
+            AlertGateDecision::Sent
+        );
+        assert_eq!(
+            state.on_call(&options, time(1), &call("CPU at 93%")),
+            AlertGateDecision::Suppressed { repeats: 1 }
+        );
+        assert_eq!(
+            state.on_call(&options, time(2), &call("CPU at 95%")),
+            AlertGateDecision::Suppressed { repeats: 2 }
+        );
+        assert_eq!(
+            state.on_call(&options, time(2), &call("Disk full")),
+            AlertGateDecision::Sent
+        );
+
+        assert!(state.expire(&options, time(4)).is_empty());
+        assert_eq!(
+            texts(state.expire(&options, time(5))),
+            vec!["2 repeats suppressed: CPU at 95%"]
+        );
+
+        // Nothing was suppressed for the other call
+        assert!(state.expire(&options, time(10)).is_empty());
+        assert_eq!(
+            state.on_call(&options, time(10), &call("CPU at 91%")),
+            AlertGateDecision::Sent
+        );
+    }
+
+    #[test]
+    fn different_recipients_are_not_repeats() {
+        let options = AlertGateOptions::default();
+        let mut state = AlertGateState::default();
+
+        let mut other = call("CPU at 91%");
+        other.recipients = vec!["m0def".to_string()];
+
+        assert_eq!(
+            state.on_call(&options, time(0), &call("CPU at 91%")),
+            AlertGateDecision::Sent
+        );
+        assert_eq!(
+            state.on_call(&options, time(0), &other),
+            AlertGateDecision::Sent
+        );
+    }
+
+    #[test]
+    fn flapping_alert_is_held_down() {
+        let options = AlertGateOptions::default();
+        let mut state = AlertGateState::default();
+        let firing = call("FIRING: link down");
+        let resolved = call("RESOLVED: link down");
+
+        let mut alert = |minute, alert_state, call| {
+            state.on_alert(&options, time(minute), "link", alert_state, call)
+        };
+        assert_eq!(
+            alert(0, AlertState::Firing, &firing),
+            AlertGateDecision::Sent
+        );
+        assert_eq!(
+            alert(1, AlertState::Resolved, &resolved),
+            AlertGateDecision::Sent
+        );
+        assert_eq!(
+            alert(2, AlertState::Firing, &firing),
+            AlertGateDecision::Sent
+        );
+        assert_eq!(
+            alert(3, AlertState::Resolved, &resolved),
+            AlertGateDecision::HeldDown { changes: 1 }
+        );
+        assert_eq!(
+            alert(4, AlertState::Firing, &firing),
+            AlertGateDecision::HeldDown { changes: 2 }
+        );
+        assert_eq!(
+            alert(5, AlertState::Firing, &firing),
+            AlertGateDecision::HeldDown { changes: 3 }
+        );
+
+        // Hold-down lasts until 10 minutes after the last change
+        assert!(texts(state.expire(&options, time(13))).is_empty());
+        assert_eq!(
+            texts(state.expire(&options, time(14))),
+            vec!["3 changes suppressed while flapping: FIRING: link down"]
+        );
+
+        // Back to normal
+        assert_eq!(
+            state.on_alert(&options, time(15), "link", AlertState::Resolved, &resolved),
+            AlertGateDecision::Sent
+        );
+    }
+
+    #[test]
+    fn slow_changes_are_not_flapping() {
+        let options = AlertGateOptions::default();
+        let mut state = AlertGateState::default();
+        let firing = call("FIRING: link down");
+        let resolved = call("RESOLVED: link down");
+
+        for i in 0..5 {
+            let (alert_state, call) = if i % 2 == 0 {
+                (AlertState::Firing, &firing)
+            } else {
+                (AlertState::Resolved, &resolved)
+            };
+            state.expire(&options, time(i * 6));
+            assert_eq!(
+                state.on_alert(&options, time(i * 6), "link", alert_state, call),
+                AlertGateDecision::Sent
+            );
+        }
+    }
+
+    #[test]
+    fn resolved_alerts_are_forgotten() {
+        let options = AlertGateOptions::default();
+        let mut state = AlertGateState::default();
+
+        state.on_alert(
+            &options,
+            time(0),
+            "link",
+            AlertState::Firing,
+            &call("FIRING"),
+        );
+        state.on_alert(
+            &options,
+            time(1),
+            "link",
+            AlertState::Resolved,
+            &call("RESOLVED"),
+        );
+        state.expire(&options, time(30));
+        assert_eq!(state, AlertGateState::default());
+    }
+
+    fn unreachable_gate() -> AlertGate<MemoryAlertGateStore> {
+        AlertGate::new(
+            unreachable_client(),
+            AlertGateOptions::default(),
+            MemoryAlertGateStore::default(),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn failed_summary_does_not_block_calls() {
+        let (url, requests) = crate::test_util::server(vec![(201, "")]).await;
+        // Summaries never start with the prefix, so are always refused by a strict client
+        let client = Client::new("m0nxn", "hunter2")
+            .with_api_url(url)
+            .with_sender_prefix(SenderPrefix::new("M0NXN", SenderPrefixMode::Strict));
+        let gate = AlertGate::new(
+            client,
+            AlertGateOptions::default(),
+            MemoryAlertGateStore::default(),
+        )
+        .unwrap();
+
+        {
+            let mut state = gate.state.lock().await;
+            let an_hour_ago = Utc::now() - TimeDelta::hours(1);
+            state.on_call(&gate.options, an_hour_ago, &call("M0NXN: CPU at 91%"));
+            state.on_call(&gate.options, an_hour_ago, &call("M0NXN: CPU at 93%"));
+        }
+
+        assert_eq!(
+            gate.new_call(&call("M0NXN: disk full")).await.unwrap(),
+            AlertGateDecision::Sent
+        );
+        assert!(gate.state.lock().await.summaries.is_empty());
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn failed_call_is_not_recorded() {
+        let gate = unreachable_gate();
+
+        assert!(gate.new_call(&call("CPU at 91%")).await.is_err());
+        assert_eq!(*gate.state.lock().await, AlertGateState::default());
+        assert_eq!(gate.store.load().unwrap(), AlertGateState::default());
+    }
+
+    #[tokio::test]
+    async fn unsent_summaries_are_kept() {
+        let gate = unreachable_gate();
+        let options = AlertGateOptions::default();
+
+        let mut state = AlertGateState::default();
+        state.on_call(&options, time(0), &call("CPU at 91%"));
+        state.on_call(&options, time(1), &call("CPU at 93%"));
+        state.on_call(&options, time(0), &call("Disk full"));
+        state.on_call(&options, time(1), &call("Disk full"));
+
+        assert!(gate.flush_locked(&mut state, time(10)).await.is_err());
+        assert!(state.repeats.is_empty());
+        assert_eq!(state.summaries.len(), 2);
+        assert_eq!(gate.store.load().unwrap(), state);
+
+        // Still queued after another failure, without being summarized again
+        assert!(gate.flush_locked(&mut state, time(20)).await.is_err());
+        assert_eq!(state.summaries.len(), 2);
+    }
+
+    #[test]
+    fn file_store() {
+        let path = std::env::temp_dir().join(format!("dapnet-alert-gate-{}", std::process::id()));
+        let store = FileAlertGateStore::new(&path);
+        assert_eq!(store.load().unwrap(), AlertGateState::default());
+
+        let options = AlertGateOptions::default();
+        let mut state = AlertGateState::default();
+        state.on_call(&options, time(0), &call("CPU at 91%"));
+        state.on_call(&options, time(1), &call("CPU at 93%"));
+        store.save(&state).unwrap();
+
+        let loaded = store.load();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), state);
+    }
+}
@@ -0,0 +1,195 @@
+use dapnet_api::{PasswordSource, Profile};
+use serde::Deserialize;
+use std::{collections::BTreeMap, net::SocketAddr, path::Path};
+
+/// Gateway configuration, loaded from a TOML file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct GatewayConfig {
+    /// Address to listen on.
+    #[serde(default = "default_listen")]
+    pub(crate) listen: SocketAddr,
+
+    /// Bearer token that requests must present, by default requests are not authenticated.
+    pub(crate) token: Option<PasswordSource>,
+
+    /// DAPNET user that pages are sent as, in the same format as a profile in the `dapnet`
+    /// config file.
+    pub(crate) dapnet: Profile,
+
+    /// Webhook routes, served at `/webhook/<name>`.
+    #[serde(default)]
+    pub(crate) routes: BTreeMap<String, Route>,
+
+    /// ntfy topics, published to at `/<name>`.
+    ///
+    /// Messages with urgent priority are sent as emergency calls.
+    #[serde(default)]
+    pub(crate) topics: BTreeMap<String, Target>,
+}
+
+fn default_listen() -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], 8080))
+}
+
+impl GatewayConfig {
+    pub(crate) fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let config = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+        Ok(toml::from_str(&config).map_err(|e| format!("invalid {}: {e}", path.display()))?)
+    }
+}
+
+/// Who a page is sent to.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Target {
+    #[serde(default)]
+    pub(crate) recipients: Vec<String>,
+
+    /// Transmitter groups, the profile's transmitter groups if empty.
+    #[serde(default)]
+    pub(crate) transmitter_groups: Vec<String>,
+
+    #[serde(default)]
+    pub(crate) emergency: bool,
+}
+
+/// How the body of a webhook is read.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "format", rename_all = "snake_case")]
+pub(crate) enum Format {
+    /// Prometheus Alertmanager, one page per alert.
+    Alertmanager,
+
+    /// Grafana alerting, one page per notification.
+    Grafana,
+
+    /// Any JSON, with the text and fields found by JSON pointers.
+    Json {
+        text: String,
+
+        #[serde(default)]
+        fields: BTreeMap<String, String>,
+    },
+
+    /// The body is the text of the page.
+    Text,
+}
+
+/// A webhook endpoint.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct Route {
+    #[serde(flatten)]
+    pub(crate) format: Format,
+
+    #[serde(flatten)]
+    pub(crate) target: Target,
+
+    /// Template for the text of pages, using the fields of the webhook and `text`.
+    pub(crate) template: Option<String>,
+
+    /// Rules that change the target of pages whose fields match, the first that matches is used.
+    #[serde(default)]
+    pub(crate) rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Rule {
+    /// Fields and the values they must have for the rule to match.
+    #[serde(rename = "match")]
+    pub(crate) matches: BTreeMap<String, String>,
+
+    pub(crate) recipients: Option<Vec<String>>,
+    pub(crate) transmitter_groups: Option<Vec<String>>,
+    pub(crate) emergency: Option<bool>,
+}
+
+impl Route {
+    /// Target for a page with the given fields.
+    pub(crate) fn target(&self, fields: &BTreeMap<String, String>) -> Target {
+        let rule = self.rules.iter().find(|rule| {
+            rule.matches
+                .iter()
+                .all(|(name, value)| fields.get(name) == Some(value))
+        });
+
+        match rule {
+            Some(rule) => Target {
+                recipients: rule
+                    .recipients
+                    .clone()
+                    .unwrap_or_else(|| self.target.recipients.clone()),
+                transmitter_groups: rule
+                    .transmitter_groups
+                    .clone()
+                    .unwrap_or_else(|| self.target.transmitter_groups.clone()),
+                emergency: rule.emergency.unwrap_or(self.target.emergency),
+            },
+            None => self.target.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_and_route() {
+        let config: GatewayConfig = toml::from_str(
+            r#"
+            listen = "0.0.0.0:9000"
+
+            [dapnet]
+            username = "m0nxn"
+            password = { env = "DAPNET_PASSWORD" }
+            transmitter_groups = ["uk-all"]
+
+            [routes.ops]
+            format = "alertmanager"
+            recipients = ["m0nxn"]
+
+            [[routes.ops.rules]]
+            match = { severity = "critical" }
+            recipients = ["m0nxn", "m0abc"]
+            emergency = true
+
+            [routes.backup]
+            format = "json"
+            text = "/message"
+            fields = { host = "/host" }
+            recipients = ["m0abc"]
+            transmitter_groups = ["uk-london"]
+
+            [topics.doorbell]
+            recipients = ["m0nxn"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.listen, "0.0.0.0:9000".parse().unwrap());
+        assert_eq!(config.topics["doorbell"].recipients, vec!["m0nxn"]);
+        assert_eq!(
+            config.routes["backup"].format,
+            Format::Json {
+                text: "/message".to_string(),
+                fields: BTreeMap::from([("host".to_string(), "/host".to_string())]),
+            }
+        );
+
+        let ops = &config.routes["ops"];
+        assert_eq!(ops.format, Format::Alertmanager);
+        let critical = BTreeMap::from([("severity".to_string(), "critical".to_string())]);
+        assert_eq!(
+            ops.target(&critical),
+            Target {
+                recipients: vec!["m0nxn".to_string(), "m0abc".to_string()],
+                transmitter_groups: Vec::new(),
+                emergency: true,
+            }
+        );
+        assert_eq!(ops.target(&BTreeMap::new()), ops.target);
+    }
+}
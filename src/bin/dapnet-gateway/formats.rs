@@ -0,0 +1,210 @@
+use crate::config::Format;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Something to page about, read from a webhook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Alert {
+    /// Text used when the route has no template.
+    pub(crate) text: String,
+
+    /// Values used by routing rules and templates.
+    pub(crate) fields: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AlertmanagerPayload {
+    alerts: Vec<AlertmanagerAlert>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AlertmanagerAlert {
+    status: String,
+    #[serde(default)]
+    labels: BTreeMap<String, String>,
+    #[serde(default)]
+    annotations: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GrafanaPayload {
+    status: String,
+    title: Option<String>,
+    message: Option<String>,
+    #[serde(default)]
+    common_labels: BTreeMap<String, String>,
+    #[serde(default)]
+    common_annotations: BTreeMap<String, String>,
+}
+
+impl Format {
+    /// Reads the alerts in a webhook body.
+    pub(crate) fn parse(&self, body: &[u8]) -> Result<Vec<Alert>, String> {
+        match self {
+            Self::Alertmanager => {
+                let payload: AlertmanagerPayload = serde_json::from_slice(body)
+                    .map_err(|e| format!("invalid Alertmanager webhook: {e}"))?;
+                Ok(payload
+                    .alerts
+                    .into_iter()
+                    .map(|alert| {
+                        let summary = alert
+                            .annotations
+                            .get("summary")
+                            .or(alert.labels.get("alertname"))
+                            .cloned()
+                            .unwrap_or_default();
+                        let text = format!("{}: {summary}", alert.status.to_uppercase());
+
+                        let mut fields = alert.annotations;
+                        fields.extend(alert.labels);
+                        fields.insert("status".to_string(), alert.status);
+                        Alert { text, fields }
+                    })
+                    .collect())
+            }
+            Self::Grafana => {
+                let payload: GrafanaPayload = serde_json::from_slice(body)
+                    .map_err(|e| format!("invalid Grafana webhook: {e}"))?;
+                let text = payload
+                    .title
+                    .clone()
+                    .or(payload.message.clone())
+                    .unwrap_or_else(|| payload.status.to_uppercase());
+
+                let mut fields = payload.common_annotations;
+                fields.extend(payload.common_labels);
+                fields.insert("status".to_string(), payload.status);
+                fields.extend(payload.title.map(|title| ("title".to_string(), title)));
+                fields.extend(
+                    payload
+                        .message
+                        .map(|message| ("message".to_string(), message)),
+                );
+                Ok(vec![Alert { text, fields }])
+            }
+            Self::Json { text, fields } => {
+                let payload: Value =
+                    serde_json::from_slice(body).map_err(|e| format!("invalid JSON: {e}"))?;
+                let text = payload
+                    .pointer(text)
+                    .map(value_to_string)
+                    .ok_or_else(|| format!("no text at {text}"))?;
+                let fields = fields
+                    .iter()
+                    .filter_map(|(name, pointer)| {
+                        payload
+                            .pointer(pointer)
+                            .map(|value| (name.clone(), value_to_string(value)))
+                    })
+                    .collect();
+                Ok(vec![Alert { text, fields }])
+            }
+            Self::Text => {
+                let text = std::str::from_utf8(body)
+                    .map_err(|_| "text is not UTF-8".to_string())?
+                    .trim()
+                    .to_string();
+                Ok(vec![Alert {
+                    text,
+                    fields: BTreeMap::new(),
+                }])
+            }
+        }
+    }
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn alertmanager() {
+        let body = json!({
+            "version": "4",
+            "status": "firing",
+            "receiver": "dapnet",
+            "alerts": [
+                {
+                    "status": "firing",
+                    "labels": {"alertname": "HighCPU", "severity": "critical"},
+                    "annotations": {"summary": "CPU at 98% on db1"},
+                },
+                {
+                    "status": "resolved",
+                    "labels": {"alertname": "DiskFull"},
+                },
+            ],
+        });
+
+        let alerts = Format::Alertmanager
+            .parse(body.to_string().as_bytes())
+            .unwrap();
+        assert_eq!(alerts.len(), 2);
+        assert_eq!(alerts[0].text, "FIRING: CPU at 98% on db1");
+        assert_eq!(alerts[0].fields["severity"], "critical");
+        assert_eq!(alerts[0].fields["status"], "firing");
+        assert_eq!(alerts[1].text, "RESOLVED: DiskFull");
+    }
+
+    #[test]
+    fn grafana() {
+        let body = json!({
+            "receiver": "dapnet",
+            "status": "firing",
+            "alerts": [],
+            "commonLabels": {"alertname": "HighCPU", "team": "ops"},
+            "title": "[FIRING:1] HighCPU",
+            "message": "CPU is high",
+        });
+
+        let alerts = Format::Grafana.parse(body.to_string().as_bytes()).unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].text, "[FIRING:1] HighCPU");
+        assert_eq!(alerts[0].fields["team"], "ops");
+        assert_eq!(alerts[0].fields["message"], "CPU is high");
+    }
+
+    #[test]
+    fn json_pointers() {
+        let format = Format::Json {
+            text: "/event/message".to_string(),
+            fields: BTreeMap::from([
+                ("host".to_string(), "/event/host".to_string()),
+                ("code".to_string(), "/code".to_string()),
+                ("missing".to_string(), "/nothing".to_string()),
+            ]),
+        };
+        let body = json!({"event": {"message": "backup failed", "host": "nas"}, "code": 3});
+
+        let alerts = format.parse(body.to_string().as_bytes()).unwrap();
+        assert_eq!(alerts[0].text, "backup failed");
+        assert_eq!(
+            alerts[0].fields,
+            BTreeMap::from([
+                ("host".to_string(), "nas".to_string()),
+                ("code".to_string(), "3".to_string()),
+            ])
+        );
+
+        assert!(format.parse(b"{}").is_err());
+        assert!(format.parse(b"not json").is_err());
+    }
+
+    #[test]
+    fn text() {
+        let alerts = Format::Text.parse(b"  door opened\n").unwrap();
+        assert_eq!(alerts[0].text, "door opened");
+    }
+}
@@ -0,0 +1,488 @@
+use crate::config::{GatewayConfig, Route, Target};
+use axum::{
+    http::{HeaderMap, StatusCode, header::AUTHORIZATION},
+    response::{IntoResponse, Response},
+};
+use dapnet_api::{
+    Client, MessageSanitizationOptions, MessageTemplate, OutgoingCall, OutgoingCallBuilder, Secret,
+    TemplateVariables, sanitize_message,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug)]
+pub(crate) enum GatewayError {
+    Unauthorized,
+    NotFound(String),
+    BadRequest(String),
+    Upstream(dapnet_api::Error),
+}
+
+impl IntoResponse for GatewayError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Unauthorized => (StatusCode::UNAUTHORIZED, "unauthorized".to_string()),
+            Self::NotFound(name) => (StatusCode::NOT_FOUND, format!("{name} not found")),
+            Self::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+            Self::Upstream(e) => (StatusCode::BAD_GATEWAY, e.to_string()),
+        }
+        .into_response()
+    }
+}
+
+/// Outcome of sending one of the calls for a request.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub(crate) enum CallStatus {
+    Sent,
+    Failed { error: String },
+}
+
+/// Statuses of the calls for a request, an error if none were sent.
+///
+/// Only a request that sent nothing fails, so that retrying it cannot send any call twice.
+fn statuses(results: Vec<Result<(), dapnet_api::Error>>) -> Result<Vec<CallStatus>, GatewayError> {
+    if !results.is_empty() && results.iter().all(Result::is_err) {
+        let error = results.into_iter().find_map(Result::err).unwrap();
+        return Err(GatewayError::Upstream(error));
+    }
+
+    Ok(results
+        .into_iter()
+        .map(|result| match result {
+            Ok(()) => CallStatus::Sent,
+            Err(e) => CallStatus::Failed {
+                error: e.to_string(),
+            },
+        })
+        .collect())
+}
+
+/// Turns webhooks into calls and sends them.
+pub(crate) struct Gateway {
+    client: Client,
+    token: Option<Secret>,
+    transmitter_groups: Vec<String>,
+    sanitization: MessageSanitizationOptions,
+    routes: BTreeMap<String, (Route, Option<MessageTemplate>)>,
+    topics: BTreeMap<String, Target>,
+}
+
+/// Checks that calls to a target can be built.
+fn check_target(
+    name: &str,
+    target: &Target,
+    default_groups: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    if target.recipients.is_empty() {
+        Err(format!("{name} has no recipients").into())
+    } else if target.transmitter_groups.is_empty() && default_groups.is_empty() {
+        Err(format!("{name} has no transmitter groups and there are no defaults").into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Reads an ntfy message priority, from 1 (min) to 5 (max or urgent).
+fn ntfy_priority(priority: &str) -> Option<u8> {
+    match priority.trim().to_lowercase().as_str() {
+        "1" | "min" => Some(1),
+        "2" | "low" => Some(2),
+        "3" | "default" => Some(3),
+        "4" | "high" => Some(4),
+        "5" | "max" | "urgent" => Some(5),
+        _ => None,
+    }
+}
+
+impl Gateway {
+    pub(crate) fn new(config: &GatewayConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut routes = BTreeMap::new();
+        for (name, route) in &config.routes {
+            check_target(
+                &format!("route {name}"),
+                &route.target,
+                &config.dapnet.transmitter_groups,
+            )?;
+            let template = route
+                .template
+                .as_deref()
+                .map(MessageTemplate::parse)
+                .transpose()
+                .map_err(|e| format!("route {name}: {e}"))?;
+            routes.insert(name.clone(), (route.clone(), template));
+        }
+        for (name, topic) in &config.topics {
+            check_target(
+                &format!("topic {name}"),
+                topic,
+                &config.dapnet.transmitter_groups,
+            )?;
+        }
+
+        Ok(Self {
+            client: config.dapnet.client()?,
+            token: config
+                .token
+                .as_ref()
+                .map(|token| token.read())
+                .transpose()?,
+            transmitter_groups: config.dapnet.transmitter_groups.clone(),
+            sanitization: config.dapnet.sanitization.options(),
+            routes,
+            topics: config.topics.clone(),
+        })
+    }
+
+    /// Checks the bearer token of a request, if one is required.
+    pub(crate) fn authorize(&self, headers: &HeaderMap) -> Result<(), GatewayError> {
+        let Some(token) = &self.token else {
+            return Ok(());
+        };
+
+        let presented = headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        if presented == Some(token.expose_secret()) {
+            Ok(())
+        } else {
+            Err(GatewayError::Unauthorized)
+        }
+    }
+
+    /// Reads the calls to send for a webhook to a route.
+    ///
+    /// Query parameters are added to the fields of each alert, without replacing any.
+    pub(crate) fn webhook_calls(
+        &self,
+        route: &str,
+        body: &[u8],
+        query: &BTreeMap<String, String>,
+    ) -> Result<Vec<OutgoingCall>, GatewayError> {
+        let (route, template) = self
+            .routes
+            .get(route)
+            .ok_or_else(|| GatewayError::NotFound(format!("route {route}")))?;
+
+        let alerts = route.format.parse(body).map_err(GatewayError::BadRequest)?;
+        alerts
+            .into_iter()
+            .map(|mut alert| {
+                for (name, value) in query {
+                    alert
+                        .fields
+                        .entry(name.clone())
+                        .or_insert_with(|| value.clone());
+                }
+
+                let text = match template {
+                    Some(template) => {
+                        let mut variables = TemplateVariables::new().with("text", alert.text);
+                        for (name, value) in &alert.fields {
+                            variables.insert(name, value.as_str());
+                        }
+                        template
+                            .render(&variables)
+                            .map_err(|e| GatewayError::BadRequest(e.to_string()))?
+                    }
+                    None => alert.text,
+                };
+
+                let target = route.target(&alert.fields);
+                self.call(
+                    text,
+                    target.recipients,
+                    target.transmitter_groups,
+                    target.emergency,
+                )
+            })
+            .collect()
+    }
+
+    /// Reads the call to send for a message published to an ntfy topic.
+    ///
+    /// The title and priority are read from headers (`Title`, `X-Title`, `Priority`,
+    /// `X-Priority` or their short forms) or query parameters, as ntfy does.
+    /// The message is the body, or the `message` query parameter if the body is empty.
+    pub(crate) fn ntfy_call(
+        &self,
+        topic: &str,
+        headers: &HeaderMap,
+        query: &BTreeMap<String, String>,
+        body: &[u8],
+    ) -> Result<OutgoingCall, GatewayError> {
+        let target = self
+            .topics
+            .get(topic)
+            .ok_or_else(|| GatewayError::NotFound(format!("topic {topic}")))?;
+
+        let parameter = |names: &[&str]| {
+            names.iter().find_map(|name| {
+                headers
+                    .get(*name)
+                    .and_then(|value| value.to_str().ok())
+                    .or_else(|| query.get(*name).map(String::as_str))
+                    .filter(|value| !value.is_empty())
+            })
+        };
+
+        let message = std::str::from_utf8(body)
+            .map_err(|_| GatewayError::BadRequest("message is not UTF-8".to_string()))?
+            .trim();
+        let message = match (message, parameter(&["x-message", "message", "m"])) {
+            ("", Some(message)) => message,
+            (message, _) => message,
+        };
+        let text = match parameter(&["x-title", "title", "ti", "t"]) {
+            Some(title) if message.is_empty() => title.to_string(),
+            Some(title) => format!("{title}: {message}"),
+            None => message.to_string(),
+        };
+
+        let priority = match parameter(&["x-priority", "priority", "prio", "p"]) {
+            Some(priority) => ntfy_priority(priority)
+                .ok_or_else(|| GatewayError::BadRequest(format!("invalid priority {priority}")))?,
+            None => 3,
+        };
+
+        self.call(
+            text,
+            target.recipients.clone(),
+            target.transmitter_groups.clone(),
+            target.emergency || priority == 5,
+        )
+    }
+
+    /// Builds a sanitized call, using the default transmitter groups if none are given.
+    pub(crate) fn call(
+        &self,
+        text: String,
+        recipients: Vec<String>,
+        transmitter_groups: Vec<String>,
+        emergency: bool,
+    ) -> Result<OutgoingCall, GatewayError> {
+        let transmitter_groups = if transmitter_groups.is_empty() {
+            self.transmitter_groups.clone()
+        } else {
+            transmitter_groups
+        };
+
+        OutgoingCallBuilder::default()
+            .text(sanitize_message(text, &self.sanitization))
+            .recipients(recipients)
+            .transmitter_groups(transmitter_groups)
+            .emergency(emergency)
+            .build()
+            .map_err(|e| GatewayError::BadRequest(e.to_string()))
+    }
+
+    /// Sends every call, carrying on after a failure, see [`statuses`].
+    pub(crate) async fn send(
+        &self,
+        calls: &[OutgoingCall],
+    ) -> Result<Vec<CallStatus>, GatewayError> {
+        let mut results = Vec::new();
+        for call in calls {
+            results.push(self.client.new_call(call).await);
+        }
+        statuses(results)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    pub(crate) fn gateway(config: &str) -> Gateway {
+        let config: GatewayConfig = toml::from_str(&format!(
+            r#"
+            {config}
+
+            [dapnet]
+            username = "m0nxn"
+            password = {{ command = ["echo", "hunter2"] }}
+            transmitter_groups = ["uk-all"]
+
+            [dapnet.sanitization]
+            non_ascii = "transliterate"
+            "#
+        ))
+        .unwrap();
+        Gateway::new(&config).unwrap()
+    }
+
+    #[test]
+    fn routes_and_renders() {
+        let gateway = gateway(
+            r#"
+            [routes.ops]
+            format = "alertmanager"
+            recipients = ["m0nxn"]
+            template = "{{status|upper}} {{instance}}: {{text}}"
+
+            [[routes.ops.rules]]
+            match = { severity = "critical" }
+            recipients = ["m0nxn", "m0abc"]
+            transmitter_groups = ["uk-london"]
+            emergency = true
+            "#,
+        );
+        let body = serde_json::json!({
+            "alerts": [
+                {
+                    "status": "firing",
+                    "labels": {"alertname": "HighCPU", "severity": "critical", "instance": "db1"},
+                },
+                {
+                    "status": "firing",
+                    "labels": {"alertname": "HighCPU", "instance": "db2"},
+                },
+            ],
+        })
+        .to_string();
+
+        let calls = gateway
+            .webhook_calls("ops", body.as_bytes(), &BTreeMap::new())
+            .unwrap();
+        let json = serde_json::to_value(&calls).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {
+                    "text": "FIRING db1: FIRING: HighCPU",
+                    "callSignNames": ["m0nxn", "m0abc"],
+                    "transmitterGroupNames": ["uk-london"],
+                    "emergency": true,
+                },
+                {
+                    "text": "FIRING db2: FIRING: HighCPU",
+                    "callSignNames": ["m0nxn"],
+                    "transmitterGroupNames": ["uk-all"],
+                    "emergency": false,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn query_fields_and_sanitization() {
+        let gateway = gateway(
+            r#"
+            [routes.plain]
+            format = "text"
+            recipients = ["m0nxn"]
+
+            [[routes.plain.rules]]
+            match = { urgent = "yes" }
+            emergency = true
+            "#,
+        );
+        let query = BTreeMap::from([("urgent".to_string(), "yes".to_string())]);
+
+        let calls = gateway
+            .webhook_calls("plain", "Tür offen".as_bytes(), &query)
+            .unwrap();
+        let json = serde_json::to_value(&calls).unwrap();
+        assert_eq!(json[0]["text"], "Tuer offen");
+        assert_eq!(json[0]["emergency"], true);
+    }
+
+    #[test]
+    fn errors() {
+        let gateway = gateway(
+            r#"
+            [routes.plain]
+            format = "text"
+            recipients = ["m0nxn"]
+            "#,
+        );
+
+        assert!(matches!(
+            gateway.webhook_calls("other", b"text", &BTreeMap::new()),
+            Err(GatewayError::NotFound(_))
+        ));
+        assert!(matches!(
+            gateway.webhook_calls("plain", &[0xff], &BTreeMap::new()),
+            Err(GatewayError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn ntfy() {
+        let gateway = gateway(
+            r#"
+            [topics.doorbell]
+            recipients = ["m0nxn"]
+            "#,
+        );
+        let mut headers = HeaderMap::new();
+        let mut query = BTreeMap::new();
+
+        let call = |headers: &HeaderMap, query: &BTreeMap<String, String>, body: &[u8]| {
+            serde_json::to_value(gateway.ntfy_call("doorbell", headers, query, body).unwrap())
+                .unwrap()
+        };
+
+        let json = call(&headers, &query, b"someone at the door");
+        assert_eq!(json["text"], "someone at the door");
+        assert_eq!(json["callSignNames"], serde_json::json!(["m0nxn"]));
+        assert_eq!(json["emergency"], false);
+
+        headers.insert("Title", "Front door".parse().unwrap());
+        headers.insert("Priority", "urgent".parse().unwrap());
+        let json = call(&headers, &query, b"someone at the door");
+        assert_eq!(json["text"], "Front door: someone at the door");
+        assert_eq!(json["emergency"], true);
+
+        let headers = HeaderMap::new();
+        query.insert("message".to_string(), "parcel delivered".to_string());
+        query.insert("p".to_string(), "4".to_string());
+        let json = call(&headers, &query, b"");
+        assert_eq!(json["text"], "parcel delivered");
+        assert_eq!(json["emergency"], false);
+
+        query.insert("p".to_string(), "loud".to_string());
+        assert!(matches!(
+            gateway.ntfy_call("doorbell", &headers, &query, b"x"),
+            Err(GatewayError::BadRequest(_))
+        ));
+        assert!(matches!(
+            gateway.ntfy_call("other", &headers, &BTreeMap::new(), b"x"),
+            Err(GatewayError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn partial_success() {
+        let failed = || Err(dapnet_api::Error::NotFound("callsign m0abc".to_string()));
+
+        assert_eq!(
+            statuses(vec![Ok(()), failed()]).unwrap(),
+            vec![
+                CallStatus::Sent,
+                CallStatus::Failed {
+                    error: dapnet_api::Error::NotFound("callsign m0abc".to_string()).to_string()
+                }
+            ]
+        );
+        assert!(matches!(
+            statuses(vec![failed(), failed()]),
+            Err(GatewayError::Upstream(_))
+        ));
+        assert!(statuses(Vec::new()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn authorization() {
+        let mut headers = HeaderMap::new();
+        assert!(gateway("").authorize(&headers).is_ok());
+
+        let gateway = gateway(r#"token = { command = ["echo", "s3cret"] }"#);
+        assert!(gateway.authorize(&headers).is_err());
+        headers.insert(AUTHORIZATION, "Bearer wrong".parse().unwrap());
+        assert!(gateway.authorize(&headers).is_err());
+        headers.insert(AUTHORIZATION, "Bearer s3cret".parse().unwrap());
+        assert!(gateway.authorize(&headers).is_ok());
+    }
+}
@@ -0,0 +1,145 @@
+//! HTTP gateway that turns webhooks from alerting tools into DAPNET calls.
+
+mod config;
+mod formats;
+mod gateway;
+
+use axum::{
+    Json, Router,
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    routing::post,
+};
+use clap::Parser;
+use config::GatewayConfig;
+use gateway::{CallStatus, Gateway, GatewayError};
+use serde::Serialize;
+use std::{collections::BTreeMap, net::SocketAddr, path::PathBuf, process::ExitCode, sync::Arc};
+
+/// HTTP gateway that sends DAPNET calls for webhooks from alerting tools.
+///
+/// Webhooks are accepted at /webhook/ROUTE for each route in the config file, and messages
+/// are published with the ntfy API at /TOPIC for each topic.
+#[derive(Debug, Parser)]
+#[command(name = "dapnet-gateway", version)]
+struct Cli {
+    /// Gateway config file
+    #[arg(long, short, env = "DAPNET_GATEWAY_CONFIG")]
+    config: PathBuf,
+
+    /// Address to listen on [default: from the config file]
+    #[arg(long, short)]
+    listen: Option<SocketAddr>,
+}
+
+/// Response to a webhook, with the status of the call for each alert.
+#[derive(Debug, Serialize)]
+struct Sent {
+    sent: usize,
+    failed: usize,
+    results: Vec<CallStatus>,
+}
+
+/// Response to an ntfy publish, as ntfy would send.
+#[derive(Debug, Serialize)]
+struct Published {
+    id: String,
+    time: i64,
+    event: &'static str,
+    topic: String,
+    message: String,
+}
+
+fn router(gateway: Arc<Gateway>) -> Router {
+    Router::new()
+        .route("/webhook/{route}", post(webhook))
+        .route("/{topic}", post(publish).put(publish))
+        .with_state(gateway)
+}
+
+async fn webhook(
+    State(gateway): State<Arc<Gateway>>,
+    Path(route): Path<String>,
+    Query(query): Query<BTreeMap<String, String>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<(StatusCode, Json<Sent>), GatewayError> {
+    gateway.authorize(&headers)?;
+    let calls = gateway.webhook_calls(&route, &body, &query)?;
+    let results = gateway.send(&calls).await?;
+
+    let sent = results
+        .iter()
+        .filter(|status| **status == CallStatus::Sent)
+        .count();
+    let failed = results.len() - sent;
+    // Partly sent requests are reported as a success, so they aren't retried and sent twice
+    let status = if failed == 0 {
+        StatusCode::OK
+    } else {
+        StatusCode::MULTI_STATUS
+    };
+    Ok((
+        status,
+        Json(Sent {
+            sent,
+            failed,
+            results,
+        }),
+    ))
+}
+
+async fn publish(
+    State(gateway): State<Arc<Gateway>>,
+    Path(topic): Path<String>,
+    Query(query): Query<BTreeMap<String, String>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<Published>, GatewayError> {
+    gateway.authorize(&headers)?;
+    let call = gateway.ntfy_call(&topic, &headers, &query, &body)?;
+    gateway.send(std::slice::from_ref(&call)).await?;
+
+    let now = chrono::Utc::now();
+    Ok(Json(Published {
+        id: format!("{:x}", now.timestamp_nanos_opt().unwrap_or_default()),
+        time: now.timestamp(),
+        event: "message",
+        topic,
+        message: String::from_utf8_lossy(&body).trim().to_string(),
+    }))
+}
+
+async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let config = GatewayConfig::load(&cli.config)?;
+    let listen = cli.listen.unwrap_or(config.listen);
+    let gateway = Arc::new(Gateway::new(&config)?);
+
+    let listener = tokio::net::TcpListener::bind(listen).await?;
+    eprintln!("Listening on {}", listener.local_addr()?);
+    axum::serve(listener, router(gateway)).await?;
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(Cli::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn cli() {
+        Cli::command().debug_assert();
+    }
+}
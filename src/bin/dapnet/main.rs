@@ -0,0 +1,676 @@
+//! Command line client for the DAPNET API.
+
+mod output;
+mod profile;
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use dapnet_api::{
+    CallValidationReport, Client, MessageSplitOptionsBuilder, NonAsciiHandling,
+    NumericRecipientPolicy, OutgoingNewsBuilder, PagerCharset, Profile, SanitizationDefaults,
+    SenderPrefix, SenderPrefixMode, TransmitterStatus, sanitize_message,
+};
+use output::{Format, Record};
+use serde::Serialize;
+use std::{path::PathBuf, process::ExitCode, time::Duration};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Command line client for the DAPNET amateur paging network.
+///
+/// Credentials are read from the DAPNET_USERNAME and DAPNET_PASSWORD environment variables or
+/// from a profile in the config file.
+#[derive(Debug, Parser)]
+#[command(name = "dapnet", version)]
+struct Cli {
+    /// Profile from the config file to use [default: DAPNET_USERNAME and DAPNET_PASSWORD if set,
+    /// otherwise the default profile]
+    #[arg(long, short, global = true, env = "DAPNET_PROFILE")]
+    profile: Option<String>,
+
+    /// Config file [default: ~/.config/dapnet/config.toml]
+    #[arg(long, global = true, env = "DAPNET_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Output format
+    #[arg(long, short, global = true, value_enum, default_value_t = Format::Table)]
+    output: Format,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Send a call
+    Send(SendArgs),
+
+    /// Send or list news
+    #[command(subcommand)]
+    News(NewsCommand),
+
+    /// List calls sent by a user
+    Calls {
+        /// User that sent the calls [default: the current user]
+        #[arg(long)]
+        owner: Option<String>,
+
+        /// Only show calls sent within this long (e.g. "30m", "1h", "2d") or since this time
+        /// (RFC 3339)
+        #[arg(long, value_parser = parse_since)]
+        since: Option<DateTime<Utc>>,
+    },
+
+    /// Show network statistics
+    Stats,
+
+    /// List or show callsigns
+    #[command(subcommand)]
+    Callsigns(ListOrGet),
+
+    /// List or show transmitters
+    #[command(subcommand)]
+    Transmitters(TransmittersCommand),
+
+    /// List or show transmitter groups
+    #[command(subcommand)]
+    Groups(ListOrGet),
+
+    /// List or show nodes
+    #[command(subcommand)]
+    Nodes(ListOrGet),
+
+    /// List or show rubrics
+    #[command(subcommand)]
+    Rubrics(ListOrGet),
+}
+
+#[derive(Debug, Subcommand)]
+enum ListOrGet {
+    /// List all
+    List,
+
+    /// Show one by name
+    Get { name: String },
+}
+
+#[derive(Debug, Subcommand)]
+enum TransmittersCommand {
+    /// List all transmitters
+    List {
+        /// Only list transmitters with this status
+        #[arg(long, value_enum)]
+        status: Option<StatusFilter>,
+    },
+
+    /// Show one transmitter by name
+    Get { name: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum StatusFilter {
+    Online,
+    Offline,
+    Error,
+}
+
+impl From<StatusFilter> for TransmitterStatus {
+    fn from(status: StatusFilter) -> Self {
+        match status {
+            StatusFilter::Online => Self::Online,
+            StatusFilter::Offline => Self::Offline,
+            StatusFilter::Error => Self::Error,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum NewsCommand {
+    /// Send news to a rubric
+    Send {
+        /// Rubric to send to
+        #[arg(long)]
+        rubric: String,
+
+        /// News position (1-10)
+        #[arg(long, default_value_t = 1)]
+        number: i8,
+
+        #[command(flatten)]
+        message: MessageArgs,
+    },
+
+    /// List the news in a rubric
+    List { rubric: String },
+}
+
+#[derive(Debug, Args)]
+struct SendArgs {
+    /// Recipient callsigns
+    #[arg(long = "to", short = 't', required = true, value_delimiter = ',')]
+    recipients: Vec<String>,
+
+    /// Transmitter groups to send the call with [default: the groups of the profile]
+    #[arg(long = "group", short = 'g', value_delimiter = ',')]
+    transmitter_groups: Vec<String>,
+
+    /// Send with high priority
+    #[arg(long)]
+    emergency: bool,
+
+    /// Send a converted copy of the text to recipients with numeric pagers instead of refusing
+    /// to send to them
+    #[arg(long)]
+    convert_numeric: bool,
+
+    /// Split text that is too long into several calls instead of truncating it
+    #[arg(long)]
+    split: bool,
+
+    /// Check the call against the network and show any problems instead of sending it
+    #[arg(long, conflicts_with = "split")]
+    dry_run: bool,
+
+    #[command(flatten)]
+    message: MessageArgs,
+}
+
+#[derive(Debug, Args)]
+struct MessageArgs {
+    /// Do not start the message with "USERNAME: "
+    #[arg(long)]
+    no_prefix: bool,
+
+    #[command(flatten)]
+    sanitization: SanitizationArgs,
+
+    /// Message text
+    text: String,
+}
+
+/// Flags that map to `MessageSanitizationOptions`, overriding the defaults of the profile.
+#[derive(Debug, Args)]
+struct SanitizationArgs {
+    /// Send the text exactly as given, without sanitization
+    #[arg(long)]
+    raw: bool,
+
+    /// Maximum message length in characters [default: 80]
+    #[arg(long)]
+    max_length: Option<usize>,
+
+    /// Text to end truncated messages with [default: ...]
+    #[arg(long)]
+    ellipses: Option<String>,
+
+    /// What to do with non-ASCII characters [default: replace]
+    #[arg(long, value_enum)]
+    non_ascii: Option<NonAsciiPolicy>,
+
+    /// Replacement for non-ASCII characters with the "replace" and "transliterate" policies
+    /// [default: ?]
+    #[arg(long)]
+    replacement: Option<char>,
+
+    /// Character set of the receiving pagers [default: ascii]
+    #[arg(long, value_enum)]
+    charset: Option<Charset>,
+
+    /// Shorten messages that are too long using the built in abbreviations before truncating
+    #[arg(long)]
+    compact: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum NonAsciiPolicy {
+    Keep,
+    Remove,
+    Replace,
+    Transliterate,
+}
+
+impl From<NonAsciiPolicy> for NonAsciiHandling {
+    fn from(policy: NonAsciiPolicy) -> Self {
+        match policy {
+            NonAsciiPolicy::Keep => Self::DoNothing,
+            NonAsciiPolicy::Remove => Self::Remove,
+            NonAsciiPolicy::Replace => Self::Replace,
+            NonAsciiPolicy::Transliterate => Self::Transliterate,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Charset {
+    Ascii,
+    Din66003,
+}
+
+impl From<Charset> for PagerCharset {
+    fn from(charset: Charset) -> Self {
+        match charset {
+            Charset::Ascii => Self::Ascii,
+            Charset::Din66003 => Self::Din66003,
+        }
+    }
+}
+
+impl SanitizationArgs {
+    /// Applies the flags that were given on top of `defaults`.
+    fn merge(&self, defaults: &SanitizationDefaults) -> SanitizationDefaults {
+        SanitizationDefaults {
+            max_length: self.max_length.or(defaults.max_length),
+            ellipses: self.ellipses.clone().or(defaults.ellipses.clone()),
+            non_ascii: self.non_ascii.map(Into::into).or(defaults.non_ascii),
+            replacement: self.replacement.or(defaults.replacement),
+            charset: self.charset.map(Into::into).or(defaults.charset),
+            compact: self.compact || defaults.compact,
+        }
+    }
+
+    /// Sanitizes text, leaving room for `prefix` if it will be inserted, when `split` is set the
+    /// text is not truncated.
+    fn apply(
+        &self,
+        text: &str,
+        defaults: &SanitizationDefaults,
+        prefix: Option<&SenderPrefix>,
+        split: bool,
+    ) -> String {
+        if self.raw {
+            return text.to_string();
+        }
+
+        let mut sanitization = self.merge(defaults);
+        if split {
+            sanitization.max_length = Some(usize::MAX);
+        } else if let Some(prefix) = prefix.filter(|prefix| !prefix.is_present(text)) {
+            // Otherwise inserting the prefix would truncate the text a second time
+            let max_length = sanitization.max_length.unwrap_or(80);
+            sanitization.max_length =
+                Some(max_length.saturating_sub(prefix.prefix().chars().count()));
+        }
+        sanitize_message(text.to_string(), &sanitization.options())
+    }
+}
+
+/// Parses a duration such as "90s", "30m", "1h", "2d" or "1w" as a time that long ago, or an
+/// RFC 3339 time.
+fn parse_since(s: &str) -> std::result::Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+        return Ok(time.with_timezone(&Utc));
+    }
+
+    let error = || format!("\"{s}\" is not a duration (e.g. \"1h\") or an RFC 3339 time");
+    let unit_start = s.find(|c: char| !c.is_ascii_digit()).ok_or_else(error)?;
+    let (value, unit) = s.split_at(unit_start);
+    let value: i64 = value.parse().map_err(|_| error())?;
+
+    let duration = match unit {
+        "s" => ChronoDuration::try_seconds(value),
+        "m" => ChronoDuration::try_minutes(value),
+        "h" => ChronoDuration::try_hours(value),
+        "d" => ChronoDuration::try_days(value),
+        "w" => ChronoDuration::try_weeks(value),
+        _ => None,
+    }
+    .ok_or_else(error)?;
+
+    Ok(Utc::now() - duration)
+}
+
+/// A problem found by `send --dry-run`.
+#[derive(Debug, Serialize)]
+struct Issue {
+    severity: &'static str,
+    issue: String,
+}
+
+impl Record for Issue {
+    const HEADERS: &'static [&'static str] = &["severity", "issue"];
+
+    fn row(&self) -> Vec<String> {
+        vec![self.severity.to_string(), self.issue.clone()]
+    }
+}
+
+fn issues(report: &CallValidationReport) -> Vec<Issue> {
+    let issue = |severity| {
+        move |issue: &dapnet_api::CallIssue| Issue {
+            severity,
+            issue: issue.to_string(),
+        }
+    };
+    report
+        .errors
+        .iter()
+        .map(issue("error"))
+        .chain(report.warnings.iter().map(issue("warning")))
+        .collect()
+}
+
+/// Writes records to stdout.
+fn print_list<R: Record>(format: Format, records: &[R]) -> Result<()> {
+    output::write_list(&mut std::io::stdout().lock(), format, records)
+}
+
+fn print_one<R: Record>(format: Format, record: Option<R>, what: &str) -> Result<()> {
+    let record = record.ok_or_else(|| format!("{what} not found"))?;
+    output::write_one(&mut std::io::stdout().lock(), format, &record)
+}
+
+/// Applies the sender prefix unless it was disabled for a message.
+fn sender_prefix(profile: &Profile, message: &MessageArgs) -> Option<SenderPrefix> {
+    (!message.no_prefix).then(|| SenderPrefix::new(&profile.username, SenderPrefixMode::Insert))
+}
+
+fn client_for(client: &Client, prefix: Option<&SenderPrefix>) -> Client {
+    match prefix {
+        Some(prefix) => client.clone().with_sender_prefix(prefix.clone()),
+        None => client.clone(),
+    }
+}
+
+async fn run(cli: Cli) -> Result<ExitCode> {
+    let profile = profile::load(cli.profile.as_deref(), cli.config.as_deref())?;
+    let client = profile.client()?;
+    let format = cli.output;
+
+    match cli.command {
+        Command::Send(args) => {
+            let prefix = sender_prefix(&profile, &args.message);
+            let client = client_for(&client, prefix.as_ref());
+            let sanitization = &args.message.sanitization;
+            let text = sanitization.apply(
+                &args.message.text,
+                &profile.sanitization,
+                prefix.as_ref(),
+                args.split,
+            );
+
+            let mut template = profile.call_builder();
+            template
+                .recipients(args.recipients)
+                .emergency(args.emergency);
+            if !args.transmitter_groups.is_empty() {
+                template.transmitter_groups(args.transmitter_groups);
+            } else if profile.transmitter_groups.is_empty() {
+                return Err("no transmitter groups given and the profile has no defaults".into());
+            }
+
+            if args.split {
+                let sanitization = sanitization.merge(&profile.sanitization);
+                let mut options = MessageSplitOptionsBuilder::default();
+                if let Some(max_length) = sanitization.max_length {
+                    options.max_length(max_length);
+                }
+                if let Some(ellipses) = sanitization.ellipses {
+                    options.ellipses(ellipses);
+                }
+                let options = options.build()?;
+                let parts = client
+                    .new_call_split(&text, &template, &options, Duration::from_secs(2))
+                    .await?;
+                eprintln!("Sent {parts} call(s)");
+                return Ok(ExitCode::SUCCESS);
+            }
+
+            let call = template.text(text).build()?;
+            let policy = if args.convert_numeric {
+                NumericRecipientPolicy::Convert
+            } else {
+                NumericRecipientPolicy::Refuse
+            };
+
+            if args.dry_run {
+                // Validated as it would be sent, a lookup that fails is reported by validating
+                // the call as given
+                let calls = match policy {
+                    NumericRecipientPolicy::Convert => client
+                        .checked_calls(&call, policy)
+                        .await
+                        .unwrap_or_else(|_| vec![call]),
+                    NumericRecipientPolicy::Refuse => vec![call],
+                };
+
+                let mut report = CallValidationReport::default();
+                for call in &calls {
+                    let call_report = client.validate_call(call).await?;
+                    for (issues, found) in [
+                        (&mut report.errors, call_report.errors),
+                        (&mut report.warnings, call_report.warnings),
+                    ] {
+                        for issue in found {
+                            if !issues.contains(&issue) {
+                                issues.push(issue);
+                            }
+                        }
+                    }
+                }
+
+                print_list(format, &issues(&report))?;
+                return Ok(if report.is_valid() {
+                    ExitCode::SUCCESS
+                } else {
+                    ExitCode::FAILURE
+                });
+            }
+
+            client.new_call_checked(&call, policy).await?;
+        }
+        Command::News(NewsCommand::Send {
+            rubric,
+            number,
+            message,
+        }) => {
+            let prefix = sender_prefix(&profile, &message);
+            let client = client_for(&client, prefix.as_ref());
+            let news = OutgoingNewsBuilder::default()
+                .rubric(rubric)
+                .number(number)
+                .text(message.sanitization.apply(
+                    &message.text,
+                    &profile.sanitization,
+                    prefix.as_ref(),
+                    false,
+                ))
+                .build()?;
+            client.new_news(&news).await?;
+        }
+        Command::News(NewsCommand::List { rubric }) => {
+            print_list(format, &client.get_news(&rubric).await?.unwrap_or_default())?
+        }
+        Command::Calls { owner, since } => {
+            let owner = owner.unwrap_or(profile.username);
+            let mut calls = client.get_calls_by(&owner).await?.unwrap_or_default();
+            if let Some(since) = since {
+                calls.retain(|call| call.timestamp >= since);
+            }
+            print_list(format, &calls)?
+        }
+        Command::Stats => print_one(format, client.get_statistics().await?, "statistics")?,
+        Command::Callsigns(ListOrGet::List) => print_list(
+            format,
+            &client.get_all_callsigns().await?.unwrap_or_default(),
+        )?,
+        Command::Callsigns(ListOrGet::Get { name }) => print_one(
+            format,
+            client.get_callsign(&name).await?,
+            &format!("callsign {name}"),
+        )?,
+        Command::Transmitters(TransmittersCommand::List { status }) => {
+            let mut transmitters = client.get_all_transmitters().await?.unwrap_or_default();
+            if let Some(status) = status {
+                transmitters.retain(|t| t.status == status.into());
+            }
+            print_list(format, &transmitters)?
+        }
+        Command::Transmitters(TransmittersCommand::Get { name }) => print_one(
+            format,
+            client.get_transmitter(&name).await?,
+            &format!("transmitter {name}"),
+        )?,
+        Command::Groups(ListOrGet::List) => print_list(
+            format,
+            &client
+                .get_all_transmitter_groups()
+                .await?
+                .unwrap_or_default(),
+        )?,
+        Command::Groups(ListOrGet::Get { name }) => print_one(
+            format,
+            client.get_transmitter_group(&name).await?,
+            &format!("transmitter group {name}"),
+        )?,
+        Command::Nodes(ListOrGet::List) => {
+            print_list(format, &client.get_all_nodes().await?.unwrap_or_default())?
+        }
+        Command::Nodes(ListOrGet::Get { name }) => print_one(
+            format,
+            client.get_node(&name).await?,
+            &format!("node {name}"),
+        )?,
+        Command::Rubrics(ListOrGet::List) => {
+            print_list(format, &client.get_all_rubrics().await?.unwrap_or_default())?
+        }
+        Command::Rubrics(ListOrGet::Get { name }) => print_one(
+            format,
+            client.get_rubric(&name).await?,
+            &format!("rubric {name}"),
+        )?,
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(Cli::parse()).await {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn cli() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn send_arguments() {
+        let cli = Cli::try_parse_from([
+            "dapnet",
+            "send",
+            "--to",
+            "m0nxn,m0abc",
+            "-g",
+            "uk-all",
+            "--emergency",
+            "hello",
+        ])
+        .unwrap();
+        let Command::Send(args) = cli.command else {
+            panic!("expected send");
+        };
+        assert_eq!(args.recipients, vec!["m0nxn", "m0abc"]);
+        assert_eq!(args.transmitter_groups, vec!["uk-all"]);
+        assert!(args.emergency);
+        assert_eq!(args.message.text, "hello");
+    }
+
+    #[test]
+    fn sanitization_flags() {
+        let cli = Cli::try_parse_from([
+            "dapnet",
+            "send",
+            "--to",
+            "m0nxn",
+            "-g",
+            "uk-all",
+            "--max-length",
+            "12",
+            "--non-ascii",
+            "transliterate",
+            "Grüße aus Köln",
+        ])
+        .unwrap();
+        let Command::Send(args) = cli.command else {
+            panic!("expected send");
+        };
+        let sanitization = &args.message.sanitization;
+        let defaults = SanitizationDefaults {
+            max_length: Some(40),
+            ellipses: Some("~".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            sanitization.apply(&args.message.text, &defaults, None, false),
+            "Gruesse aus~"
+        );
+        assert_eq!(
+            sanitization.apply(&args.message.text, &defaults, None, true),
+            "Gruesse aus Koeln"
+        );
+        assert_eq!(
+            sanitization.apply(
+                &args.message.text,
+                &SanitizationDefaults::default(),
+                None,
+                false
+            ),
+            "Gruesse a..."
+        );
+    }
+
+    #[test]
+    fn sanitization_leaves_room_for_prefix() {
+        let cli = Cli::try_parse_from(["dapnet", "send", "--to", "m0nxn", "--ellipses", "~", "x"])
+            .unwrap();
+        let Command::Send(args) = cli.command else {
+            panic!("expected send");
+        };
+        let sanitization = &args.message.sanitization;
+        let defaults = SanitizationDefaults::default();
+        let prefix = SenderPrefix::new("M0NXN", SenderPrefixMode::Insert);
+
+        let text = sanitization.apply(&"x".repeat(100), &defaults, Some(&prefix), false);
+        assert_eq!(text, format!("{}~", "x".repeat(72)));
+        let sent = prefix.apply(&text).unwrap();
+        assert_eq!(sent.chars().count(), 80);
+        assert!(sent.ends_with("x~"));
+
+        // Nothing is inserted when the text already has the prefix
+        let text = format!("M0NXN: {}", "x".repeat(100));
+        assert_eq!(
+            sanitization
+                .apply(&text, &defaults, Some(&prefix), false)
+                .chars()
+                .count(),
+            80
+        );
+    }
+
+    #[test]
+    fn since() {
+        let now = Utc::now();
+        let since = parse_since("1h").unwrap();
+        assert!((now - since - ChronoDuration::hours(1)).num_seconds().abs() < 5);
+
+        assert_eq!(
+            parse_since("2024-01-01T00:00:00Z").unwrap(),
+            DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap()
+        );
+
+        assert!(parse_since("1y").is_err());
+        assert!(parse_since("h").is_err());
+        assert!(parse_since("soon").is_err());
+    }
+}
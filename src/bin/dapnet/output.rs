@@ -0,0 +1,321 @@
+use clap::ValueEnum;
+use dapnet_api::{Call, Callsign, News, Node, Rubric, Statistics, Transmitter, TransmitterGroup};
+use serde::Serialize;
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum Format {
+    /// Aligned columns for reading in a terminal
+    Table,
+    /// The records as returned by the API
+    Json,
+    /// One line per record, with a header line
+    Csv,
+}
+
+/// Something that can be printed in any [`Format`].
+pub(crate) trait Record: Serialize {
+    /// Column names for the table and CSV formats.
+    const HEADERS: &'static [&'static str];
+
+    /// Values for each of [`Record::HEADERS`].
+    fn row(&self) -> Vec<String>;
+}
+
+type Result = std::result::Result<(), Box<dyn std::error::Error>>;
+
+/// Writes a list of records, e.g. the output of a `list` command.
+pub(crate) fn write_list<R: Record>(w: &mut impl Write, format: Format, records: &[R]) -> Result {
+    match format {
+        Format::Table => {
+            let rows = records.iter().map(Record::row).collect::<Vec<_>>();
+            write_table(w, R::HEADERS, &rows)?;
+        }
+        Format::Json => writeln!(w, "{}", serde_json::to_string_pretty(records)?)?,
+        Format::Csv => write_csv(w, R::HEADERS, records.iter().map(Record::row))?,
+    }
+    Ok(())
+}
+
+/// Writes a single record, e.g. the output of a `get` command.
+///
+/// In the table format each field is printed on its own line.
+pub(crate) fn write_one<R: Record>(w: &mut impl Write, format: Format, record: &R) -> Result {
+    match format {
+        Format::Table => {
+            let rows = R::HEADERS
+                .iter()
+                .zip(record.row())
+                .map(|(header, value)| vec![format!("{header}:"), value])
+                .collect::<Vec<_>>();
+            write_table(w, &[], &rows)?;
+        }
+        Format::Json => writeln!(w, "{}", serde_json::to_string_pretty(record)?)?,
+        Format::Csv => write_csv(w, R::HEADERS, std::iter::once(record.row()))?,
+    }
+    Ok(())
+}
+
+fn write_table(w: &mut impl Write, headers: &[&str], rows: &[Vec<String>]) -> Result {
+    let headers = headers
+        .iter()
+        .map(|header| header.to_uppercase())
+        .collect::<Vec<_>>();
+    let lines = (!headers.is_empty())
+        .then_some(&headers)
+        .into_iter()
+        .chain(rows);
+
+    let mut widths = Vec::new();
+    for line in lines.clone() {
+        widths.resize(widths.len().max(line.len()), 0);
+        for (width, value) in widths.iter_mut().zip(line) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+
+    for line in lines {
+        let line = line
+            .iter()
+            .zip(&widths)
+            .map(|(value, width)| format!("{value:width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        writeln!(w, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
+
+fn write_csv(
+    w: &mut impl Write,
+    headers: &[&str],
+    rows: impl Iterator<Item = Vec<String>>,
+) -> Result {
+    let mut csv = csv::Writer::from_writer(w);
+    csv.write_record(headers)?;
+    for row in rows {
+        csv.write_record(row)?;
+    }
+    csv.flush()?;
+    Ok(())
+}
+
+fn list(values: &[String]) -> String {
+    values.join(",")
+}
+
+fn lowercase(value: impl std::fmt::Debug) -> String {
+    format!("{value:?}").to_lowercase()
+}
+
+impl Record for Call {
+    const HEADERS: &'static [&'static str] = &[
+        "timestamp",
+        "sender",
+        "recipients",
+        "transmitter_groups",
+        "emergency",
+        "text",
+    ];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.timestamp.to_rfc3339(),
+            self.sender.clone(),
+            list(&self.recipients),
+            list(&self.transmitter_groups),
+            self.emergency.to_string(),
+            self.text.clone(),
+        ]
+    }
+}
+
+impl Record for Callsign {
+    const HEADERS: &'static [&'static str] = &["name", "numeric", "owners", "description"];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.numeric.to_string(),
+            list(&self.owners),
+            self.description.clone(),
+        ]
+    }
+}
+
+impl Record for News {
+    const HEADERS: &'static [&'static str] = &["rubric", "number", "timestamp", "sender", "text"];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.rubric.clone(),
+            self.number.map(|n| n.to_string()).unwrap_or_default(),
+            self.timestamp.to_rfc3339(),
+            self.sender.clone(),
+            self.text.clone(),
+        ]
+    }
+}
+
+impl Record for Node {
+    const HEADERS: &'static [&'static str] = &["name", "status", "version", "owners"];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            lowercase(self.status),
+            self.version.clone(),
+            list(&self.owners),
+        ]
+    }
+}
+
+impl Record for Rubric {
+    const HEADERS: &'static [&'static str] =
+        &["name", "number", "label", "transmitter_groups", "owners"];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.number.to_string(),
+            self.label.clone(),
+            list(&self.transmitter_groups),
+            list(&self.owners),
+        ]
+    }
+}
+
+impl Record for Statistics {
+    const HEADERS: &'static [&'static str] = &[
+        "users",
+        "callsigns",
+        "calls",
+        "calls_total",
+        "nodes_online",
+        "nodes_total",
+        "transmitters_online",
+        "transmitters_total",
+        "rubrics",
+        "news",
+        "news_total",
+    ];
+
+    fn row(&self) -> Vec<String> {
+        [
+            self.users,
+            self.callsigns,
+            self.calls,
+            self.calls_total,
+            self.nodes_online,
+            self.nodes_total,
+            self.transmitters_online,
+            self.transmitters_total,
+            self.rubrics,
+            self.news,
+            self.news_total,
+        ]
+        .iter()
+        .map(|n| n.to_string())
+        .collect()
+    }
+}
+
+impl Record for Transmitter {
+    const HEADERS: &'static [&'static str] = &[
+        "name",
+        "status",
+        "usage",
+        "node",
+        "calls",
+        "power",
+        "timeslots",
+        "owners",
+    ];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            lowercase(self.status),
+            lowercase(self.usage),
+            self.node.clone().unwrap_or_default(),
+            self.call_count.to_string(),
+            self.power.clone(),
+            self.timeslots.clone(),
+            list(&self.owners),
+        ]
+    }
+}
+
+impl Record for TransmitterGroup {
+    const HEADERS: &'static [&'static str] = &["name", "transmitters", "owners", "description"];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            list(&self.transmitters),
+            list(&self.owners),
+            self.description.clone(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn callsigns() -> Vec<Callsign> {
+        serde_json::from_value(serde_json::json!([
+            {"name": "m0nxn", "description": "Dan", "numeric": false, "ownerNames": ["m0nxn"]},
+            {"name": "m0abcde", "description": "", "numeric": true, "ownerNames": ["m0nxn", "m0abc"]},
+        ]))
+        .unwrap()
+    }
+
+    fn render(f: impl FnOnce(&mut Vec<u8>) -> Result) -> String {
+        let mut out = Vec::new();
+        f(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn table() {
+        let out = render(|w| write_list(w, Format::Table, &callsigns()));
+        assert_eq!(
+            out,
+            "NAME     NUMERIC  OWNERS       DESCRIPTION\n\
+             m0nxn    false    m0nxn        Dan\n\
+             m0abcde  true     m0nxn,m0abc\n"
+        );
+    }
+
+    #[test]
+    fn single_record_table() {
+        let out = render(|w| write_one(w, Format::Table, &callsigns()[0]));
+        assert_eq!(
+            out,
+            "name:         m0nxn\n\
+             numeric:      false\n\
+             owners:       m0nxn\n\
+             description:  Dan\n"
+        );
+    }
+
+    #[test]
+    fn csv() {
+        let out = render(|w| write_list(w, Format::Csv, &callsigns()));
+        assert_eq!(
+            out,
+            "name,numeric,owners,description\n\
+             m0nxn,false,m0nxn,Dan\n\
+             m0abcde,true,\"m0nxn,m0abc\",\n"
+        );
+    }
+
+    #[test]
+    fn json() {
+        let out = render(|w| write_list(w, Format::Json, &callsigns()[..1]));
+        let value: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(value[0]["name"], "m0nxn");
+        assert_eq!(value[0]["ownerNames"][0], "m0nxn");
+    }
+}
@@ -0,0 +1,74 @@
+use dapnet_api::{Config, PasswordSource, Profile, SanitizationDefaults};
+use std::path::Path;
+
+/// Finds the profile to use.
+///
+/// A profile named on the command line is always read from the config file.
+/// Otherwise the `DAPNET_USERNAME` and `DAPNET_PASSWORD` environment variables are used if both
+/// are set, falling back to the default profile of the config file.
+pub(crate) fn load(
+    name: Option<&str>,
+    config: Option<&Path>,
+) -> Result<Profile, Box<dyn std::error::Error>> {
+    if name.is_none()
+        && let Some(profile) = from_environment()
+    {
+        return Ok(profile);
+    }
+
+    let config = match config {
+        Some(path) => Config::load(path),
+        None => Config::load_default(),
+    }
+    .map_err(|e| {
+        format!("{e} (set DAPNET_USERNAME and DAPNET_PASSWORD or create a config file)")
+    })?;
+
+    Ok(select(&config, name)?.clone())
+}
+
+fn from_environment() -> Option<Profile> {
+    let username = std::env::var("DAPNET_USERNAME").ok()?;
+    std::env::var_os("DAPNET_PASSWORD")?;
+
+    Some(Profile {
+        api_url: None,
+        username,
+        password: PasswordSource::Env("DAPNET_PASSWORD".to_string()),
+        transmitter_groups: Vec::new(),
+        sanitization: SanitizationDefaults::default(),
+    })
+}
+
+fn select<'a>(config: &'a Config, name: Option<&str>) -> dapnet_api::Result<&'a Profile> {
+    match name {
+        Some(name) => config.profile(name),
+        None => config.default_profile(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn select_profile() {
+        let config: Config = r#"
+            default_profile = "club"
+
+            [profiles.club]
+            username = "m0nxn"
+            password = { env = "CLUB_PASSWORD" }
+
+            [profiles.test]
+            username = "m0abc"
+            password = { env = "TEST_PASSWORD" }
+        "#
+        .parse()
+        .unwrap();
+
+        assert_eq!(select(&config, None).unwrap().username, "m0nxn");
+        assert_eq!(select(&config, Some("test")).unwrap().username, "m0abc");
+        assert!(select(&config, Some("other")).is_err());
+    }
+}
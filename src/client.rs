@@ -1,9 +1,16 @@
 use crate::{
-    Call, Callsign, News, Node, OutgoingCall, OutgoingNews, Rubric, Statistics, Transmitter,
+    Call, Callsign, MessageSplitOptions, News, Node, NumericAlphabet, OutgoingCall,
+    OutgoingCallBuilder, OutgoingNews, Rubric, Secret, SenderPrefix, Statistics, Transmitter,
     TransmitterGroup,
+    rate_limit::{RateLimiter, RateLimits},
+    recipient_policy::{PolicyEnforcer, RecipientPolicies},
+    split_message,
+    validation::MetadataCache,
 };
+use chrono::{DateTime, Utc};
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, sync::Arc, time::Duration};
 use url::Url;
 
 #[derive(Clone, Debug)]
@@ -19,12 +26,27 @@ impl Default for ClientConfig {
     }
 }
 
+/// What to do when a call's text cannot be displayed by a recipient's numeric pager.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NumericRecipientPolicy {
+    /// Do not send the call at all.
+    Refuse,
+
+    /// Send the numeric recipients a separate call with the text converted to the numeric
+    /// alphabet, see [`NumericAlphabet::sanitize`].
+    Convert,
+}
+
 #[derive(Clone, Debug)]
 pub struct Client {
     client: reqwest::Client,
     username: String,
-    password: String,
+    password: Secret,
     config: ClientConfig,
+    pub(crate) sender_prefix: Option<SenderPrefix>,
+    pub(crate) metadata_cache: Option<Arc<MetadataCache>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    recipient_policies: Option<Arc<PolicyEnforcer>>,
 }
 
 impl Client {
@@ -39,16 +61,101 @@ impl Client {
         Self {
             client: reqwest::Client::new(),
             username: username.to_string(),
-            password: password.to_string(),
+            password: Secret::from(password),
             config: ClientConfig::default(),
+            sender_prefix: None,
+            metadata_cache: None,
+            rate_limiter: None,
+            recipient_policies: None,
         }
     }
 
+    /// Applies a sender identification prefix to the text of every call and news item sent by
+    /// this client.
+    ///
+    /// Calls built in numeric mode are sent unchanged, as numeric pagers cannot display a
+    /// callsign.
+    ///
+    /// Example:
+    /// ```
+    /// use dapnet_api::{Client, SenderPrefix, SenderPrefixMode};
+    /// let client = Client::new("m0nxn", "my_super_secret_password")
+    ///     .with_sender_prefix(SenderPrefix::new("M0NXN", SenderPrefixMode::Insert));
+    /// ```
+    pub fn with_sender_prefix(mut self, prefix: SenderPrefix) -> Self {
+        self.sender_prefix = Some(prefix);
+        self
+    }
+
+    /// Connects to a different DAPNET API, e.g. a private network or a test instance.
+    ///
+    /// Example:
+    /// ```
+    /// use dapnet_api::Client;
+    /// let client = Client::new("m0nxn", "my_super_secret_password")
+    ///     .with_api_url("http://localhost:8080/api/".parse().unwrap());
+    /// ```
+    pub fn with_api_url(mut self, api_url: Url) -> Self {
+        self.config.api_url = api_url;
+        self
+    }
+
+    /// Caches the callsigns, transmitter groups and transmitters fetched by
+    /// [`validate_call`](Client::validate_call) for `ttl`.
+    ///
+    /// The cache is shared between clones of the client.
+    pub fn with_metadata_cache(mut self, ttl: Duration) -> Self {
+        self.metadata_cache = Some(Arc::new(MetadataCache::new(ttl)));
+        self
+    }
+
+    /// Limits how often calls and news can be sent by this client.
+    ///
+    /// Every call sent counts, including each part sent by
+    /// [`new_call_split`](Client::new_call_split) and each call sent by
+    /// [`new_call_checked`](Client::new_call_checked).
+    /// The limits are shared between clones of the client.
+    pub fn with_rate_limits(mut self, limits: RateLimits) -> Self {
+        self.rate_limiter = Some(Arc::new(RateLimiter::new(limits)));
+        self
+    }
+
+    /// Applies delivery policies to the recipients of every call sent by this client.
+    ///
+    /// Calls are only sent to the recipients whose policies allow it, the client's username is
+    /// the sender checked against allowed senders.
+    /// The pages counted towards hourly limits and the deferred calls are shared between clones
+    /// of the client.
+    ///
+    /// Example:
+    /// ```
+    /// use chrono::NaiveTime;
+    /// use dapnet_api::{Client, HeldCallAction, QuietHours, RecipientPolicies, RecipientPolicy};
+    /// let client = Client::new("m0nxn", "my_super_secret_password").with_recipient_policies(
+    ///     RecipientPolicies::new()
+    ///         .with_policy(
+    ///             "m0abc",
+    ///             RecipientPolicy::new()
+    ///                 .with_quiet_hours(QuietHours::new(
+    ///                     NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+    ///                     NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+    ///                     chrono_tz::Europe::London,
+    ///                 ))
+    ///                 .with_max_pages_per_hour(10),
+    ///         )
+    ///         .with_held_calls(HeldCallAction::Defer),
+    /// );
+    /// ```
+    pub fn with_recipient_policies(mut self, policies: RecipientPolicies) -> Self {
+        self.recipient_policies = Some(Arc::new(PolicyEnforcer::new(policies)));
+        self
+    }
+
     async fn get<T: for<'de> Deserialize<'de>>(&self, path: &str) -> crate::Result<Option<T>> {
         let result = self
             .client
             .get(self.config.api_url.join(path)?)
-            .basic_auth(&self.username, Some(&self.password))
+            .basic_auth(&self.username, Some(self.password.expose_secret()))
             .send()
             .await?;
 
@@ -68,7 +175,7 @@ impl Client {
         let result = self
             .client
             .get(self.config.api_url.join(path)?)
-            .basic_auth(&self.username, Some(&self.password))
+            .basic_auth(&self.username, Some(self.password.expose_secret()))
             .send()
             .await?;
 
@@ -85,7 +192,7 @@ impl Client {
         let result = self
             .client
             .post(self.config.api_url.join(path)?)
-            .basic_auth(&self.username, Some(&self.password))
+            .basic_auth(&self.username, Some(self.password.expose_secret()))
             .json(item)
             .send()
             .await?;
@@ -107,6 +214,10 @@ impl Client {
 
     /// Sends a new call/message.
     ///
+    /// With [`with_recipient_policies`](Client::with_recipient_policies) the call is only sent to
+    /// the recipients allowed by their policies, it is an error if there are none and the call was
+    /// not deferred for any.
+    ///
     /// Example:
     /// ```no_run
     /// # use dapnet_api::{Client, OutgoingCallBuilder};
@@ -126,7 +237,231 @@ impl Client {
     /// # }
     /// ```
     pub async fn new_call(&self, call: &OutgoingCall) -> crate::Result<()> {
-        self.post("calls", call).await
+        match self.apply_recipient_policies(call)? {
+            Some(call) => self.send_call(&call).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Limits a call to the recipients allowed by their policies, `None` if it was deferred for
+    /// all of them.
+    fn apply_recipient_policies<'a>(
+        &self,
+        call: &'a OutgoingCall,
+    ) -> crate::Result<Option<Cow<'a, OutgoingCall>>> {
+        let Some(policies) = &self.recipient_policies else {
+            return Ok(Some(Cow::Borrowed(call)));
+        };
+
+        let decision = policies.check(call, &self.username, Utc::now());
+        match decision.allowed_call(call) {
+            Some(_) if decision.held.is_empty() => Ok(Some(Cow::Borrowed(call))),
+            Some(allowed) => Ok(Some(Cow::Owned(allowed))),
+            None if policies.defers(&decision) => Ok(None),
+            None => Err(crate::Error::RecipientPolicy(
+                decision
+                    .held
+                    .iter()
+                    .map(|(recipient, violation)| format!("{recipient} ({violation})"))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )),
+        }
+    }
+
+    /// Sends a call that has passed the recipient policies.
+    async fn send_call(&self, call: &OutgoingCall) -> crate::Result<()> {
+        let prefixed = match &self.sender_prefix {
+            Some(prefix) if !call.numeric => Cow::Owned(OutgoingCall {
+                text: prefix.apply(&call.text)?,
+                ..call.clone()
+            }),
+            _ => Cow::Borrowed(call),
+        };
+
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire_call(&prefixed).await?;
+        }
+        self.post("calls", &prefixed).await?;
+
+        if let Some(policies) = &self.recipient_policies {
+            policies.record_sent(call, Utc::now());
+        }
+        Ok(())
+    }
+
+    /// Sends the calls deferred by recipient policies that are now due, returning how many were
+    /// sent.
+    ///
+    /// Calls are checked against the policies again, so may be deferred again.
+    /// If sending fails the calls not yet sent stay deferred and are retried on the next call.
+    /// This should be called periodically when [`HeldCallAction::Defer`](crate::HeldCallAction)
+    /// is used.
+    pub async fn send_deferred_calls(&self) -> crate::Result<usize> {
+        let Some(policies) = &self.recipient_policies else {
+            return Ok(0);
+        };
+
+        let mut due = policies.take_due(Utc::now()).into_iter();
+        let mut sent = 0;
+        while let Some((until, call)) = due.next() {
+            let error = match self.apply_recipient_policies(&call) {
+                Ok(Some(allowed)) => match self.send_call(&allowed).await {
+                    Ok(()) => {
+                        sent += 1;
+                        continue;
+                    }
+                    Err(e) => {
+                        policies.restore([(until, allowed.into_owned())]);
+                        e
+                    }
+                },
+                Ok(None) => continue,
+                Err(e) => e,
+            };
+            policies.restore(due);
+            return Err(error);
+        }
+        Ok(sent)
+    }
+
+    /// Calls deferred by recipient policies and when they are due.
+    pub fn deferred_calls(&self) -> Vec<(DateTime<Utc>, OutgoingCall)> {
+        self.recipient_policies
+            .as_ref()
+            .map(|policies| policies.deferred())
+            .unwrap_or_default()
+    }
+
+    /// Sends a new call/message after checking that numeric pagers can display it.
+    ///
+    /// Each recipient is looked up to find out if they have a numeric pager, if any do and the
+    /// text is not valid numeric text then the call is handled according to `policy`.
+    /// Numeric pagers are sent their own numeric call, which never has a sender prefix.
+    pub async fn new_call_checked(
+        &self,
+        call: &OutgoingCall,
+        policy: NumericRecipientPolicy,
+    ) -> crate::Result<()> {
+        for call in self.checked_calls(call, policy).await? {
+            self.new_call(&call).await?;
+        }
+        Ok(())
+    }
+
+    /// The calls [`new_call_checked`](Client::new_call_checked) would send, without sending
+    /// them.
+    pub async fn checked_calls(
+        &self,
+        call: &OutgoingCall,
+        policy: NumericRecipientPolicy,
+    ) -> crate::Result<Vec<OutgoingCall>> {
+        let mut numeric = Vec::new();
+        let mut alphanumeric = Vec::new();
+
+        for recipient in &call.recipients {
+            let callsign = self
+                .get_callsign(recipient)
+                .await?
+                .ok_or_else(|| crate::Error::NotFound(format!("callsign {recipient}")))?;
+
+            if callsign.numeric {
+                numeric.push(recipient.clone());
+            } else {
+                alphanumeric.push(recipient.clone());
+            }
+        }
+
+        if numeric.is_empty() || call.numeric {
+            return Ok(vec![call.clone()]);
+        }
+
+        // Numeric pagers are always sent a numeric call, so that no sender prefix is added
+        let text = if NumericAlphabet::is_valid(&call.text) {
+            call.text.clone()
+        } else {
+            let text = NumericAlphabet::sanitize(&call.text);
+            if policy == NumericRecipientPolicy::Refuse || text.trim().is_empty() {
+                return Err(crate::Error::NotNumeric(numeric.join(", ")));
+            }
+            text
+        };
+
+        let mut calls = Vec::new();
+        if !alphanumeric.is_empty() {
+            calls.push(OutgoingCall {
+                text: call.text.clone(),
+                recipients: alphanumeric,
+                transmitter_groups: call.transmitter_groups.clone(),
+                emergency: call.emergency,
+                numeric: false,
+            });
+        }
+        calls.push(OutgoingCall {
+            text,
+            recipients: numeric,
+            transmitter_groups: call.transmitter_groups.clone(),
+            emergency: call.emergency,
+            numeric: true,
+        });
+        Ok(calls)
+    }
+
+    /// Sends a message that may be too long for a single call as several calls.
+    ///
+    /// The text is split using [`split_message`] and each part is sent in order using the
+    /// recipients, transmitter groups and priority from `template`, waiting for `pacing` between
+    /// parts so that they arrive in order.
+    /// If the client has a sender prefix it is added to every part.
+    /// Returns the number of calls that were sent.
+    ///
+    /// Example:
+    /// ```no_run
+    /// # use dapnet_api::{Client, MessageSplitOptions, OutgoingCallBuilder};
+    /// # use std::time::Duration;
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let client = Client::new("m0nxn", "my_super_secret_password");
+    /// client
+    ///     .new_call_split(
+    ///         "M0NXN: a message that is much too long to fit into a single call, so it is sent as two",
+    ///         OutgoingCallBuilder::default()
+    ///             .recipients(vec!["m0nxn".to_string()])
+    ///             .transmitter_groups(vec!["uk-all".to_string()]),
+    ///         &MessageSplitOptions::default(),
+    ///         Duration::from_secs(5),
+    ///     )
+    ///     .await
+    ///     .unwrap();
+    /// # }
+    /// ```
+    pub async fn new_call_split(
+        &self,
+        text: &str,
+        template: &OutgoingCallBuilder,
+        options: &MessageSplitOptions,
+        pacing: Duration,
+    ) -> crate::Result<usize> {
+        // Each part is identified separately, so space for the prefix is left in every part
+        let (prefix, text) = match &self.sender_prefix {
+            Some(prefix) => (prefix.prefix(), prefix.body(text)?),
+            None => (String::new(), text),
+        };
+        let options = options.reserve(prefix.chars().count());
+
+        let calls = split_message(text, &options)
+            .into_iter()
+            .map(|part| template.clone().text(format!("{prefix}{part}")).build())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (i, call) in calls.iter().enumerate() {
+            if i > 0 {
+                tokio::time::sleep(pacing).await;
+            }
+            self.new_call(call).await?;
+        }
+
+        Ok(calls.len())
     }
 
     pub async fn get_all_nodes(&self) -> crate::Result<Option<Vec<Node>>> {
@@ -202,6 +537,62 @@ impl Client {
     /// # }
     /// ```
     pub async fn new_news(&self, news: &OutgoingNews) -> crate::Result<()> {
-        self.post("news", news).await
+        let news = match &self.sender_prefix {
+            Some(prefix) => Cow::Owned(OutgoingNews {
+                text: prefix.apply(&news.text)?,
+                ..news.clone()
+            }),
+            None => Cow::Borrowed(news),
+        };
+
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire_news(&news).await?;
+        }
+        self.post("news", &news).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{SenderPrefixMode, test_util::server};
+
+    const NUMERIC: &str =
+        r#"{"name": "m0abc", "description": "", "numeric": true, "ownerNames": []}"#;
+    const ALPHANUMERIC: &str =
+        r#"{"name": "m0nxn", "description": "", "numeric": false, "ownerNames": []}"#;
+
+    #[tokio::test]
+    async fn numeric_text_to_numeric_pager_is_not_prefixed() {
+        let (url, requests) = server(vec![
+            (200, ALPHANUMERIC),
+            (200, NUMERIC),
+            (201, ""),
+            (201, ""),
+        ])
+        .await;
+        let client = Client::new("m0nxn", "hunter2")
+            .with_api_url(url)
+            .with_sender_prefix(SenderPrefix::new("M0NXN", SenderPrefixMode::Insert));
+
+        let call = OutgoingCallBuilder::default()
+            .text("0161 555".to_string())
+            .recipients(vec!["m0nxn".to_string(), "m0abc".to_string()])
+            .transmitter_groups(vec!["uk-all".to_string()])
+            .build()
+            .unwrap();
+        client
+            .new_call_checked(&call, NumericRecipientPolicy::Refuse)
+            .await
+            .unwrap();
+
+        let calls: Vec<serde_json::Value> = requests.lock().unwrap()[2..]
+            .iter()
+            .map(|(_, body)| serde_json::from_str(body).unwrap())
+            .collect();
+        assert_eq!(calls[0]["text"], "M0NXN: 0161 555");
+        assert_eq!(calls[0]["callSignNames"], serde_json::json!(["m0nxn"]));
+        assert_eq!(calls[1]["text"], "0161 555");
+        assert_eq!(calls[1]["callSignNames"], serde_json::json!(["m0abc"]));
     }
 }
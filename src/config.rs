@@ -0,0 +1,411 @@
+use crate::{
+    Client, CompactionRule, MessageSanitizationNonAsciiPolicy, MessageSanitizationOptions,
+    MessageSanitizationOptionsBuilder, OutgoingCallBuilder, PagerCharset, Secret, Transliteration,
+};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use url::Url;
+
+/// Named profiles describing how to connect to DAPNET, loaded from a TOML file.
+///
+/// Example:
+/// ```
+/// # use dapnet_api::Config;
+/// let config: Config = r#"
+///     default_profile = "club"
+///
+///     [profiles.club]
+///     username = "m0nxn"
+///     password = { env = "CLUB_DAPNET_PASSWORD" }
+///     transmitter_groups = ["uk-all"]
+///
+///     [profiles.club.sanitization]
+///     non_ascii = "transliterate"
+///     compact = true
+///
+///     [profiles.test]
+///     api_url = "http://localhost:8080/"
+///     username = "m0nxn"
+///     password = { command = ["pass", "show", "dapnet/test"] }
+/// "#
+/// .parse()
+/// .unwrap();
+///
+/// assert_eq!(config.profile("club").unwrap().transmitter_groups, ["uk-all"]);
+/// assert_eq!(config.default_profile().unwrap().username, "m0nxn");
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Profile used when none is named, not needed if there is only one profile.
+    pub default_profile: Option<String>,
+
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl FromStr for Config {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        toml::from_str(s).map_err(|e| crate::Error::ConfigError(e.to_string()))
+    }
+}
+
+impl Config {
+    pub fn load(path: &Path) -> crate::Result<Self> {
+        std::fs::read_to_string(path)?.parse()
+    }
+
+    /// The config file used by default: `$DAPNET_CONFIG` if it is set, otherwise
+    /// `dapnet/config.toml` in `$XDG_CONFIG_HOME` or `~/.config`.
+    pub fn default_path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os("DAPNET_CONFIG") {
+            return Some(PathBuf::from(path));
+        }
+
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .map(|dir| dir.join("dapnet").join("config.toml"))
+    }
+
+    /// Loads the config file at [`Config::default_path`].
+    pub fn load_default() -> crate::Result<Self> {
+        let path = Self::default_path().ok_or_else(|| {
+            crate::Error::ConfigError("cannot find the config file, HOME is not set".to_string())
+        })?;
+        Self::load(&path)
+    }
+
+    pub fn profile(&self, name: &str) -> crate::Result<&Profile> {
+        self.profiles
+            .get(name)
+            .ok_or_else(|| crate::Error::NotFound(format!("profile {name}")))
+    }
+
+    /// The profile named by `default_profile`, or the only profile if there is just one.
+    pub fn default_profile(&self) -> crate::Result<&Profile> {
+        match (&self.default_profile, self.profiles.len()) {
+            (Some(name), _) => self.profile(name),
+            (None, 1) => Ok(self.profiles.values().next().unwrap()),
+            (None, _) => Err(crate::Error::ConfigError(
+                "no default_profile is set and there is not exactly one profile".to_string(),
+            )),
+        }
+    }
+}
+
+/// How to connect to DAPNET as one user, and defaults for the messages they send.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// API to connect to, the public DAPNET API by default.
+    pub api_url: Option<Url>,
+
+    pub username: String,
+
+    pub password: PasswordSource,
+
+    /// Transmitter groups used for calls that do not specify any.
+    #[serde(default)]
+    pub transmitter_groups: Vec<String>,
+
+    #[serde(default)]
+    pub sanitization: SanitizationDefaults,
+}
+
+impl Profile {
+    /// Creates a client for this profile, reading the password from its source.
+    pub fn client(&self) -> crate::Result<Client> {
+        let client = Client::new(&self.username, self.password.read()?.expose_secret());
+        Ok(match &self.api_url {
+            Some(url) => client.with_api_url(url.clone()),
+            None => client,
+        })
+    }
+
+    /// Creates a call builder with the default transmitter groups of this profile.
+    pub fn call_builder(&self) -> OutgoingCallBuilder {
+        let mut builder = OutgoingCallBuilder::default();
+        builder.transmitter_groups(self.transmitter_groups.clone());
+        builder
+    }
+}
+
+/// Where the password for a profile is read from.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PasswordSource {
+    /// An environment variable.
+    Env(String),
+
+    /// The contents of a file, without the trailing newline.
+    /// A leading `~/` is replaced with the home directory.
+    File(PathBuf),
+
+    /// The output of a command (program and arguments, no shell is used), without the trailing
+    /// newline.
+    /// This allows password managers to be used, e.g. `["pass", "show", "dapnet"]`.
+    Command(Vec<String>),
+}
+
+impl PasswordSource {
+    pub fn read(&self) -> crate::Result<Secret> {
+        match self {
+            Self::Env(name) => std::env::var(name).map(Secret::from).map_err(|_| {
+                crate::Error::ConfigError(format!(
+                    "password environment variable {name} is not set"
+                ))
+            }),
+            Self::File(path) => {
+                let password = std::fs::read_to_string(expand_home(path))?;
+                Ok(trim_newline(password))
+            }
+            Self::Command(command) => {
+                let (program, args) = command.split_first().ok_or_else(|| {
+                    crate::Error::ConfigError("password command is empty".to_string())
+                })?;
+                let output = std::process::Command::new(program).args(args).output()?;
+                if !output.status.success() {
+                    return Err(crate::Error::ConfigError(format!(
+                        "password command {program} failed ({})",
+                        output.status
+                    )));
+                }
+                let password = String::from_utf8(output.stdout).map_err(|_| {
+                    crate::Error::ConfigError(format!(
+                        "password command {program} did not output UTF-8"
+                    ))
+                })?;
+                Ok(trim_newline(password))
+            }
+        }
+    }
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn trim_newline(mut s: String) -> Secret {
+    let len = s.trim_end_matches(['\r', '\n']).len();
+    s.truncate(len);
+    Secret::from(s)
+}
+
+/// Defaults for [`MessageSanitizationOptions`], anything not set uses the default options.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SanitizationDefaults {
+    pub max_length: Option<usize>,
+    pub ellipses: Option<String>,
+    pub non_ascii: Option<NonAsciiHandling>,
+
+    /// Replacement for non-ASCII characters with [`NonAsciiHandling::Replace`] and characters
+    /// that cannot be transliterated with [`NonAsciiHandling::Transliterate`].
+    pub replacement: Option<char>,
+
+    pub charset: Option<PagerCharset>,
+
+    /// Shorten messages with [`CompactionRule::default_dictionary`] before truncating them.
+    #[serde(default)]
+    pub compact: bool,
+}
+
+/// What to do with non-ASCII characters, see [`MessageSanitizationNonAsciiPolicy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NonAsciiHandling {
+    DoNothing,
+    Remove,
+    Replace,
+    Transliterate,
+}
+
+impl SanitizationDefaults {
+    pub fn options(&self) -> MessageSanitizationOptions {
+        let replacement = self.replacement.unwrap_or('?');
+
+        let mut builder = MessageSanitizationOptionsBuilder::default();
+        if let Some(max_length) = self.max_length {
+            builder.max_length(max_length);
+        }
+        if let Some(ellipses) = &self.ellipses {
+            builder.ellipses(ellipses.clone());
+        }
+        builder.non_ascii_policy(match self.non_ascii.unwrap_or(NonAsciiHandling::Replace) {
+            NonAsciiHandling::DoNothing => MessageSanitizationNonAsciiPolicy::DoNothing,
+            NonAsciiHandling::Remove => MessageSanitizationNonAsciiPolicy::Remove,
+            NonAsciiHandling::Replace => {
+                MessageSanitizationNonAsciiPolicy::ReplaceWith(replacement)
+            }
+            NonAsciiHandling::Transliterate => MessageSanitizationNonAsciiPolicy::Transliterate(
+                Transliteration::default().with_fallback(Some(replacement)),
+            ),
+        });
+        if let Some(charset) = self.charset {
+            builder.charset(charset);
+        }
+        if self.compact {
+            builder.compaction(CompactionRule::default_dictionary());
+        }
+
+        builder.build().unwrap()
+    }
+}
+
+impl Client {
+    /// Creates a client from a named profile in the default config file, see
+    /// [`Config::default_path`].
+    ///
+    /// Example:
+    /// ```no_run
+    /// # use dapnet_api::Client;
+    /// let client = Client::from_profile("club").unwrap();
+    /// ```
+    pub fn from_profile(name: &str) -> crate::Result<Self> {
+        Config::load_default()?.profile(name)?.client()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let config: Config = r#"
+            [profiles.club]
+            api_url = "http://localhost:8080/api/"
+            username = "m0nxn"
+            password = { file = "~/.dapnet-password" }
+            transmitter_groups = ["uk-all", "uk-se"]
+
+            [profiles.club.sanitization]
+            max_length = 60
+            non_ascii = "transliterate"
+            charset = "din66003"
+        "#
+        .parse()
+        .unwrap();
+
+        let profile = config.default_profile().unwrap();
+        assert_eq!(
+            profile.api_url,
+            Some(Url::parse("http://localhost:8080/api/").unwrap())
+        );
+        assert_eq!(
+            profile.password,
+            PasswordSource::File(PathBuf::from("~/.dapnet-password"))
+        );
+        assert_eq!(profile.transmitter_groups, ["uk-all", "uk-se"]);
+        assert_eq!(
+            profile.sanitization.options(),
+            MessageSanitizationOptionsBuilder::default()
+                .max_length(60)
+                .non_ascii_policy(MessageSanitizationNonAsciiPolicy::Transliterate(
+                    Transliteration::default()
+                ))
+                .charset(PagerCharset::Din66003)
+                .build()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(matches!(
+            "[profiles.club]\nusername = \"m0nxn\"".parse::<Config>(),
+            Err(crate::Error::ConfigError(_))
+        ));
+        assert!(matches!(
+            "[profiles.club]\nusername = \"m0nxn\"\npassword = { env = \"X\" }\ngroups = []"
+                .parse::<Config>(),
+            Err(crate::Error::ConfigError(_))
+        ));
+    }
+
+    #[test]
+    fn profile_selection() {
+        let config: Config = r#"
+            [profiles.a]
+            username = "a"
+            password = { env = "A" }
+
+            [profiles.b]
+            username = "b"
+            password = { env = "B" }
+        "#
+        .parse()
+        .unwrap();
+
+        assert_eq!(config.profile("b").unwrap().username, "b");
+        assert!(matches!(
+            config.profile("c"),
+            Err(crate::Error::NotFound(_))
+        ));
+        assert!(config.default_profile().is_err());
+
+        let config = Config {
+            default_profile: Some("a".to_string()),
+            ..config
+        };
+        assert_eq!(config.default_profile().unwrap().username, "a");
+    }
+
+    #[test]
+    fn default_sanitization() {
+        assert_eq!(
+            SanitizationDefaults::default().options(),
+            MessageSanitizationOptions::default()
+        );
+    }
+
+    #[test]
+    fn password_from_command() {
+        let source = PasswordSource::Command(vec![
+            "echo".to_string(),
+            "my_super_secret_password".to_string(),
+        ]);
+        assert_eq!(
+            source.read().unwrap().expose_secret(),
+            "my_super_secret_password"
+        );
+
+        assert!(
+            PasswordSource::Command(vec!["false".to_string()])
+                .read()
+                .is_err()
+        );
+        assert!(PasswordSource::Command(vec![]).read().is_err());
+    }
+
+    #[test]
+    fn password_from_file() {
+        let path = std::env::temp_dir().join(format!("dapnet-password-{}", std::process::id()));
+        std::fs::write(&path, "my_super_secret_password\n").unwrap();
+
+        let password = PasswordSource::File(path.clone()).read();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            password.unwrap().expose_secret(),
+            "my_super_secret_password"
+        );
+    }
+
+    #[test]
+    fn password_from_missing_env() {
+        assert!(matches!(
+            PasswordSource::Env("DAPNET_TEST_UNSET_VARIABLE".to_string()).read(),
+            Err(crate::Error::ConfigError(_))
+        ));
+    }
+}
@@ -6,8 +6,60 @@ pub enum Error {
     #[error("HTTP error {0}")]
     HttpError(#[from] reqwest::Error),
 
+    #[error("{0} not found")]
+    NotFound(String),
+
+    #[error("IO error {0}")]
+    IoError(#[from] std::io::Error),
+
     #[error("URL error {0}")]
     UrlError(#[from] url::ParseError),
+
+    #[error("RIC {0} is out of range")]
+    InvalidRic(u32),
+
+    #[error("Alphanumeric page text contains characters outside 7 bit ASCII: {0:?}")]
+    NonAsciiText(String),
+
+    #[error("Skyper encoding error: {0}")]
+    SkyperError(String),
+
+    #[error("Transmitter protocol error: {0}")]
+    ProtocolError(String),
+
+    #[error("Text cannot be sent to numeric pager(s) {0}")]
+    NotNumeric(String),
+
+    #[error("Message does not start with the sender prefix \"{0}\"")]
+    MissingSenderPrefix(String),
+
+    #[error("Rate limit for {limit} exceeded, retry after {retry_after:?}")]
+    RateLimited {
+        limit: String,
+        retry_after: std::time::Duration,
+    },
+
+    #[error("Call not sent by recipient policy: {0}")]
+    RecipientPolicy(String),
+
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error {0}")]
+    SqliteError(#[from] rusqlite::Error),
+
+    #[error("Schedule error: {0}")]
+    ScheduleError(String),
+
+    #[error("Config error: {0}")]
+    ConfigError(String),
+
+    #[error("Template error: {0}")]
+    TemplateError(String),
+
+    #[error("Regex error {0}")]
+    RegexError(#[from] regex::Error),
+
+    #[error("Invalid call {0}")]
+    CallBuilderError(#[from] crate::OutgoingCallBuilderError),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,34 @@
+use std::{ffi::OsString, path::Path};
+
+/// Replaces the contents of a file such that a crash cannot leave it partly written.
+///
+/// The contents are written to a temporary file next to it, named by appending ".tmp" to the
+/// whole file name, which is then renamed over the file.
+pub(crate) fn write_atomically(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let temporary = temporary_path(path);
+    std::fs::write(&temporary, contents)?;
+    std::fs::rename(&temporary, path)
+}
+
+fn temporary_path(path: &Path) -> std::path::PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn temporary_files_do_not_collide() {
+        assert_eq!(
+            temporary_path(Path::new("/var/lib/dapnet/state.json")),
+            Path::new("/var/lib/dapnet/state.json.tmp")
+        );
+        assert_ne!(
+            temporary_path(Path::new("state.json")),
+            temporary_path(Path::new("state.jsonl"))
+        );
+    }
+}
@@ -6,23 +6,81 @@
 //! Currently the library focuses on reading data from the API.
 //! The only non-idempotent operation it supports is sending a new call/page/message and new rubric
 //! news item.
+//!
+//! The [`pocsag`] module can be used to render calls into POCSAG transmissions, e.g. for testing
+//! pagers with a signal generator.
+//! The [`skyper`] module encodes rubrics and news the same way the DAPNET core does for Skyper
+//! pagers.
+//! The [`transmitter`] module implements the protocol transmitters use to connect to DAPNET core
+//! nodes.
+//! The [`pager`] module simulates pagers receiving transmissions.
 
+mod alert_gate;
 mod client;
+mod config;
 mod error;
+mod fs;
 mod message_sanitization;
+mod outbox;
+pub mod pager;
+pub mod pocsag;
+mod rate_limit;
+mod recipient_policy;
+mod scheduler;
+mod secret;
+mod sender_prefix;
+pub mod skyper;
+mod template;
+#[cfg(test)]
+mod test_util;
+pub mod transmitter;
 mod types;
+mod validation;
 
 pub use crate::{
-    client::Client,
+    alert_gate::{
+        AlertGate, AlertGateDecision, AlertGateOptions, AlertGateOptionsBuilder,
+        AlertGateOptionsBuilderError, AlertGateState, AlertGateStore, AlertSimilarity, AlertState,
+        FileAlertGateStore, MemoryAlertGateStore,
+    },
+    client::{Client, NumericRecipientPolicy},
+    config::{Config, NonAsciiHandling, PasswordSource, Profile, SanitizationDefaults},
     error::{Error, Result},
     message_sanitization::{
-        MessageSanitizationNonAsciiPolicy, MessageSanitizationOptions,
-        MessageSanitizationOptionsBuilder, MessageSanitizationOptionsBuilderError,
-        sanitize_message,
+        CollapseWhitespace, Compacted, Compaction, CompactionRule, CompactionWord, ElideUrls,
+        EmojiToText, MAX_NUMERIC_LENGTH, MessageSanitizationNonAsciiPolicy,
+        MessageSanitizationOptions, MessageSanitizationOptionsBuilder,
+        MessageSanitizationOptionsBuilderError, MessageSplitOptions, MessageSplitOptionsBuilder,
+        MessageSplitOptionsBuilderError, NormalizeControlCharacters, NumericAlphabet, PagerCharset,
+        RecipientCharsets, RegexReplace, SanitizationPipeline, SanitizationStep,
+        SplitMarkerPosition, Transliteration, Truncate, sanitize_message, split_message,
+    },
+    outbox::{
+        FileOutboxStore, MemoryOutboxStore, Outbox, OutboxEntry, OutboxMessage, OutboxOptions,
+        OutboxOptionsBuilder, OutboxOptionsBuilderError, OutboxStatus, OutboxStore,
+    },
+    rate_limit::{
+        EmergencyPolicy, Rate, RateLimitAction, RateLimitBudget, RateLimits, RateLimitsBuilder,
+        RateLimitsBuilderError,
     },
+    recipient_policy::{
+        EmergencyOverride, HeldCallAction, PolicyDecision, PolicyViolation, QuietHours,
+        RecipientPolicies, RecipientPolicy,
+    },
+    scheduler::{
+        FileJobStore, JobStore, MemoryJobStore, MissedRunPolicy, Schedule, ScheduledJob, Scheduler,
+    },
+    secret::Secret,
+    sender_prefix::{SenderPrefix, SenderPrefixMode},
+    template::{MessageTemplate, RenderedMessage, TemplateValue, TemplateVariables},
     types::{
-        Call, Callsign, Connection, News, Node, OutgoingCall, OutgoingCallBuilder,
-        OutgoingCallBuilderError, OutgoingNews, OutgoingNewsBuilder, OutgoingNewsBuilderError,
-        Rubric, Statistics, Transmitter, TransmitterGroup,
+        AntennaType, Call, Callsign, Connection, News, Node, NodeStatus, OutgoingCall,
+        OutgoingCallBuilder, OutgoingCallBuilderError, OutgoingNews, OutgoingNewsBuilder,
+        OutgoingNewsBuilderError, Rubric, Statistics, Transmitter, TransmitterGroup,
+        TransmitterStatus, TransmitterUsage,
     },
+    validation::{CallIssue, CallValidationReport},
 };
+
+#[cfg(feature = "sqlite")]
+pub use crate::outbox::SqliteOutboxStore;
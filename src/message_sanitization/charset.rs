@@ -0,0 +1,163 @@
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Character set used by the display of a pager.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PagerCharset {
+    /// Plain 7 bit ASCII.
+    #[default]
+    Ascii,
+
+    /// The German 7 bit character set (DIN 66003) used by many alpha pagers, where `[\]{|}~`
+    /// are displayed as `ÄÖÜäöüß`.
+    Din66003,
+}
+
+/// Characters that DIN 66003 places at the positions of ASCII characters.
+const DIN_66003: [(char, char); 7] = [
+    ('Ä', '['),
+    ('Ö', '\\'),
+    ('Ü', ']'),
+    ('ä', '{'),
+    ('ö', '|'),
+    ('ü', '}'),
+    ('ß', '~'),
+];
+
+impl PagerCharset {
+    /// Maps text to the codes that display it correctly on the pager.
+    ///
+    /// For DIN 66003, literal ASCII characters that would be displayed as umlauts are escaped by
+    /// replacing them with similar looking characters (e.g. `[` with `(`).
+    /// Characters not in the character set are left for the non-ASCII policy to handle.
+    pub fn encode(&self, msg: &str) -> String {
+        match self {
+            Self::Ascii => msg.to_string(),
+            Self::Din66003 => msg
+                .chars()
+                .map(|c| match c {
+                    '[' | '{' => '(',
+                    ']' | '}' => ')',
+                    '\\' | '|' => '/',
+                    '~' => '-',
+                    c => DIN_66003
+                        .iter()
+                        .find(|(unicode, _)| *unicode == c)
+                        .map_or(c, |(_, code)| *code),
+                })
+                .collect(),
+        }
+    }
+
+    /// Maps codes received by a pager to the text it displays.
+    pub fn decode(&self, msg: &str) -> String {
+        match self {
+            Self::Ascii => msg.to_string(),
+            Self::Din66003 => msg
+                .chars()
+                .map(|c| {
+                    DIN_66003
+                        .iter()
+                        .find(|(_, code)| *code == c)
+                        .map_or(c, |(unicode, _)| *unicode)
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Character sets of the pagers belonging to each recipient.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RecipientCharsets {
+    default: PagerCharset,
+    recipients: HashMap<String, PagerCharset>,
+}
+
+impl RecipientCharsets {
+    /// Creates a mapping where every recipient uses `default` unless set otherwise.
+    pub fn new(default: PagerCharset) -> Self {
+        Self {
+            default,
+            recipients: HashMap::new(),
+        }
+    }
+
+    pub fn with_recipient(mut self, callsign: &str, charset: PagerCharset) -> Self {
+        self.recipients.insert(callsign.to_lowercase(), charset);
+        self
+    }
+
+    pub fn charset(&self, callsign: &str) -> PagerCharset {
+        self.recipients
+            .get(&callsign.to_lowercase())
+            .copied()
+            .unwrap_or(self.default)
+    }
+
+    /// Groups recipients by character set, so that one call can be sent per character set.
+    pub fn partition(&self, recipients: &[String]) -> BTreeMap<PagerCharset, Vec<String>> {
+        let mut groups: BTreeMap<PagerCharset, Vec<String>> = BTreeMap::new();
+        for recipient in recipients {
+            groups
+                .entry(self.charset(recipient))
+                .or_default()
+                .push(recipient.clone());
+        }
+        groups
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn din_66003_encode() {
+        assert_eq!(
+            PagerCharset::Din66003.encode("Grüße aus München, Ärger in Österreich"),
+            "Gr}~e aus M}nchen, [rger in \\sterreich"
+        );
+    }
+
+    #[test]
+    fn din_66003_escapes_literal_characters() {
+        assert_eq!(
+            PagerCharset::Din66003.encode("[ALERT] {x} a|b c\\d ~5"),
+            "(ALERT) (x) a/b c/d -5"
+        );
+    }
+
+    #[test]
+    fn din_66003_round_trip() {
+        let msg = "Schöne Grüße: ÄÖÜ äöü ß";
+        let charset = PagerCharset::Din66003;
+        assert_eq!(charset.decode(&charset.encode(msg)), msg);
+    }
+
+    #[test]
+    fn ascii_is_unchanged() {
+        assert_eq!(PagerCharset::Ascii.encode("[ä]"), "[ä]");
+        assert_eq!(PagerCharset::Ascii.decode("[ä]"), "[ä]");
+    }
+
+    #[test]
+    fn recipient_charsets() {
+        let charsets = RecipientCharsets::new(PagerCharset::Ascii)
+            .with_recipient("DL1ABC", PagerCharset::Din66003);
+
+        assert_eq!(charsets.charset("dl1abc"), PagerCharset::Din66003);
+        assert_eq!(charsets.charset("m0nxn"), PagerCharset::Ascii);
+
+        let groups = charsets.partition(&[
+            "m0nxn".to_string(),
+            "dl1abc".to_string(),
+            "m0abc".to_string(),
+        ]);
+        assert_eq!(
+            groups[&PagerCharset::Ascii],
+            vec!["m0nxn".to_string(), "m0abc".to_string()]
+        );
+        assert_eq!(groups[&PagerCharset::Din66003], vec!["dl1abc".to_string()]);
+    }
+}
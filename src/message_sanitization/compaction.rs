@@ -0,0 +1,372 @@
+use super::pipeline::SanitizationStep;
+use regex::{Captures, Regex};
+
+/// A single way of shortening a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompactionRule {
+    /// Replace runs of whitespace with a single space and remove leading and trailing whitespace.
+    SqueezeWhitespace,
+
+    /// Replace a whole word (case insensitive) with an abbreviation.
+    Abbreviate {
+        word: CompactionWord,
+        abbreviation: String,
+    },
+
+    /// Remove a whole word (case insensitive) and the whitespace around it.
+    DropWord(CompactionWord),
+}
+
+impl CompactionRule {
+    pub fn abbreviate(word: &str, abbreviation: &str) -> Self {
+        Self::Abbreviate {
+            word: CompactionWord::new(word, "", ""),
+            abbreviation: abbreviation.to_string(),
+        }
+    }
+
+    pub fn drop_word(word: &str) -> Self {
+        Self::DropWord(CompactionWord::new(word, r"(\s*)", r"(\s*)"))
+    }
+
+    /// The built in dictionary: whitespace squeezing, then abbreviations of common alerting
+    /// terms, units and weekdays, then removal of filler words.
+    pub fn default_dictionary() -> Vec<Self> {
+        std::iter::once(Self::SqueezeWhitespace)
+            .chain(
+                ABBREVIATIONS
+                    .iter()
+                    .map(|(word, abbreviation)| Self::abbreviate(word, abbreviation)),
+            )
+            .chain(FILLER_WORDS.iter().map(|word| Self::drop_word(word)))
+            .collect()
+    }
+
+    fn apply(&self, msg: &str) -> String {
+        match self {
+            Self::SqueezeWhitespace => msg.split_whitespace().collect::<Vec<_>>().join(" "),
+            Self::Abbreviate { word, abbreviation } => word
+                .regex
+                .replace_all(msg, regex::NoExpand(abbreviation))
+                .into_owned(),
+            Self::DropWord(word) => word
+                .regex
+                .replace_all(msg, |captures: &Captures| {
+                    // Keep a single space only if the word was between two others
+                    if captures[1].is_empty() || captures[2].is_empty() {
+                        ""
+                    } else {
+                        " "
+                    }
+                })
+                .into_owned(),
+        }
+    }
+}
+
+impl std::fmt::Display for CompactionRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SqueezeWhitespace => write!(f, "squeeze whitespace"),
+            Self::Abbreviate { word, abbreviation } => {
+                write!(f, "abbreviate \"{word}\" to \"{abbreviation}\"")
+            }
+            Self::DropWord(word) => write!(f, "drop \"{word}\""),
+        }
+    }
+}
+
+/// A word matched by a [`CompactionRule`], created by [`CompactionRule::abbreviate`] and
+/// [`CompactionRule::drop_word`].
+///
+/// The regex matching it is compiled once, when the rule is created.
+#[derive(Debug, Clone)]
+pub struct CompactionWord {
+    word: String,
+    regex: Regex,
+}
+
+impl CompactionWord {
+    /// Builds a case insensitive regex matching `word` only where it is not part of a longer
+    /// word, between the patterns `before` and `after`.
+    fn new(word: &str, before: &str, after: &str) -> Self {
+        let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+        let start = if is_word(word.chars().next()) {
+            r"\b"
+        } else {
+            ""
+        };
+        let end = if is_word(word.chars().last()) {
+            r"\b"
+        } else {
+            ""
+        };
+
+        Self {
+            word: word.to_string(),
+            regex: Regex::new(&format!(
+                "(?i){before}{start}{}{end}{after}",
+                regex::escape(word)
+            ))
+            .unwrap(),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.word
+    }
+}
+
+impl PartialEq for CompactionWord {
+    fn eq(&self, other: &Self) -> bool {
+        self.word == other.word
+    }
+}
+
+impl Eq for CompactionWord {}
+
+impl std::fmt::Display for CompactionWord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.word)
+    }
+}
+
+/// The result of compacting a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Compacted {
+    pub text: String,
+
+    /// The rules that changed the message, in the order they were applied.
+    pub applied: Vec<CompactionRule>,
+
+    /// Whether the message now fits within the maximum length.
+    pub fits: bool,
+}
+
+/// Shortens messages using an ordered list of rules, stopping as soon as the message fits.
+///
+/// Rules are tried in priority order and each one replaces every match in the message, so the
+/// result only depends on the message and the rules.
+/// Messages that are still too long after every rule has been applied are returned as they are
+/// for truncation to deal with.
+///
+/// Example:
+/// ```
+/// # use dapnet_api::Compaction;
+/// let compacted = Compaction::new(40)
+///     .with_default_rules()
+///     .compact("[FIRING] Warning: the production database is currently down");
+///
+/// assert_eq!(compacted.text, "[FIRING] WARN: the prod db is down");
+/// assert!(compacted.fits);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Compaction {
+    max_length: usize,
+    rules: Vec<CompactionRule>,
+}
+
+impl Compaction {
+    /// Creates a compaction stage with no rules.
+    pub fn new(max_length: usize) -> Self {
+        Self {
+            max_length,
+            rules: Vec::new(),
+        }
+    }
+
+    /// Adds a rule after those already added, so it is only used if they are not enough.
+    pub fn with_rule(mut self, rule: CompactionRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn with_rules(mut self, rules: impl IntoIterator<Item = CompactionRule>) -> Self {
+        self.rules.extend(rules);
+        self
+    }
+
+    /// Adds the rules from [`CompactionRule::default_dictionary`].
+    pub fn with_default_rules(self) -> Self {
+        self.with_rules(CompactionRule::default_dictionary())
+    }
+
+    pub fn compact(&self, msg: &str) -> Compacted {
+        let fits = |text: &str| text.chars().count() <= self.max_length;
+
+        let mut text = msg.to_string();
+        let mut applied = Vec::new();
+
+        for rule in &self.rules {
+            if fits(&text) {
+                break;
+            }
+
+            let compacted = rule.apply(&text);
+            if compacted != text {
+                text = compacted;
+                applied.push(rule.clone());
+            }
+        }
+
+        Compacted {
+            fits: fits(&text),
+            text,
+            applied,
+        }
+    }
+}
+
+impl SanitizationStep for Compaction {
+    fn apply(&self, msg: String) -> String {
+        self.compact(&msg).text
+    }
+}
+
+const ABBREVIATIONS: &[(&str, &str)] = &[
+    // Alerting
+    ("production", "prod"),
+    ("development", "dev"),
+    ("staging", "stg"),
+    ("critical", "CRIT"),
+    ("warning", "WARN"),
+    ("error", "ERR"),
+    ("information", "info"),
+    ("database", "db"),
+    ("server", "srv"),
+    ("memory", "mem"),
+    ("temperature", "temp"),
+    ("available", "avail"),
+    ("average", "avg"),
+    ("maximum", "max"),
+    ("minimum", "min"),
+    ("message", "msg"),
+    ("received", "rcvd"),
+    ("transmitter", "TX"),
+    ("receiver", "RX"),
+    // Units
+    ("percent", "%"),
+    ("milliseconds", "ms"),
+    ("seconds", "s"),
+    ("minutes", "min"),
+    ("hours", "h"),
+    ("kilobytes", "KB"),
+    ("megabytes", "MB"),
+    ("gigabytes", "GB"),
+    ("terabytes", "TB"),
+    ("degrees", "deg"),
+    // Weekdays
+    ("Monday", "Mon"),
+    ("Tuesday", "Tue"),
+    ("Wednesday", "Wed"),
+    ("Thursday", "Thu"),
+    ("Friday", "Fri"),
+    ("Saturday", "Sat"),
+    ("Sunday", "Sun"),
+];
+
+/// Filler words, least useful first.
+const FILLER_WORDS: &[&str] = &[
+    "please",
+    "currently",
+    "the",
+    "an",
+    "a",
+    "has",
+    "have",
+    "been",
+    "is",
+    "are",
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn message_that_fits_is_unchanged() {
+        let compacted = Compaction::new(80)
+            .with_default_rules()
+            .compact("Warning:  production   is fine");
+        assert_eq!(compacted.text, "Warning:  production   is fine");
+        assert!(compacted.applied.is_empty());
+        assert!(compacted.fits);
+    }
+
+    #[test]
+    fn only_needed_rules_are_applied() {
+        let compaction = Compaction::new(20)
+            .with_rule(CompactionRule::SqueezeWhitespace)
+            .with_rule(CompactionRule::abbreviate("production", "prod"))
+            .with_rule(CompactionRule::abbreviate("warning", "WARN"));
+
+        let compacted = compaction.compact("Warning: production   down");
+        assert_eq!(compacted.text, "Warning: prod down");
+        assert_eq!(
+            compacted.applied,
+            vec![
+                CompactionRule::SqueezeWhitespace,
+                CompactionRule::abbreviate("production", "prod"),
+            ]
+        );
+        assert!(compacted.fits);
+    }
+
+    #[test]
+    fn rules_that_do_not_match_are_not_reported() {
+        let compacted = Compaction::new(5)
+            .with_rule(CompactionRule::abbreviate("production", "prod"))
+            .compact("nothing to see here");
+        assert!(compacted.applied.is_empty());
+        assert!(!compacted.fits);
+    }
+
+    #[test]
+    fn abbreviations_match_whole_words_only() {
+        let rule = CompactionRule::abbreviate("error", "ERR");
+        assert_eq!(rule.apply("Error: errors in error"), "ERR: errors in ERR");
+        assert_eq!(
+            CompactionRule::abbreviate("percent", "%").apply("50 Percent"),
+            "50 %"
+        );
+    }
+
+    #[test]
+    fn drop_word() {
+        let rule = CompactionRule::drop_word("the");
+        assert_eq!(rule.apply("The disk the, then the"), "disk, then");
+        assert_eq!(rule.apply("theme"), "theme");
+    }
+
+    #[test]
+    fn default_rules_report() {
+        let compacted = Compaction::new(50).with_default_rules().compact(
+            "[FIRING:1] Warning: the temperature of the production server has been above \
+             80 degrees for 15 minutes since Monday",
+        );
+        assert_eq!(
+            compacted.text,
+            "[FIRING:1] WARN: temp of prod srv above 80 deg for 15 min since Mon"
+        );
+        assert_eq!(
+            compacted
+                .applied
+                .iter()
+                .map(|rule| rule.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "abbreviate \"production\" to \"prod\"",
+                "abbreviate \"warning\" to \"WARN\"",
+                "abbreviate \"server\" to \"srv\"",
+                "abbreviate \"temperature\" to \"temp\"",
+                "abbreviate \"minutes\" to \"min\"",
+                "abbreviate \"degrees\" to \"deg\"",
+                "abbreviate \"Monday\" to \"Mon\"",
+                "drop \"the\"",
+                "drop \"has\"",
+                "drop \"been\"",
+            ]
+        );
+        assert!(!compacted.fits);
+    }
+}
@@ -0,0 +1,474 @@
+mod charset;
+mod compaction;
+mod numeric;
+mod pipeline;
+mod split;
+mod steps;
+mod transliteration;
+
+pub use self::{
+    charset::{PagerCharset, RecipientCharsets},
+    compaction::{Compacted, Compaction, CompactionRule, CompactionWord},
+    numeric::{MAX_NUMERIC_LENGTH, NumericAlphabet},
+    pipeline::{SanitizationPipeline, SanitizationStep},
+    split::{
+        MessageSplitOptions, MessageSplitOptionsBuilder, MessageSplitOptionsBuilderError,
+        SplitMarkerPosition, split_message,
+    },
+    steps::{
+        CollapseWhitespace, ElideUrls, EmojiToText, NormalizeControlCharacters, RegexReplace,
+        Truncate,
+    },
+    transliteration::Transliteration,
+};
+
+use derive_builder::Builder;
+
+/// Options that control exactly how messages are sanitized.
+#[derive(Debug, Clone, PartialEq, Eq, Builder)]
+pub struct MessageSanitizationOptions {
+    /// Maximum message length, in characters.
+    ///
+    /// DAPNET enforces an upper limit of 80, however shorter limits can be set if desired.
+    #[builder(default = "80")]
+    max_length: usize,
+
+    /// The string to include at the end of a truncated message.
+    #[builder(default = "\"...\".to_string()")]
+    ellipses: String,
+
+    /// What to do with non-ASCII characters in the message.
+    #[builder(default = "MessageSanitizationNonAsciiPolicy::ReplaceWith('?')")]
+    non_ascii_policy: MessageSanitizationNonAsciiPolicy,
+
+    /// Character set of the receiving pagers.
+    ///
+    /// Characters that the character set can display are mapped before the non-ASCII policy is
+    /// applied.
+    #[builder(default)]
+    charset: PagerCharset,
+
+    /// Rules used to shorten messages that are too long before falling back to truncation.
+    ///
+    /// See [`Compaction`], by default no compaction is done.
+    #[builder(default)]
+    compaction: Vec<CompactionRule>,
+}
+
+impl Default for MessageSanitizationOptions {
+    fn default() -> Self {
+        Self {
+            max_length: 80,
+            ellipses: "...".to_string(),
+            non_ascii_policy: MessageSanitizationNonAsciiPolicy::ReplaceWith('?'),
+            charset: PagerCharset::Ascii,
+            compaction: Vec::new(),
+        }
+    }
+}
+
+impl MessageSanitizationOptions {
+    /// Creates a copy of these options for pagers using a different character set.
+    pub fn with_charset(&self, charset: PagerCharset) -> Self {
+        Self {
+            charset,
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MessageSanitizationNonAsciiPolicy {
+    /// Don't attempt to change any non-ASCII characters and let DAPNET handle them.
+    DoNothing,
+
+    /// Just remove all non-ASCII characters.
+    Remove,
+
+    /// Replace each non-ASCII character with a specified char.
+    ReplaceWith(char),
+
+    /// Replace non-ASCII characters with readable ASCII equivalents (e.g. "ä" with "ae").
+    Transliterate(Transliteration),
+}
+
+/// Sanitize a message to ensure it is suitable for both POCSAG and DAPNET.
+///
+/// This applies the preset [`SanitizationPipeline`] described by `options`, build a pipeline
+/// directly for more control over the steps.
+pub fn sanitize_message(msg: String, options: &MessageSanitizationOptions) -> String {
+    SanitizationPipeline::from(options).apply(msg)
+}
+
+/// Sanitize a message in the same way as [`sanitize_message`], also returning the number of
+/// characters by which it exceeded the maximum length before it was truncated.
+pub(crate) fn sanitize_message_with_overflow(
+    msg: String,
+    options: &MessageSanitizationOptions,
+) -> (String, usize) {
+    let msg = SanitizationPipeline::without_truncation(options).apply(msg);
+    let overflow = msg.chars().count().saturating_sub(options.max_length);
+    (
+        truncate(msg, options.max_length, &options.ellipses),
+        overflow,
+    )
+}
+
+/// Truncates a message to at most `max_length` characters, ending it with `ellipses` if anything
+/// was removed.
+///
+/// Length is counted in characters, not bytes, so this never splits a multibyte character.
+/// If `ellipses` alone is longer than `max_length` then it is itself truncated.
+pub(crate) fn truncate(msg: String, max_length: usize, ellipses: &str) -> String {
+    if msg.chars().count() <= max_length {
+        return msg;
+    }
+
+    let ellipses: String = ellipses.chars().take(max_length).collect();
+    let keep = max_length - ellipses.chars().count();
+
+    msg.chars().take(keep).chain(ellipses.chars()).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn options_builder_default_matches_options_default() {
+        let a = MessageSanitizationOptionsBuilder::default()
+            .build()
+            .unwrap();
+        let b = MessageSanitizationOptions::default();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn build_options() {
+        let options = MessageSanitizationOptionsBuilder::default()
+            .ellipses("~~~".to_string())
+            .max_length(50)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            options,
+            MessageSanitizationOptions {
+                max_length: 50,
+                ellipses: "~~~".to_string(),
+                non_ascii_policy: MessageSanitizationNonAsciiPolicy::ReplaceWith('?'),
+                charset: PagerCharset::Ascii,
+                compaction: Vec::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn perfectly_valid_message_with_default_settings() {
+        let msg = "This message is less than 80 chars, woo.".to_string();
+        let sanitized = sanitize_message(msg.clone(), &MessageSanitizationOptions::default());
+        assert_eq!(sanitized, msg);
+    }
+
+    #[test]
+    fn message_that_is_too_long() {
+        let msg = "This message is too long, oh dear.".to_string();
+        let sanitized = sanitize_message(
+            msg,
+            &MessageSanitizationOptions {
+                max_length: 20,
+                ellipses: "...".to_string(),
+                non_ascii_policy: MessageSanitizationNonAsciiPolicy::DoNothing,
+                charset: PagerCharset::Ascii,
+                compaction: Vec::new(),
+            },
+        );
+        assert_eq!(sanitized, "This message is t...");
+        assert_eq!(sanitized.len(), 20);
+    }
+
+    #[test]
+    fn message_with_non_ascii_do_nothing() {
+        let msg = "→ This message has non-ascii chars ❤, oh dear.".to_string();
+        let sanitized = sanitize_message(
+            msg.clone(),
+            &MessageSanitizationOptions {
+                max_length: 80,
+                ellipses: "...".to_string(),
+                non_ascii_policy: MessageSanitizationNonAsciiPolicy::DoNothing,
+                charset: PagerCharset::Ascii,
+                compaction: Vec::new(),
+            },
+        );
+        assert_eq!(sanitized, msg);
+    }
+
+    #[test]
+    fn message_with_non_ascii_remove() {
+        let msg = "→ This message has non-ascii chars ❤, oh dear.".to_string();
+        let sanitized = sanitize_message(
+            msg,
+            &MessageSanitizationOptions {
+                max_length: 80,
+                ellipses: "...".to_string(),
+                non_ascii_policy: MessageSanitizationNonAsciiPolicy::Remove,
+                charset: PagerCharset::Ascii,
+                compaction: Vec::new(),
+            },
+        );
+        assert_eq!(sanitized, " This message has non-ascii chars , oh dear.");
+    }
+
+    #[test]
+    fn message_with_non_ascii_replace_with() {
+        let msg = "→ This message has non-ascii chars ❤, oh dear.".to_string();
+        let sanitized = sanitize_message(
+            msg,
+            &MessageSanitizationOptions {
+                max_length: 80,
+                ellipses: "...".to_string(),
+                non_ascii_policy: MessageSanitizationNonAsciiPolicy::ReplaceWith('_'),
+                charset: PagerCharset::Ascii,
+                compaction: Vec::new(),
+            },
+        );
+        assert_eq!(sanitized, "_ This message has non-ascii chars _, oh dear.");
+    }
+
+    #[test]
+    fn message_with_non_ascii_transliterate() {
+        let msg = "Grüße aus München → 20°C ❤".to_string();
+        let sanitized = sanitize_message(
+            msg,
+            &MessageSanitizationOptions {
+                max_length: 80,
+                ellipses: "...".to_string(),
+                non_ascii_policy: MessageSanitizationNonAsciiPolicy::Transliterate(
+                    Transliteration::default().with_override('❤', "<3"),
+                ),
+                charset: PagerCharset::Ascii,
+                compaction: Vec::new(),
+            },
+        );
+        assert_eq!(sanitized, "Gruesse aus Muenchen -> 20degC <3");
+    }
+
+    #[test]
+    fn transliteration_and_message_truncation_interoperate_correctly() {
+        let msg = "ääääää".to_string();
+        let sanitized = sanitize_message(
+            msg,
+            &MessageSanitizationOptions {
+                max_length: 8,
+                ellipses: "...".to_string(),
+                non_ascii_policy: MessageSanitizationNonAsciiPolicy::Transliterate(
+                    Transliteration::default(),
+                ),
+                charset: PagerCharset::Ascii,
+                compaction: Vec::new(),
+            },
+        );
+        assert_eq!(sanitized, "aeaea...");
+    }
+
+    #[test]
+    fn message_with_din_66003_charset() {
+        let msg = "[WX] Grüße aus München ❤".to_string();
+        let sanitized = sanitize_message(
+            msg,
+            &MessageSanitizationOptionsBuilder::default()
+                .charset(PagerCharset::Din66003)
+                .build()
+                .unwrap(),
+        );
+        assert_eq!(sanitized, "(WX) Gr}~e aus M}nchen ?");
+    }
+
+    #[test]
+    fn message_is_compacted_before_truncation() {
+        let options = MessageSanitizationOptionsBuilder::default()
+            .max_length(30)
+            .compaction(CompactionRule::default_dictionary())
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            sanitize_message("Warning: production is down".to_string(), &options),
+            "Warning: production is down"
+        );
+        assert_eq!(
+            sanitize_message(
+                "Warning: the production database is down again".to_string(),
+                &options
+            ),
+            "WARN: prod db is down again"
+        );
+        assert_eq!(
+            sanitize_message(
+                "Critical: production database replication lag is above threshold".to_string(),
+                &options
+            ),
+            "CRIT: prod db replication l..."
+        );
+    }
+
+    #[test]
+    fn non_ascii_removal_and_message_truncation_interoperate_correctly() {
+        let msg = "❤❤❤❤❤123456789".to_string();
+        let sanitized = sanitize_message(
+            msg,
+            &MessageSanitizationOptions {
+                max_length: 8,
+                ellipses: "...".to_string(),
+                non_ascii_policy: MessageSanitizationNonAsciiPolicy::Remove,
+                charset: PagerCharset::Ascii,
+                compaction: Vec::new(),
+            },
+        );
+        assert_eq!(sanitized, "12345...");
+    }
+
+    #[test]
+    fn multibyte_message_that_is_too_long() {
+        let msg = "Grüße aus München, schönes Wetter heute".to_string();
+        let sanitized = sanitize_message(
+            msg,
+            &MessageSanitizationOptions {
+                max_length: 20,
+                ellipses: "...".to_string(),
+                non_ascii_policy: MessageSanitizationNonAsciiPolicy::DoNothing,
+                charset: PagerCharset::Ascii,
+                compaction: Vec::new(),
+            },
+        );
+        assert_eq!(sanitized, "Grüße aus München...");
+        assert_eq!(sanitized.chars().count(), 20);
+    }
+
+    #[test]
+    fn non_ascii_ellipses() {
+        let msg = "This message is too long, oh dear.".to_string();
+        let sanitized = sanitize_message(
+            msg,
+            &MessageSanitizationOptions {
+                max_length: 20,
+                ellipses: "…".to_string(),
+                non_ascii_policy: MessageSanitizationNonAsciiPolicy::DoNothing,
+                charset: PagerCharset::Ascii,
+                compaction: Vec::new(),
+            },
+        );
+        assert_eq!(sanitized, "This message is too…");
+        assert_eq!(sanitized.chars().count(), 20);
+    }
+
+    #[test]
+    fn ellipses_longer_than_max_length() {
+        let msg = "This message is too long, oh dear.".to_string();
+        let sanitized = sanitize_message(
+            msg,
+            &MessageSanitizationOptions {
+                max_length: 2,
+                ellipses: "...".to_string(),
+                non_ascii_policy: MessageSanitizationNonAsciiPolicy::DoNothing,
+                charset: PagerCharset::Ascii,
+                compaction: Vec::new(),
+            },
+        );
+        assert_eq!(sanitized, "..");
+    }
+
+    fn non_ascii_policy() -> impl Strategy<Value = MessageSanitizationNonAsciiPolicy> {
+        prop_oneof![
+            Just(MessageSanitizationNonAsciiPolicy::DoNothing),
+            Just(MessageSanitizationNonAsciiPolicy::Remove),
+            any::<char>().prop_map(MessageSanitizationNonAsciiPolicy::ReplaceWith),
+            Just(MessageSanitizationNonAsciiPolicy::Transliterate(
+                Transliteration::default()
+            )),
+        ]
+    }
+
+    fn options() -> impl Strategy<Value = MessageSanitizationOptions> {
+        (
+            0usize..100,
+            ".{0,5}",
+            non_ascii_policy(),
+            prop_oneof![Just(PagerCharset::Ascii), Just(PagerCharset::Din66003)],
+        )
+            .prop_map(|(max_length, ellipses, non_ascii_policy, charset)| {
+                MessageSanitizationOptions {
+                    max_length,
+                    ellipses,
+                    non_ascii_policy,
+                    charset,
+                    compaction: Vec::new(),
+                }
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn sanitized_message_never_exceeds_max_length(msg in ".{0,200}", options in options()) {
+            let sanitized = sanitize_message(msg, &options);
+            prop_assert!(sanitized.chars().count() <= options.max_length);
+        }
+
+        #[test]
+        fn short_message_is_not_truncated(msg in ".{0,200}", max_length in 0usize..300) {
+            let options = MessageSanitizationOptions {
+                max_length,
+                ellipses: "...".to_string(),
+                non_ascii_policy: MessageSanitizationNonAsciiPolicy::DoNothing,
+                charset: PagerCharset::Ascii,
+                compaction: Vec::new(),
+            };
+            let sanitized = sanitize_message(msg.clone(), &options);
+            if msg.chars().count() <= max_length {
+                prop_assert_eq!(sanitized, msg);
+            } else {
+                prop_assert_eq!(sanitized.chars().count(), max_length);
+            }
+        }
+
+        #[test]
+        fn truncated_message_keeps_prefix(msg in ".{0,200}", options in options()) {
+            let sanitized = sanitize_message(msg.clone(), &options);
+            let ellipses: String = options.ellipses.chars().take(options.max_length).collect();
+            if let Some(prefix) = sanitized.strip_suffix(&ellipses) {
+                let filtered = sanitize_message(
+                    msg,
+                    &MessageSanitizationOptions {
+                        max_length: usize::MAX,
+                        ..options.clone()
+                    },
+                );
+                prop_assert!(filtered.starts_with(prefix));
+            }
+        }
+
+        #[test]
+        fn ascii_policies_produce_ascii(
+            msg in ".{0,200}",
+            max_length in 0usize..100,
+            replacement in proptest::char::range('\0', '\x7f'),
+            policy in 0..3,
+        ) {
+            let options = MessageSanitizationOptions {
+                max_length,
+                ellipses: "...".to_string(),
+                non_ascii_policy: match policy {
+                    0 => MessageSanitizationNonAsciiPolicy::Remove,
+                    1 => MessageSanitizationNonAsciiPolicy::ReplaceWith(replacement),
+                    _ => MessageSanitizationNonAsciiPolicy::Transliterate(
+                        Transliteration::default(),
+                    ),
+                },
+                charset: PagerCharset::Ascii,
+                compaction: Vec::new(),
+            };
+            prop_assert!(sanitize_message(msg, &options).is_ascii());
+        }
+    }
+}
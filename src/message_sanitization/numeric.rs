@@ -0,0 +1,103 @@
+use super::pipeline::SanitizationStep;
+use crate::pocsag::NUMERIC_ALPHABET;
+
+/// Maximum length of a message sent to a numeric pager, in characters.
+///
+/// This is a conservative limit, many numeric pagers cannot store or display longer messages.
+pub const MAX_NUMERIC_LENGTH: usize = 20;
+
+/// The POCSAG numeric alphabet: `0-9`, space, `U`, `-`, `[`, `]` and `*`.
+///
+/// Used as a [`SanitizationStep`] it converts text with [`NumericAlphabet::convert`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumericAlphabet;
+
+impl NumericAlphabet {
+    pub fn contains(c: char) -> bool {
+        NUMERIC_ALPHABET.contains(&c)
+    }
+
+    /// Checks that a message only uses the numeric alphabet and is not longer than
+    /// [`MAX_NUMERIC_LENGTH`].
+    pub fn is_valid(msg: &str) -> bool {
+        msg.chars().count() <= MAX_NUMERIC_LENGTH && msg.chars().all(Self::contains)
+    }
+
+    /// Maps a message onto the numeric alphabet.
+    ///
+    /// Brackets become `[` or `]`, separators such as `.`, `:` and `/` become `-`, `+` and `#`
+    /// become `*` and any other whitespace becomes a space.
+    /// Characters with no equivalent (e.g. letters other than `U`) are removed.
+    pub fn convert(msg: &str) -> String {
+        msg.chars()
+            .filter_map(|c| match c {
+                c if Self::contains(c) => Some(c),
+                'u' => Some('U'),
+                '(' | '{' | '<' => Some('['),
+                ')' | '}' | '>' => Some(']'),
+                '.' | ',' | ':' | ';' | '/' | '\\' | '_' | '–' | '—' => Some('-'),
+                '+' | '#' => Some('*'),
+                c if c.is_whitespace() => Some(' '),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Converts a message and limits it to [`MAX_NUMERIC_LENGTH`].
+    ///
+    /// No ellipses are added as there is nothing suitable in the numeric alphabet.
+    pub fn sanitize(msg: &str) -> String {
+        Self::convert(msg)
+            .split(' ')
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+            .chars()
+            .take(MAX_NUMERIC_LENGTH)
+            .collect()
+    }
+}
+
+impl SanitizationStep for NumericAlphabet {
+    fn apply(&self, msg: String) -> String {
+        Self::convert(&msg)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn valid() {
+        assert!(NumericAlphabet::is_valid("0123456789*U -]["));
+        assert!(NumericAlphabet::is_valid(""));
+        assert!(!NumericAlphabet::is_valid("123a"));
+        assert!(!NumericAlphabet::is_valid("u"));
+        assert!(!NumericAlphabet::is_valid(
+            &"1".repeat(MAX_NUMERIC_LENGTH + 1)
+        ));
+    }
+
+    #[test]
+    fn convert() {
+        assert_eq!(
+            NumericAlphabet::convert("Call (0161) 555.1234 at 12:30, +44\tuuu"),
+            " [0161] 555-1234  12-30- *44 UUU"
+        );
+    }
+
+    #[test]
+    fn sanitize() {
+        assert_eq!(
+            NumericAlphabet::sanitize("Call (0161) 555.1234 at 12:30"),
+            "[0161] 555-1234 12-3"
+        );
+    }
+
+    #[test]
+    fn sanitized_message_is_valid() {
+        let msg = "Grüße, 1 2 3 – ❤ (DAPNET) 0800/123456 #9";
+        assert!(NumericAlphabet::is_valid(&NumericAlphabet::sanitize(msg)));
+    }
+}
@@ -0,0 +1,131 @@
+use super::{Compaction, MessageSanitizationOptions, Truncate};
+
+/// A single transformation applied to a message as part of a [`SanitizationPipeline`].
+///
+/// Implemented for closures, so simple custom steps can be written inline:
+/// ```
+/// # use dapnet_api::SanitizationPipeline;
+/// let pipeline = SanitizationPipeline::new().with_step(|msg: String| msg.to_uppercase());
+/// assert_eq!(pipeline.apply("hello".to_string()), "HELLO");
+/// ```
+pub trait SanitizationStep: Send + Sync {
+    fn apply(&self, msg: String) -> String;
+}
+
+impl<F> SanitizationStep for F
+where
+    F: Fn(String) -> String + Send + Sync,
+{
+    fn apply(&self, msg: String) -> String {
+        self(msg)
+    }
+}
+
+/// An ordered list of steps that are applied to a message in turn.
+///
+/// Example:
+/// ```
+/// # use dapnet_api::{
+/// #     CollapseWhitespace, ElideUrls, EmojiToText, MessageSanitizationNonAsciiPolicy,
+/// #     NormalizeControlCharacters, SanitizationPipeline, Truncate,
+/// # };
+/// let pipeline = SanitizationPipeline::new()
+///     .with_step(NormalizeControlCharacters::default())
+///     .with_step(ElideUrls::KeepHost)
+///     .with_step(EmojiToText::default())
+///     .with_step(MessageSanitizationNonAsciiPolicy::ReplaceWith('?'))
+///     .with_step(CollapseWhitespace)
+///     .with_step(Truncate::new(80, "...").at_word_boundary());
+///
+/// assert_eq!(
+///     pipeline.apply("🔥 Disk full\n  see https://grafana.example.com/d/abc?x=1".to_string()),
+///     "(fire) Disk full see grafana.example.com",
+/// );
+/// ```
+#[derive(Default)]
+pub struct SanitizationPipeline {
+    steps: Vec<Box<dyn SanitizationStep>>,
+}
+
+impl std::fmt::Debug for SanitizationPipeline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SanitizationPipeline")
+            .field("steps", &self.steps.len())
+            .finish()
+    }
+}
+
+impl SanitizationPipeline {
+    /// Creates an empty pipeline, which leaves messages unchanged.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a step to the end of the pipeline.
+    pub fn with_step<S: SanitizationStep + 'static>(mut self, step: S) -> Self {
+        self.push(step);
+        self
+    }
+
+    /// Adds a step to the end of the pipeline.
+    pub fn push<S: SanitizationStep + 'static>(&mut self, step: S) {
+        self.steps.push(Box::new(step));
+    }
+
+    /// The preset pipeline described by `options`, stopping before truncation.
+    pub(crate) fn without_truncation(options: &MessageSanitizationOptions) -> Self {
+        Self::new()
+            .with_step(options.charset)
+            .with_step(options.non_ascii_policy.clone())
+            .with_step(Compaction::new(options.max_length).with_rules(options.compaction.clone()))
+    }
+
+    /// Runs a message through each step in order.
+    pub fn apply(&self, msg: String) -> String {
+        self.steps.iter().fold(msg, |msg, step| step.apply(msg))
+    }
+}
+
+/// The pipeline used by [`sanitize_message`](super::sanitize_message): character set mapping,
+/// then the non-ASCII policy, then compaction, then truncation.
+impl From<&MessageSanitizationOptions> for SanitizationPipeline {
+    fn from(options: &MessageSanitizationOptions) -> Self {
+        Self::without_truncation(options)
+            .with_step(Truncate::new(options.max_length, &options.ellipses))
+    }
+}
+
+impl SanitizationStep for SanitizationPipeline {
+    fn apply(&self, msg: String) -> String {
+        SanitizationPipeline::apply(self, msg)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{CollapseWhitespace, PagerCharset};
+
+    #[test]
+    fn empty_pipeline_does_nothing() {
+        let msg = "  Grüße\n❤ ".to_string();
+        assert_eq!(SanitizationPipeline::new().apply(msg.clone()), msg);
+    }
+
+    #[test]
+    fn steps_are_applied_in_order() {
+        let pipeline = SanitizationPipeline::new()
+            .with_step(|msg: String| format!("{msg}a"))
+            .with_step(|msg: String| format!("{msg}b"));
+        assert_eq!(pipeline.apply(String::new()), "ab");
+    }
+
+    #[test]
+    fn preset_can_be_extended() {
+        let mut pipeline = SanitizationPipeline::new().with_step(CollapseWhitespace);
+        pipeline.push(SanitizationPipeline::from(
+            &MessageSanitizationOptions::default().with_charset(PagerCharset::Din66003),
+        ));
+        assert_eq!(pipeline.apply("  [WX]   Grüße  ".to_string()), "(WX) Gr}~e");
+    }
+}
@@ -0,0 +1,322 @@
+use super::truncate;
+use derive_builder::Builder;
+
+/// Where the part marker (e.g. "(1/3)") is placed in each part.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitMarkerPosition {
+    Prefix,
+    Suffix,
+}
+
+/// Options that control how long messages are split into multiple parts.
+#[derive(Debug, Clone, PartialEq, Eq, Builder)]
+#[builder(build_fn(validate = "Self::validate"))]
+pub struct MessageSplitOptions {
+    /// Maximum length of each part in characters, including the part marker.
+    #[builder(default = "80")]
+    max_length: usize,
+
+    /// Maximum number of parts, any remaining text is truncated.
+    #[builder(default = "3")]
+    max_parts: usize,
+
+    #[builder(default = "SplitMarkerPosition::Suffix")]
+    marker_position: SplitMarkerPosition,
+
+    /// The string to include at the end of the final part if the text had to be truncated.
+    #[builder(default = "\"...\".to_string()")]
+    ellipses: String,
+}
+
+impl Default for MessageSplitOptions {
+    fn default() -> Self {
+        Self {
+            max_length: 80,
+            max_parts: 3,
+            marker_position: SplitMarkerPosition::Suffix,
+            ellipses: "...".to_string(),
+        }
+    }
+}
+
+impl MessageSplitOptionsBuilder {
+    fn validate(&self) -> Result<(), String> {
+        if self.max_parts == Some(0) {
+            Err("Maximum number of parts must be at least 1".to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl MessageSplitOptions {
+    /// Creates a copy of these options with `length` fewer characters available in each part.
+    pub(crate) fn reserve(&self, length: usize) -> Self {
+        Self {
+            max_length: self.max_length.saturating_sub(length),
+            ..self.clone()
+        }
+    }
+
+    fn marker(&self, part: usize, count: usize) -> String {
+        match self.marker_position {
+            SplitMarkerPosition::Prefix => format!("({part}/{count}) "),
+            SplitMarkerPosition::Suffix => format!(" ({part}/{count})"),
+        }
+    }
+
+    fn with_marker(&self, text: &str, part: usize, count: usize) -> String {
+        match self.marker_position {
+            SplitMarkerPosition::Prefix => format!("{}{text}", self.marker(part, count)),
+            SplitMarkerPosition::Suffix => format!("{text}{}", self.marker(part, count)),
+        }
+    }
+}
+
+/// Split a message into parts that each fit within the maximum length.
+///
+/// Text is split on word boundaries where possible, words longer than a whole part are split
+/// mid-word.
+/// When more than one part is needed each part is marked with its position, e.g. "(1/3)", and the
+/// marker counts towards the maximum length.
+/// Whitespace between words is collapsed into a single space.
+///
+/// Example:
+/// ```
+/// # use dapnet_api::{MessageSplitOptionsBuilder, split_message};
+/// let parts = split_message(
+///     "The quick brown fox jumps over the dog",
+///     &MessageSplitOptionsBuilder::default()
+///         .max_length(25)
+///         .build()
+///         .unwrap(),
+/// );
+/// assert_eq!(
+///     parts,
+///     vec!["The quick brown fox (1/2)", "jumps over the dog (2/2)"],
+/// );
+/// ```
+pub fn split_message(text: &str, options: &MessageSplitOptions) -> Vec<String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let joined = words.join(" ");
+
+    if joined.chars().count() <= options.max_length || options.max_parts == 1 {
+        return vec![truncate(joined, options.max_length, &options.ellipses)];
+    }
+
+    // The marker length depends on the number of digits in the part count, so start by assuming
+    // a single digit and retry with a longer marker if more parts are needed
+    let mut count = 2;
+    loop {
+        let marker_length = options.marker(count, count).chars().count();
+        let capacity = options.max_length.saturating_sub(marker_length);
+        if capacity == 0 {
+            return vec![truncate(joined, options.max_length, &options.ellipses)];
+        }
+
+        let mut chunks = chunk_words(&words, capacity);
+        let parts = chunks.len().min(options.max_parts);
+        if digits(parts) > digits(count) {
+            count = parts;
+            continue;
+        }
+
+        if chunks.len() > parts {
+            let remainder = chunks.split_off(parts - 1).join(" ");
+            chunks.push(truncate(remainder, capacity, &options.ellipses));
+        }
+
+        return mark_parts(chunks, options);
+    }
+}
+
+fn digits(n: usize) -> usize {
+    n.to_string().len()
+}
+
+fn mark_parts(parts: Vec<String>, options: &MessageSplitOptions) -> Vec<String> {
+    let count = parts.len();
+    if count == 1 {
+        return parts;
+    }
+
+    parts
+        .iter()
+        .enumerate()
+        .map(|(i, part)| options.with_marker(part, i + 1, count))
+        .collect()
+}
+
+/// Greedily packs words into chunks of at most `capacity` characters.
+fn chunk_words(words: &[&str], capacity: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_length = 0;
+
+    for word in words {
+        let mut word: Vec<char> = word.chars().collect();
+
+        // Start a new chunk if the word does not fit in the current one
+        if current_length > 0 && current_length + 1 + word.len() > capacity {
+            chunks.push(std::mem::take(&mut current));
+            current_length = 0;
+        }
+
+        if current_length > 0 {
+            current.push(' ');
+            current_length += 1;
+        }
+
+        // Split words that are longer than a whole chunk
+        while current_length + word.len() > capacity {
+            let split = capacity - current_length;
+            current.extend(&word[..split]);
+            chunks.push(std::mem::take(&mut current));
+            current_length = 0;
+            word.drain(..split);
+        }
+
+        current.extend(&word);
+        current_length += word.len();
+    }
+
+    if current_length > 0 {
+        chunks.push(current);
+    }
+
+    chunks
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn options(max_length: usize, max_parts: usize) -> MessageSplitOptions {
+        MessageSplitOptionsBuilder::default()
+            .max_length(max_length)
+            .max_parts(max_parts)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn options_builder_default_matches_options_default() {
+        let a = MessageSplitOptionsBuilder::default().build().unwrap();
+        let b = MessageSplitOptions::default();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn options_builder_rejects_zero_parts() {
+        assert!(
+            MessageSplitOptionsBuilder::default()
+                .max_parts(0)
+                .build()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn short_message_is_not_split() {
+        assert_eq!(
+            split_message("Short  message", &options(80, 3)),
+            vec!["Short message"]
+        );
+    }
+
+    #[test]
+    fn split_on_word_boundaries() {
+        let parts = split_message(
+            "Disk usage on db-01 is above 90%, cleanup required before the nightly backup runs at 02:00 UTC",
+            &options(40, 5),
+        );
+        assert_eq!(
+            parts,
+            vec![
+                "Disk usage on db-01 is above 90%, (1/3)",
+                "cleanup required before the (2/3)",
+                "nightly backup runs at 02:00 UTC (3/3)",
+            ]
+        );
+    }
+
+    #[test]
+    fn prefix_marker() {
+        let parts = split_message(
+            "one two three four five six",
+            &MessageSplitOptionsBuilder::default()
+                .max_length(16)
+                .marker_position(SplitMarkerPosition::Prefix)
+                .build()
+                .unwrap(),
+        );
+        assert_eq!(
+            parts,
+            vec!["(1/3) one two", "(2/3) three four", "(3/3) five six"]
+        );
+    }
+
+    #[test]
+    fn long_words_are_split() {
+        let parts = split_message("abcdefghijklmnopqrstuvwxyz", &options(16, 5));
+        assert_eq!(
+            parts,
+            vec!["abcdefghij (1/3)", "klmnopqrst (2/3)", "uvwxyz (3/3)"]
+        );
+    }
+
+    #[test]
+    fn too_many_parts_are_truncated() {
+        let parts = split_message(
+            "one two three four five six seven eight nine ten",
+            &options(16, 2),
+        );
+        assert_eq!(parts, vec!["one two (1/2)", "three f... (2/2)"]);
+    }
+
+    #[test]
+    fn marker_length_grows_with_part_count() {
+        let text = (0..40)
+            .map(|i| format!("w{i:02}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let parts = split_message(&text, &options(14, 50));
+
+        assert_eq!(parts.len(), 40);
+        assert_eq!(parts[0], "w00 (1/40)");
+        assert!(parts.iter().all(|p| p.chars().count() <= 14));
+    }
+
+    proptest! {
+        #[test]
+        fn parts_never_exceed_limits(
+            text in ".{0,400}",
+            max_length in 0usize..100,
+            max_parts in 1usize..15,
+        ) {
+            let parts = split_message(&text, &options(max_length, max_parts));
+            prop_assert!(parts.len() <= max_parts);
+            prop_assert!(parts.iter().all(|p| p.chars().count() <= max_length));
+        }
+
+        #[test]
+        fn text_is_preserved_when_parts_allow(
+            text in "[a-z]{1,12}( [a-z]{1,12}){0,30}",
+        ) {
+            let options = options(40, 100);
+            let parts = split_message(&text, &options);
+
+            let rejoined = if parts.len() == 1 {
+                parts[0].clone()
+            } else {
+                parts
+                    .iter()
+                    .map(|p| p.rsplit_once(" (").unwrap().0)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            };
+            prop_assert_eq!(rejoined, text);
+        }
+    }
+}
@@ -0,0 +1,372 @@
+use super::{
+    MessageSanitizationNonAsciiPolicy, PagerCharset, Transliteration, pipeline::SanitizationStep,
+    truncate,
+};
+use regex::Regex;
+use std::{collections::BTreeMap, sync::LazyLock};
+
+impl SanitizationStep for PagerCharset {
+    fn apply(&self, msg: String) -> String {
+        self.encode(&msg)
+    }
+}
+
+impl SanitizationStep for MessageSanitizationNonAsciiPolicy {
+    fn apply(&self, msg: String) -> String {
+        match self {
+            Self::DoNothing => msg,
+            Self::Remove => msg.chars().filter(|c| c.is_ascii()).collect(),
+            Self::ReplaceWith(replacement) => msg
+                .chars()
+                .map(|c| if c.is_ascii() { c } else { *replacement })
+                .collect(),
+            Self::Transliterate(transliteration) => transliteration.apply(&msg),
+        }
+    }
+}
+
+impl SanitizationStep for Transliteration {
+    fn apply(&self, msg: String) -> String {
+        Transliteration::apply(self, &msg)
+    }
+}
+
+/// Replaces line breaks and removes other control characters.
+///
+/// Pagers generally have no concept of a new line, so by default line breaks become spaces.
+/// Tabs are always replaced with a space.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalizeControlCharacters {
+    newline: String,
+}
+
+impl Default for NormalizeControlCharacters {
+    fn default() -> Self {
+        Self {
+            newline: " ".to_string(),
+        }
+    }
+}
+
+impl NormalizeControlCharacters {
+    /// Sets the string that each line break is replaced with.
+    pub fn with_newline(mut self, newline: &str) -> Self {
+        self.newline = newline.to_string();
+        self
+    }
+}
+
+impl SanitizationStep for NormalizeControlCharacters {
+    fn apply(&self, msg: String) -> String {
+        let mut result = String::with_capacity(msg.len());
+        let mut chars = msg.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\r' => {
+                    chars.next_if_eq(&'\n');
+                    result.push_str(&self.newline);
+                }
+                '\n' | '\u{2028}' | '\u{2029}' => result.push_str(&self.newline),
+                '\t' => result.push(' '),
+                c if c.is_control() => {}
+                c => result.push(c),
+            }
+        }
+        result
+    }
+}
+
+/// Replaces each run of whitespace with a single space and removes leading and trailing
+/// whitespace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CollapseWhitespace;
+
+impl SanitizationStep for CollapseWhitespace {
+    fn apply(&self, msg: String) -> String {
+        msg.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// Replaces common emoji with text equivalents (e.g. "❤" becomes "<3").
+///
+/// Emoji modifiers (variation selectors, skin tones and joiners) are removed, emoji without a
+/// mapping are left for the non-ASCII policy to handle.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EmojiToText {
+    overrides: BTreeMap<char, String>,
+}
+
+impl EmojiToText {
+    /// Adds or replaces the mapping for an emoji.
+    pub fn with_override(mut self, emoji: char, replacement: &str) -> Self {
+        self.overrides.insert(emoji, replacement.to_string());
+        self
+    }
+}
+
+impl SanitizationStep for EmojiToText {
+    fn apply(&self, msg: String) -> String {
+        let mut result = String::with_capacity(msg.len());
+        for c in msg.chars() {
+            if let Some(replacement) = self.overrides.get(&c) {
+                result.push_str(replacement);
+            } else if let Some(replacement) = builtin_emoji(c) {
+                result.push_str(replacement);
+            } else if !is_emoji_modifier(c) {
+                result.push(c);
+            }
+        }
+        result
+    }
+}
+
+fn is_emoji_modifier(c: char) -> bool {
+    matches!(
+        c,
+        '\u{FE0E}' | '\u{FE0F}' | '\u{200D}' | '\u{1F3FB}'..='\u{1F3FF}'
+    )
+}
+
+fn builtin_emoji(c: char) -> Option<&'static str> {
+    Some(match c {
+        '❤' | '♥' | '💙' | '💚' | '💛' | '💜' | '🧡' => "<3",
+        '💔' => "</3",
+        '🙂' | '😊' | '☺' => ":)",
+        '😀' | '😃' | '😄' | '😁' => ":D",
+        '😂' | '🤣' => "XD",
+        '😉' => ";)",
+        '🙁' | '☹' | '😞' => ":(",
+        '😢' | '😭' => ":'(",
+        '😛' | '😜' => ":P",
+        '😮' | '😲' => ":O",
+        '😐' => ":|",
+        '👍' => "(y)",
+        '👎' => "(n)",
+        '👋' => "o/",
+        '⚠' | '🚨' | '❗' | '❕' => "(!)",
+        '❓' | '❔' => "(?)",
+        '✅' | '✔' | '☑' => "(ok)",
+        '❌' | '✖' => "(x)",
+        '🔥' => "(fire)",
+        '⭐' | '🌟' => "*",
+        '☀' => "(sun)",
+        '☁' => "(cloud)",
+        '🌧' => "(rain)",
+        '⚡' => "(lightning)",
+        '❄' => "(snow)",
+        '📞' | '☎' => "(tel)",
+        '📧' | '✉' => "(mail)",
+        '📻' => "(radio)",
+        '📟' => "(pager)",
+        _ => return None,
+    })
+}
+
+static URL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?i)\b(?:(?:https?|ftp)://|www\.)(?:[^\s/?#@]*@)?([\w.-]*\w)(?:\S*[^\s.,;:!?)\]'"])?"#,
+    )
+    .unwrap()
+});
+
+/// Shortens URLs, which are rarely useful on a pager display.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ElideUrls {
+    /// Replace each URL with just its host name.
+    KeepHost,
+
+    /// Replace each URL with a specified string.
+    ReplaceWith(String),
+}
+
+impl SanitizationStep for ElideUrls {
+    fn apply(&self, msg: String) -> String {
+        match self {
+            Self::KeepHost => URL.replace_all(&msg, "$1").into_owned(),
+            Self::ReplaceWith(replacement) => URL
+                .replace_all(&msg, regex::NoExpand(replacement))
+                .into_owned(),
+        }
+    }
+}
+
+/// Replaces every match of a regular expression.
+///
+/// The replacement may refer to capture groups, e.g. `$1` or `${name}`.
+#[derive(Debug, Clone)]
+pub struct RegexReplace {
+    regex: Regex,
+    replacement: String,
+}
+
+impl RegexReplace {
+    pub fn new(pattern: &str, replacement: &str) -> crate::Result<Self> {
+        Ok(Self {
+            regex: Regex::new(pattern)?,
+            replacement: replacement.to_string(),
+        })
+    }
+}
+
+impl SanitizationStep for RegexReplace {
+    fn apply(&self, msg: String) -> String {
+        self.regex
+            .replace_all(&msg, self.replacement.as_str())
+            .into_owned()
+    }
+}
+
+/// Enforces a maximum message length, in characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Truncate {
+    max_length: usize,
+    ellipses: String,
+    word_boundary: bool,
+}
+
+impl Truncate {
+    /// Truncates messages longer than `max_length`, ending them with `ellipses`.
+    pub fn new(max_length: usize, ellipses: &str) -> Self {
+        Self {
+            max_length,
+            ellipses: ellipses.to_string(),
+            word_boundary: false,
+        }
+    }
+
+    /// Truncates at the end of the last whole word that fits, rather than mid-word.
+    ///
+    /// A single word that does not fit is still cut.
+    pub fn at_word_boundary(mut self) -> Self {
+        self.word_boundary = true;
+        self
+    }
+}
+
+impl SanitizationStep for Truncate {
+    fn apply(&self, msg: String) -> String {
+        if !self.word_boundary || msg.chars().count() <= self.max_length {
+            return truncate(msg, self.max_length, &self.ellipses);
+        }
+
+        let keep = self
+            .max_length
+            .saturating_sub(self.ellipses.chars().count());
+
+        let mut chars = msg.chars();
+        let kept: String = chars.by_ref().take(keep).collect();
+        let at_boundary = chars.next().is_some_and(char::is_whitespace);
+
+        let kept = match kept.rfind(char::is_whitespace) {
+            Some(i) if !at_boundary => kept[..i].trim_end(),
+            _ => kept.trim_end(),
+        };
+
+        if kept.is_empty() {
+            truncate(msg, self.max_length, &self.ellipses)
+        } else {
+            format!("{kept}{}", self.ellipses)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn normalize_control_characters() {
+        assert_eq!(
+            NormalizeControlCharacters::default()
+                .apply("line 1\r\nline 2\rline 3\nline\t4\x07\x1b[0m".to_string()),
+            "line 1 line 2 line 3 line 4[0m"
+        );
+        assert_eq!(
+            NormalizeControlCharacters::default()
+                .with_newline(" / ")
+                .apply("a\n\nb".to_string()),
+            "a /  / b"
+        );
+    }
+
+    #[test]
+    fn collapse_whitespace() {
+        assert_eq!(
+            CollapseWhitespace.apply("  too   much \n space  ".to_string()),
+            "too much space"
+        );
+    }
+
+    #[test]
+    fn emoji_to_text() {
+        assert_eq!(
+            EmojiToText::default().apply("I ❤️ DAPNET 👍🏽 🚀".to_string()),
+            "I <3 DAPNET (y) 🚀"
+        );
+        assert_eq!(
+            EmojiToText::default()
+                .with_override('🚀', "(launch)")
+                .with_override('❤', "love")
+                .apply("I ❤️ 🚀".to_string()),
+            "I love (launch)"
+        );
+    }
+
+    #[test]
+    fn elide_urls() {
+        let msg = "See https://user@grafana.example.com:3000/d/abc?x=1 or www.example.org/x, \
+                   not example.com"
+            .to_string();
+        assert_eq!(
+            ElideUrls::KeepHost.apply(msg.clone()),
+            "See grafana.example.com or example.org, not example.com"
+        );
+        assert_eq!(
+            ElideUrls::ReplaceWith("<$url>".to_string()).apply(msg),
+            "See <$url> or <$url>, not example.com"
+        );
+    }
+
+    #[test]
+    fn regex_replace() {
+        let step = RegexReplace::new(r"(?i)\bserver-(\d+)\b", "srv$1").unwrap();
+        assert_eq!(
+            step.apply("Server-01 and server-02 down".to_string()),
+            "srv01 and srv02 down"
+        );
+    }
+
+    #[test]
+    fn regex_replace_invalid_pattern() {
+        assert!(matches!(
+            RegexReplace::new("(", ""),
+            Err(crate::Error::RegexError(_))
+        ));
+    }
+
+    #[test]
+    fn truncate_at_word_boundary() {
+        let step = Truncate::new(20, "...").at_word_boundary();
+        assert_eq!(
+            step.apply("This message is too long, oh dear.".to_string()),
+            "This message is..."
+        );
+        assert_eq!(
+            step.apply("This message is X yzwvut".to_string()),
+            "This message is X..."
+        );
+        assert_eq!(
+            step.apply("Supercalifragilisticexpialidocious".to_string()),
+            "Supercalifragilis..."
+        );
+        assert_eq!(step.apply("Short message".to_string()), "Short message");
+    }
+
+    #[test]
+    fn truncate_mid_word() {
+        assert_eq!(
+            Truncate::new(20, "...").apply("This message is too long, oh dear.".to_string()),
+            "This message is t..."
+        );
+    }
+}
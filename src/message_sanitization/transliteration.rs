@@ -0,0 +1,205 @@
+use std::collections::BTreeMap;
+
+/// Rules for replacing non-ASCII characters with readable ASCII equivalents.
+///
+/// Built in tables cover German umlauts, other European diacritics, typographic punctuation and
+/// common symbols (e.g. "Grüße" becomes "Gruesse" and "€" becomes "EUR").
+/// User supplied overrides take priority over the built in tables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transliteration {
+    overrides: BTreeMap<char, String>,
+
+    /// What to replace characters that have no mapping with, `None` removes them.
+    fallback: Option<char>,
+}
+
+impl Default for Transliteration {
+    fn default() -> Self {
+        Self {
+            overrides: BTreeMap::new(),
+            fallback: Some('?'),
+        }
+    }
+}
+
+impl Transliteration {
+    /// Adds or replaces the mapping for a character.
+    pub fn with_override(mut self, c: char, replacement: &str) -> Self {
+        self.overrides.insert(c, replacement.to_string());
+        self
+    }
+
+    /// Sets the replacement for characters that have no mapping, `None` removes them.
+    pub fn with_fallback(mut self, fallback: Option<char>) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    /// Transliterates all non-ASCII characters in a string.
+    pub fn apply(&self, msg: &str) -> String {
+        let mut result = String::with_capacity(msg.len());
+        for c in msg.chars() {
+            if let Some(replacement) = self.overrides.get(&c) {
+                result.push_str(replacement);
+            } else if c.is_ascii() {
+                result.push(c);
+            } else if let Some(replacement) = builtin(c) {
+                result.push_str(replacement);
+            } else if let Some(fallback) = self.fallback {
+                result.push(fallback);
+            }
+        }
+        result
+    }
+}
+
+fn builtin(c: char) -> Option<&'static str> {
+    Some(match c {
+        // German
+        'ä' => "ae",
+        'ö' => "oe",
+        'ü' => "ue",
+        'Ä' => "Ae",
+        'Ö' => "Oe",
+        'Ü' => "Ue",
+        'ß' => "ss",
+        'ẞ' => "SS",
+
+        // Other European letters
+        'à' | 'á' | 'â' | 'ã' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'À' | 'Á' | 'Â' | 'Ã' | 'Å' | 'Ā' | 'Ă' | 'Ą' => "A",
+        'æ' => "ae",
+        'Æ' => "AE",
+        'ç' | 'ć' | 'č' | 'ĉ' | 'ċ' => "c",
+        'Ç' | 'Ć' | 'Č' | 'Ĉ' | 'Ċ' => "C",
+        'ď' | 'đ' | 'ð' => "d",
+        'Ď' | 'Đ' | 'Ð' => "D",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ė' | 'ę' | 'ě' => "e",
+        'È' | 'É' | 'Ê' | 'Ë' | 'Ē' | 'Ė' | 'Ę' | 'Ě' => "E",
+        'ğ' | 'ģ' => "g",
+        'Ğ' | 'Ģ' => "G",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | 'į' | 'ı' => "i",
+        'Ì' | 'Í' | 'Î' | 'Ï' | 'Ī' | 'Į' | 'İ' => "I",
+        'ķ' => "k",
+        'Ķ' => "K",
+        'ĺ' | 'ļ' | 'ľ' | 'ł' => "l",
+        'Ĺ' | 'Ļ' | 'Ľ' | 'Ł' => "L",
+        'ñ' | 'ń' | 'ņ' | 'ň' => "n",
+        'Ñ' | 'Ń' | 'Ņ' | 'Ň' => "N",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ø' | 'ō' | 'ő' => "o",
+        'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ø' | 'Ō' | 'Ő' => "O",
+        'œ' => "oe",
+        'Œ' => "OE",
+        'ŕ' | 'ř' => "r",
+        'Ŕ' | 'Ř' => "R",
+        'ś' | 'š' | 'ş' | 'ș' => "s",
+        'Ś' | 'Š' | 'Ş' | 'Ș' => "S",
+        'ť' | 'ţ' | 'ț' => "t",
+        'Ť' | 'Ţ' | 'Ț' => "T",
+        'þ' => "th",
+        'Þ' => "TH",
+        'ù' | 'ú' | 'û' | 'ū' | 'ů' | 'ű' | 'ų' => "u",
+        'Ù' | 'Ú' | 'Û' | 'Ū' | 'Ů' | 'Ű' | 'Ų' => "U",
+        'ý' | 'ÿ' => "y",
+        'Ý' | 'Ÿ' => "Y",
+        'ź' | 'ż' | 'ž' => "z",
+        'Ź' | 'Ż' | 'Ž' => "Z",
+
+        // Typographic punctuation
+        '‘' | '’' | '‚' | '‛' | '′' => "'",
+        '“' | '”' | '„' | '‟' | '″' => "\"",
+        '«' => "<<",
+        '»' => ">>",
+        '‹' => "<",
+        '›' => ">",
+        '‐' | '‑' | '‒' | '–' | '—' | '―' | '−' => "-",
+        '…' => "...",
+        '•' | '·' => "*",
+        '\u{00A0}' | '\u{2002}' | '\u{2003}' | '\u{2009}' | '\u{202F}' => " ",
+        '¡' => "!",
+        '¿' => "?",
+
+        // Symbols
+        '€' => "EUR",
+        '£' => "GBP",
+        '¥' => "JPY",
+        '¢' => "c",
+        '°' => "deg",
+        '©' => "(c)",
+        '®' => "(R)",
+        '™' => "TM",
+        '§' => "S",
+        '¶' => "P",
+        '×' => "x",
+        '÷' => "/",
+        '±' => "+/-",
+        'µ' | 'μ' => "u",
+        'Ω' => "Ohm",
+        '½' => "1/2",
+        '¼' => "1/4",
+        '¾' => "3/4",
+        '¹' => "1",
+        '²' => "2",
+        '³' => "3",
+        '→' => "->",
+        '←' => "<-",
+        '↔' => "<->",
+        '⇒' => "=>",
+        '≤' => "<=",
+        '≥' => ">=",
+        '≠' => "!=",
+        '≈' => "~",
+        '∞' => "inf",
+
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn german() {
+        assert_eq!(
+            Transliteration::default().apply("Grüße aus München, Straße"),
+            "Gruesse aus Muenchen, Strasse"
+        );
+    }
+
+    #[test]
+    fn european_diacritics() {
+        assert_eq!(
+            Transliteration::default().apply("Crème brûlée à Kraków, Øresund"),
+            "Creme brulee a Krakow, Oresund"
+        );
+    }
+
+    #[test]
+    fn punctuation_and_symbols() {
+        assert_eq!(
+            Transliteration::default().apply("“Price” – 5 € … it’s 21.5 °C"),
+            "\"Price\" - 5 EUR ... it's 21.5 degC"
+        );
+    }
+
+    #[test]
+    fn unmapped_characters_use_fallback() {
+        assert_eq!(Transliteration::default().apply("I ❤ DAPNET"), "I ? DAPNET");
+        assert_eq!(
+            Transliteration::default()
+                .with_fallback(None)
+                .apply("I ❤ DAPNET"),
+            "I  DAPNET"
+        );
+    }
+
+    #[test]
+    fn overrides() {
+        let transliteration = Transliteration::default()
+            .with_override('❤', "<3")
+            .with_override('°', "*")
+            .with_override('&', "and");
+        assert_eq!(transliteration.apply("I ❤ 20° & sun"), "I <3 20* and sun");
+    }
+}
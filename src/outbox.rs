@@ -0,0 +1,735 @@
+use crate::{Client, OutgoingCall, OutgoingNews};
+use chrono::{DateTime, TimeDelta, Utc};
+use derive_builder::Builder;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, io::Write, path::PathBuf, sync::Mutex, time::Duration};
+
+/// A message waiting in, or delivered from, an [`Outbox`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "StoredMessage", into = "StoredMessage")]
+pub enum OutboxMessage {
+    Call(OutgoingCall),
+    News(OutgoingNews),
+}
+
+/// Serialized form of [`OutboxMessage`], the numeric flag of a call is not part of the call's
+/// own serialization as it is not sent to the API.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum StoredMessage {
+    Call { call: OutgoingCall, numeric: bool },
+    News(OutgoingNews),
+}
+
+impl From<StoredMessage> for OutboxMessage {
+    fn from(message: StoredMessage) -> Self {
+        match message {
+            StoredMessage::Call { call, numeric } => Self::Call(OutgoingCall { numeric, ..call }),
+            StoredMessage::News(news) => Self::News(news),
+        }
+    }
+}
+
+impl From<OutboxMessage> for StoredMessage {
+    fn from(message: OutboxMessage) -> Self {
+        match message {
+            OutboxMessage::Call(call) => Self::Call {
+                numeric: call.numeric,
+                call,
+            },
+            OutboxMessage::News(news) => Self::News(news),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboxStatus {
+    /// Waiting to be sent, possibly after failed attempts.
+    Pending,
+
+    Sent,
+
+    /// Rejected by the API or out of attempts.
+    Failed,
+
+    /// Not sent before its time to live ran out.
+    Expired,
+}
+
+/// A message in an [`Outbox`] and its delivery status.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    pub id: u64,
+    pub message: OutboxMessage,
+    pub status: OutboxStatus,
+    pub queued: DateTime<Utc>,
+    pub expires: Option<DateTime<Utc>>,
+    pub attempts: u32,
+
+    /// Earliest time of the next attempt, if pending.
+    pub next_attempt: DateTime<Utc>,
+
+    /// Error from the most recent failed attempt.
+    pub last_error: Option<String>,
+}
+
+/// Storage for the entries of an [`Outbox`], so that messages survive restarts.
+pub trait OutboxStore: Send + Sync {
+    fn load(&self) -> crate::Result<Vec<OutboxEntry>>;
+
+    /// Adds or replaces an entry.
+    fn save(&self, entry: &OutboxEntry) -> crate::Result<()>;
+
+    fn remove(&self, id: u64) -> crate::Result<()>;
+}
+
+/// Keeps entries in memory only.
+#[derive(Debug, Default)]
+pub struct MemoryOutboxStore(Mutex<BTreeMap<u64, OutboxEntry>>);
+
+impl OutboxStore for MemoryOutboxStore {
+    fn load(&self) -> crate::Result<Vec<OutboxEntry>> {
+        Ok(self.0.lock().unwrap().values().cloned().collect())
+    }
+
+    fn save(&self, entry: &OutboxEntry) -> crate::Result<()> {
+        self.0.lock().unwrap().insert(entry.id, entry.clone());
+        Ok(())
+    }
+
+    fn remove(&self, id: u64) -> crate::Result<()> {
+        self.0.lock().unwrap().remove(&id);
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum JournalRecord {
+    Entry(OutboxEntry),
+    Removed(u64),
+}
+
+/// Keeps entries in a journal file of JSON lines, each change is appended and synced to disk.
+///
+/// The journal is compacted when it is loaded.
+#[derive(Debug)]
+pub struct FileOutboxStore {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileOutboxStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn append(&self, record: &JournalRecord) -> crate::Result<()> {
+        let _lock = self.lock.lock().unwrap();
+        let mut line = serde_json::to_vec(record).unwrap();
+        line.push(b'\n');
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(&line)?;
+        file.sync_data()?;
+        Ok(())
+    }
+}
+
+impl OutboxStore for FileOutboxStore {
+    fn load(&self) -> crate::Result<Vec<OutboxEntry>> {
+        let _lock = self.lock.lock().unwrap();
+        let contents = match std::fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut entries = BTreeMap::new();
+        for (number, line) in contents.lines().enumerate() {
+            // A crash while appending can only leave the last line incomplete
+            let record = match serde_json::from_str(line) {
+                Ok(record) => record,
+                Err(_) if number + 1 == contents.lines().count() => break,
+                Err(e) => {
+                    return Err(crate::Error::ConfigError(format!(
+                        "invalid outbox journal {} line {}: {e}",
+                        self.path.display(),
+                        number + 1
+                    )));
+                }
+            };
+            match record {
+                JournalRecord::Entry(entry) => {
+                    entries.insert(entry.id, entry);
+                }
+                JournalRecord::Removed(id) => {
+                    entries.remove(&id);
+                }
+            }
+        }
+
+        let mut compacted = Vec::new();
+        for entry in entries.values() {
+            serde_json::to_writer(&mut compacted, &JournalRecord::Entry(entry.clone())).unwrap();
+            compacted.push(b'\n');
+        }
+        crate::fs::write_atomically(&self.path, &compacted)?;
+
+        Ok(entries.into_values().collect())
+    }
+
+    fn save(&self, entry: &OutboxEntry) -> crate::Result<()> {
+        self.append(&JournalRecord::Entry(entry.clone()))
+    }
+
+    fn remove(&self, id: u64) -> crate::Result<()> {
+        self.append(&JournalRecord::Removed(id))
+    }
+}
+
+/// Keeps entries in a SQLite database.
+#[cfg(feature = "sqlite")]
+#[derive(Debug)]
+pub struct SqliteOutboxStore(Mutex<rusqlite::Connection>);
+
+#[cfg(feature = "sqlite")]
+impl SqliteOutboxStore {
+    /// Opens or creates a database.
+    pub fn open(path: impl AsRef<std::path::Path>) -> crate::Result<Self> {
+        Self::new(rusqlite::Connection::open(path)?)
+    }
+
+    /// Uses an existing connection, creating the outbox table if needed.
+    pub fn new(connection: rusqlite::Connection) -> crate::Result<Self> {
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS outbox (id INTEGER PRIMARY KEY, entry TEXT NOT NULL)",
+            (),
+        )?;
+        Ok(Self(Mutex::new(connection)))
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl OutboxStore for SqliteOutboxStore {
+    fn load(&self) -> crate::Result<Vec<OutboxEntry>> {
+        let connection = self.0.lock().unwrap();
+        let mut statement = connection.prepare("SELECT entry FROM outbox ORDER BY id")?;
+        statement
+            .query_map((), |row| row.get::<_, String>(0))?
+            .map(|entry| {
+                serde_json::from_str(&entry?).map_err(|e| {
+                    crate::Error::ConfigError(format!("invalid outbox database entry: {e}"))
+                })
+            })
+            .collect()
+    }
+
+    fn save(&self, entry: &OutboxEntry) -> crate::Result<()> {
+        self.0.lock().unwrap().execute(
+            "INSERT OR REPLACE INTO outbox (id, entry) VALUES (?1, ?2)",
+            (entry.id as i64, serde_json::to_string(entry).unwrap()),
+        )?;
+        Ok(())
+    }
+
+    fn remove(&self, id: u64) -> crate::Result<()> {
+        self.0
+            .lock()
+            .unwrap()
+            .execute("DELETE FROM outbox WHERE id = ?1", (id as i64,))?;
+        Ok(())
+    }
+}
+
+/// Options that control delivery from an [`Outbox`].
+#[derive(Debug, Clone, PartialEq, Eq, Builder)]
+pub struct OutboxOptions {
+    /// How long a message may wait before it expires, `None` to keep trying forever.
+    #[builder(default = "Some(Duration::from_secs(24 * 60 * 60))")]
+    ttl: Option<Duration>,
+
+    /// Delay before the first retry, doubled after each further failure.
+    #[builder(default = "Duration::from_secs(5)")]
+    retry_delay: Duration,
+
+    #[builder(default = "Duration::from_secs(10 * 60)")]
+    max_retry_delay: Duration,
+
+    /// Number of attempts after which a message fails, `None` to retry until it expires.
+    #[builder(default = "None")]
+    max_attempts: Option<u32>,
+}
+
+impl Default for OutboxOptions {
+    fn default() -> Self {
+        OutboxOptionsBuilder::default().build().unwrap()
+    }
+}
+
+impl OutboxOptions {
+    fn retry_delay(&self, attempts: u32) -> TimeDelta {
+        let delay = self
+            .retry_delay
+            .saturating_mul(2u32.saturating_pow(attempts.saturating_sub(1)))
+            .min(self.max_retry_delay);
+        TimeDelta::from_std(delay).unwrap_or(TimeDelta::MAX)
+    }
+}
+
+/// True if sending might succeed if tried again later.
+pub(crate) fn is_transient(error: &crate::Error) -> bool {
+    match error {
+        crate::Error::HttpError(_) | crate::Error::RateLimited { .. } => true,
+        crate::Error::ApiError(status) => {
+            status.is_server_error()
+                || *status == StatusCode::TOO_MANY_REQUESTS
+                || *status == StatusCode::REQUEST_TIMEOUT
+        }
+        _ => false,
+    }
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    entries: BTreeMap<u64, OutboxEntry>,
+    next_id: u64,
+}
+
+/// A persistent queue of calls and news that are delivered through a [`Client`], retrying while
+/// the API is unreachable.
+///
+/// Messages are delivered strictly in the order they were queued: while the oldest pending
+/// message is waiting to be retried no later message is sent.
+/// Messages rejected by the API (or by the client, e.g. a missing sender prefix) fail without
+/// holding up the queue.
+///
+/// Example:
+/// ```no_run
+/// # use dapnet_api::{Client, FileOutboxStore, OutboxOptions, Outbox, OutgoingCallBuilder};
+/// # use std::sync::Arc;
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let client = Client::new("m0nxn", "my_super_secret_password");
+/// let outbox = Arc::new(
+///     Outbox::new(
+///         client,
+///         OutboxOptions::default(),
+///         FileOutboxStore::new("outbox.jsonl"),
+///     )
+///     .unwrap(),
+/// );
+///
+/// let delivery = tokio::spawn({
+///     let outbox = outbox.clone();
+///     async move { outbox.run().await }
+/// });
+///
+/// let id = outbox
+///     .queue_call(
+///         OutgoingCallBuilder::default()
+///             .text("M0NXN: this is a test".to_string())
+///             .recipients(vec!["m0nxn".to_string()])
+///             .transmitter_groups(vec!["uk-all".to_string()])
+///             .build()
+///             .unwrap(),
+///     )
+///     .unwrap();
+/// println!("{:?}", outbox.status(id));
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Outbox<S: OutboxStore> {
+    client: Client,
+    options: OutboxOptions,
+    store: S,
+    entries: Mutex<Entries>,
+    delivering: tokio::sync::Mutex<()>,
+    queued: tokio::sync::Notify,
+}
+
+impl<S: OutboxStore> Outbox<S> {
+    /// Creates an outbox, loading any existing entries from `store`.
+    pub fn new(client: Client, options: OutboxOptions, store: S) -> crate::Result<Self> {
+        let entries = store
+            .load()?
+            .into_iter()
+            .map(|entry| (entry.id, entry))
+            .collect::<BTreeMap<_, _>>();
+        let next_id = entries.keys().next_back().map_or(1, |id| id + 1);
+
+        Ok(Self {
+            client,
+            options,
+            store,
+            entries: Mutex::new(Entries { entries, next_id }),
+            delivering: tokio::sync::Mutex::new(()),
+            queued: tokio::sync::Notify::new(),
+        })
+    }
+
+    /// Queues a call for delivery, returning its ID.
+    pub fn queue_call(&self, call: OutgoingCall) -> crate::Result<u64> {
+        self.queue(OutboxMessage::Call(call), Utc::now())
+    }
+
+    /// Queues a news item for delivery, returning its ID.
+    pub fn queue_news(&self, news: OutgoingNews) -> crate::Result<u64> {
+        self.queue(OutboxMessage::News(news), Utc::now())
+    }
+
+    fn queue(&self, message: OutboxMessage, now: DateTime<Utc>) -> crate::Result<u64> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = OutboxEntry {
+            id: entries.next_id,
+            message,
+            status: OutboxStatus::Pending,
+            queued: now,
+            expires: self
+                .options
+                .ttl
+                .map(|ttl| now + TimeDelta::from_std(ttl).unwrap_or(TimeDelta::MAX)),
+            attempts: 0,
+            next_attempt: now,
+            last_error: None,
+        };
+        self.store.save(&entry)?;
+
+        let id = entry.id;
+        entries.entries.insert(id, entry);
+        entries.next_id += 1;
+        drop(entries);
+
+        self.queued.notify_one();
+        Ok(id)
+    }
+
+    pub fn status(&self, id: u64) -> Option<OutboxStatus> {
+        self.entry(id).map(|entry| entry.status)
+    }
+
+    pub fn entry(&self, id: u64) -> Option<OutboxEntry> {
+        self.entries.lock().unwrap().entries.get(&id).cloned()
+    }
+
+    /// Entries with the given status, oldest first.
+    pub fn entries(&self, status: OutboxStatus) -> Vec<OutboxEntry> {
+        self.entries
+            .lock()
+            .unwrap()
+            .entries
+            .values()
+            .filter(|entry| entry.status == status)
+            .cloned()
+            .collect()
+    }
+
+    /// Removes sent, failed and expired entries queued before `before`, returning how many were
+    /// removed.
+    pub fn prune(&self, before: DateTime<Utc>) -> crate::Result<usize> {
+        let mut entries = self.entries.lock().unwrap();
+        let finished = entries
+            .entries
+            .values()
+            .filter(|entry| entry.status != OutboxStatus::Pending && entry.queued < before)
+            .map(|entry| entry.id)
+            .collect::<Vec<_>>();
+        for id in &finished {
+            self.store.remove(*id)?;
+            entries.entries.remove(id);
+        }
+        Ok(finished.len())
+    }
+
+    /// Attempts to deliver the messages that are due, returning how many were sent.
+    pub async fn deliver(&self) -> crate::Result<usize> {
+        self.deliver_at(Utc::now()).await
+    }
+
+    async fn deliver_at(&self, now: DateTime<Utc>) -> crate::Result<usize> {
+        let _delivering = self.delivering.lock().await;
+        self.expire(now)?;
+
+        let mut sent = 0;
+        while let Some(mut entry) = self.next_pending() {
+            if entry.next_attempt > now {
+                break;
+            }
+
+            let result = match &entry.message {
+                OutboxMessage::Call(call) => self.client.new_call(call).await,
+                OutboxMessage::News(news) => self.client.new_news(news).await,
+            };
+            entry.attempts += 1;
+
+            let transient = match result {
+                Ok(()) => {
+                    entry.status = OutboxStatus::Sent;
+                    entry.last_error = None;
+                    sent += 1;
+                    false
+                }
+                Err(e) => {
+                    entry.last_error = Some(e.to_string());
+                    if !is_transient(&e)
+                        || self
+                            .options
+                            .max_attempts
+                            .is_some_and(|max| entry.attempts >= max)
+                    {
+                        entry.status = OutboxStatus::Failed;
+                        false
+                    } else {
+                        entry.next_attempt = now + self.options.retry_delay(entry.attempts);
+                        true
+                    }
+                }
+            };
+
+            self.update(entry)?;
+            if transient {
+                break;
+            }
+        }
+
+        Ok(sent)
+    }
+
+    /// Delivers messages as they are queued and retries failed attempts, only returning if the
+    /// store fails.
+    pub async fn run(&self) -> crate::Result<()> {
+        loop {
+            self.deliver().await?;
+
+            let wait = self.next_pending().map(|entry| {
+                (entry.next_attempt - Utc::now())
+                    .to_std()
+                    .unwrap_or_default()
+            });
+            match wait {
+                Some(wait) => {
+                    tokio::select! {
+                        _ = self.queued.notified() => {}
+                        _ = tokio::time::sleep(wait) => {}
+                    }
+                }
+                None => self.queued.notified().await,
+            }
+        }
+    }
+
+    fn next_pending(&self) -> Option<OutboxEntry> {
+        self.entries
+            .lock()
+            .unwrap()
+            .entries
+            .values()
+            .find(|entry| entry.status == OutboxStatus::Pending)
+            .cloned()
+    }
+
+    fn update(&self, entry: OutboxEntry) -> crate::Result<()> {
+        self.store.save(&entry)?;
+        self.entries.lock().unwrap().entries.insert(entry.id, entry);
+        Ok(())
+    }
+
+    fn expire(&self, now: DateTime<Utc>) -> crate::Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        for entry in entries.entries.values_mut() {
+            if entry.status == OutboxStatus::Pending
+                && entry.expires.is_some_and(|expires| expires <= now)
+            {
+                entry.status = OutboxStatus::Expired;
+                self.store.save(entry)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{OutgoingCallBuilder, OutgoingNewsBuilder, test_util::server};
+
+    fn call(text: &str) -> OutgoingCall {
+        OutgoingCallBuilder::default()
+            .text(text.to_string())
+            .recipients(vec!["m0nxn".to_string()])
+            .transmitter_groups(vec!["uk-all".to_string()])
+            .build()
+            .unwrap()
+    }
+
+    fn outbox(url: url::Url) -> Outbox<MemoryOutboxStore> {
+        Outbox::new(
+            Client::new("m0nxn", "hunter2").with_api_url(url),
+            OutboxOptions::default(),
+            MemoryOutboxStore::default(),
+        )
+        .unwrap()
+    }
+
+    fn time(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000 + seconds, 0).unwrap()
+    }
+
+    #[tokio::test]
+    async fn delivers_in_order_with_retries() {
+        let (url, requests) = server(vec![(503, ""), (201, ""), (201, "")]).await;
+        let outbox = outbox(url);
+
+        let first = outbox
+            .queue(OutboxMessage::Call(call("first")), time(0))
+            .unwrap();
+        let second = outbox
+            .queue(
+                OutboxMessage::News(
+                    OutgoingNewsBuilder::default()
+                        .rubric("test".to_string())
+                        .text("second".to_string())
+                        .build()
+                        .unwrap(),
+                ),
+                time(0),
+            )
+            .unwrap();
+
+        // First attempt fails and holds up the queue
+        assert_eq!(outbox.deliver_at(time(0)).await.unwrap(), 0);
+        let entry = outbox.entry(first).unwrap();
+        assert_eq!(entry.status, OutboxStatus::Pending);
+        assert_eq!(entry.attempts, 1);
+        assert_eq!(entry.next_attempt, time(5));
+        assert_eq!(
+            entry.last_error.as_deref(),
+            Some("API error rc=503 Service Unavailable")
+        );
+        assert_eq!(outbox.status(second), Some(OutboxStatus::Pending));
+
+        // Not yet due
+        assert_eq!(outbox.deliver_at(time(4)).await.unwrap(), 0);
+
+        assert_eq!(outbox.deliver_at(time(5)).await.unwrap(), 2);
+        assert_eq!(outbox.status(first), Some(OutboxStatus::Sent));
+        assert_eq!(outbox.status(second), Some(OutboxStatus::Sent));
+        assert_eq!(outbox.entries(OutboxStatus::Sent).len(), 2);
+
+        let requests = requests.lock().unwrap();
+        let paths = requests
+            .iter()
+            .map(|(path, _)| path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["/calls", "/calls", "/news"]);
+        assert!(requests[1].1.contains("\"text\":\"first\""));
+    }
+
+    #[tokio::test]
+    async fn rejected_messages_fail_without_blocking() {
+        let (url, _) = server(vec![(400, ""), (201, "")]).await;
+        let outbox = outbox(url);
+
+        let first = outbox
+            .queue(OutboxMessage::Call(call("first")), time(0))
+            .unwrap();
+        let second = outbox
+            .queue(OutboxMessage::Call(call("second")), time(0))
+            .unwrap();
+
+        assert_eq!(outbox.deliver_at(time(0)).await.unwrap(), 1);
+        assert_eq!(outbox.status(first), Some(OutboxStatus::Failed));
+        assert_eq!(outbox.status(second), Some(OutboxStatus::Sent));
+    }
+
+    #[tokio::test]
+    async fn expires_and_prunes() {
+        let (url, _) = server(Vec::new()).await;
+        let outbox = outbox(url);
+
+        let id = outbox
+            .queue(OutboxMessage::Call(call("first")), time(0))
+            .unwrap();
+        assert_eq!(outbox.deliver_at(time(24 * 60 * 60)).await.unwrap(), 0);
+        assert_eq!(outbox.status(id), Some(OutboxStatus::Expired));
+
+        assert_eq!(outbox.prune(time(0)).unwrap(), 0);
+        assert_eq!(outbox.prune(time(1)).unwrap(), 1);
+        assert_eq!(outbox.status(id), None);
+    }
+
+    #[test]
+    fn retry_delay_backs_off() {
+        let options = OutboxOptionsBuilder::default()
+            .retry_delay(Duration::from_secs(5))
+            .max_retry_delay(Duration::from_secs(30))
+            .build()
+            .unwrap();
+        let delays = (1..=5)
+            .map(|attempts| options.retry_delay(attempts).num_seconds())
+            .collect::<Vec<_>>();
+        assert_eq!(delays, vec![5, 10, 20, 30, 30]);
+    }
+
+    fn check_store(store: &impl OutboxStore) {
+        let mut numeric = call("123");
+        numeric.numeric = true;
+        let mut entry = OutboxEntry {
+            id: 1,
+            message: OutboxMessage::Call(numeric),
+            status: OutboxStatus::Pending,
+            queued: time(0),
+            expires: None,
+            attempts: 0,
+            next_attempt: time(0),
+            last_error: None,
+        };
+        store.save(&entry).unwrap();
+        entry.status = OutboxStatus::Sent;
+        store.save(&entry).unwrap();
+        store
+            .save(&OutboxEntry {
+                id: 2,
+                ..entry.clone()
+            })
+            .unwrap();
+        store.remove(2).unwrap();
+
+        let loaded = store.load().unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].status, OutboxStatus::Sent);
+        let OutboxMessage::Call(call) = &loaded[0].message else {
+            panic!("expected a call");
+        };
+        assert!(call.numeric);
+    }
+
+    #[test]
+    fn file_store() {
+        let path = std::env::temp_dir().join(format!("dapnet-outbox-{}.jsonl", std::process::id()));
+        let store = FileOutboxStore::new(&path);
+        assert!(store.load().unwrap().is_empty());
+        check_store(&store);
+
+        // Loading compacted the journal
+        let lines = std::fs::read_to_string(&path).unwrap().lines().count();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(lines, 1);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_store() {
+        check_store(
+            &SqliteOutboxStore::new(rusqlite::Connection::open_in_memory().unwrap()).unwrap(),
+        );
+    }
+}
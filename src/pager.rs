@@ -0,0 +1,408 @@
+//! Simulation of a pager receiving POCSAG transmissions.
+//!
+//! A [`VirtualPager`] behaves like a real device: it only shows pages addressed to its RICs,
+//! interprets them according to its display mode, suppresses duplicates and keeps the news of
+//! subscribed Skyper rubrics.
+
+use crate::{pocsag, skyper, transmitter};
+use chrono::{DateTime, TimeDelta, Utc};
+use derive_builder::Builder;
+use std::collections::{BTreeMap, VecDeque};
+
+/// How a pager displays the content of pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PagerMode {
+    /// Every page is shown using the numeric alphabet.
+    Numeric,
+
+    /// Pages are shown as numeric or alphanumeric text depending on their function bits.
+    Alphanumeric,
+}
+
+/// Options that control how a virtual pager behaves.
+#[derive(Debug, Clone, PartialEq, Eq, Builder)]
+pub struct VirtualPagerConfig {
+    /// RICs the pager is programmed with.
+    rics: Vec<u32>,
+
+    #[builder(default = "PagerMode::Alphanumeric")]
+    mode: PagerMode,
+
+    /// Skyper rubric numbers the pager is subscribed to.
+    #[builder(default)]
+    rubrics: Vec<u8>,
+
+    /// Identical pages received within this window of each other are only shown once.
+    #[builder(default = "TimeDelta::minutes(5)")]
+    duplicate_window: TimeDelta,
+
+    /// Maximum number of messages in the inbox, the oldest messages are removed first.
+    #[builder(default = "100")]
+    inbox_capacity: usize,
+}
+
+/// A message shown in the inbox of a pager.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InboxMessage {
+    pub received: DateTime<Utc>,
+    pub ric: u32,
+    pub function: pocsag::Function,
+    pub text: String,
+}
+
+/// A news item stored for a rubric.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewsItem {
+    pub received: DateTime<Utc>,
+    pub text: String,
+}
+
+/// Everything a pager stores for a subscribed rubric.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RubricContent {
+    pub label: Option<String>,
+
+    /// News items by their position.
+    pub news: BTreeMap<u8, NewsItem>,
+}
+
+/// Example:
+/// ```
+/// # use dapnet_api::{pager::{VirtualPager, VirtualPagerConfigBuilder}, pocsag};
+/// let mut pager = VirtualPager::new(
+///     VirtualPagerConfigBuilder::default()
+///         .rics(vec![1234567])
+///         .build()
+///         .unwrap(),
+/// );
+///
+/// let page = pocsag::Page::new(
+///     1234567,
+///     pocsag::Function::Alphanumeric,
+///     pocsag::Content::Alphanumeric("M0NXN: this is a test".to_string()),
+/// )
+/// .unwrap();
+/// pager.receive_codewords(&pocsag::encode(&[page]));
+///
+/// assert_eq!(pager.inbox()[0].text, "M0NXN: this is a test");
+/// ```
+#[derive(Debug, Clone)]
+pub struct VirtualPager {
+    config: VirtualPagerConfig,
+    inbox: VecDeque<InboxMessage>,
+    rubrics: BTreeMap<u8, RubricContent>,
+    suppressed_duplicates: usize,
+}
+
+impl VirtualPager {
+    pub fn new(config: VirtualPagerConfig) -> Self {
+        Self {
+            config,
+            inbox: VecDeque::new(),
+            rubrics: BTreeMap::new(),
+            suppressed_duplicates: 0,
+        }
+    }
+
+    /// Messages in the inbox, oldest first.
+    pub fn inbox(&self) -> &VecDeque<InboxMessage> {
+        &self.inbox
+    }
+
+    pub fn clear_inbox(&mut self) {
+        self.inbox.clear();
+    }
+
+    /// Number of pages that were not shown because they duplicated a recent message.
+    pub fn suppressed_duplicates(&self) -> usize {
+        self.suppressed_duplicates
+    }
+
+    /// Content stored for a subscribed rubric.
+    pub fn rubric(&self, number: u8) -> Option<&RubricContent> {
+        self.rubrics.get(&number)
+    }
+
+    /// Receives a transmission, returning any messages added to the inbox.
+    pub fn receive_codewords(&mut self, codewords: &[u32]) -> Vec<InboxMessage> {
+        self.receive_codewords_at(codewords, Utc::now())
+    }
+
+    /// Receives a transmission at a given time, returning any messages added to the inbox.
+    pub fn receive_codewords_at(
+        &mut self,
+        codewords: &[u32],
+        time: DateTime<Utc>,
+    ) -> Vec<InboxMessage> {
+        pocsag::decode(codewords)
+            .iter()
+            .filter_map(|page| self.receive_decoded(page, time))
+            .collect()
+    }
+
+    /// Receives a single page, as if it had been transmitted.
+    pub fn receive_page(&mut self, page: &pocsag::Page) -> Vec<InboxMessage> {
+        self.receive_codewords(&pocsag::encode(std::slice::from_ref(page)))
+    }
+
+    /// Receives a message frame from the transmitter protocol, as if it had been transmitted.
+    pub fn receive_message(
+        &mut self,
+        message: &transmitter::Message,
+    ) -> crate::Result<Vec<InboxMessage>> {
+        Ok(self.receive_page(&message.to_page()?))
+    }
+
+    fn receive_decoded(
+        &mut self,
+        page: &pocsag::DecodedPage,
+        time: DateTime<Utc>,
+    ) -> Option<InboxMessage> {
+        let text = match (self.config.mode, page.function) {
+            (PagerMode::Numeric, _) | (_, pocsag::Function::Numeric) => page.numeric_text(),
+            _ => page.alphanumeric_text(),
+        };
+
+        if self.config.mode == PagerMode::Alphanumeric
+            && !self.config.rubrics.is_empty()
+            && [skyper::RUBRIC_RIC, skyper::NEWS_RIC].contains(&page.ric)
+        {
+            self.receive_skyper(page.ric, &text, time);
+            return None;
+        }
+
+        if !self.config.rics.contains(&page.ric) {
+            return None;
+        }
+
+        let duplicate = self.inbox.iter().any(|m| {
+            m.ric == page.ric
+                && m.function == page.function
+                && m.text == text
+                && time - m.received <= self.config.duplicate_window
+        });
+        if duplicate {
+            self.suppressed_duplicates += 1;
+            return None;
+        }
+
+        let message = InboxMessage {
+            received: time,
+            ric: page.ric,
+            function: page.function,
+            text,
+        };
+
+        self.inbox.push_back(message.clone());
+        while self.inbox.len() > self.config.inbox_capacity {
+            self.inbox.pop_front();
+        }
+
+        Some(message)
+    }
+
+    fn receive_skyper(&mut self, ric: u32, text: &str, time: DateTime<Utc>) {
+        let Ok(message) = skyper::SkyperMessage::decode(ric, text) else {
+            return;
+        };
+
+        match message {
+            skyper::SkyperMessage::Rubric { number, label } => {
+                if self.config.rubrics.contains(&number) {
+                    self.rubrics.entry(number).or_default().label = Some(label);
+                }
+            }
+            skyper::SkyperMessage::News {
+                rubric,
+                number,
+                text,
+            } => {
+                if self.config.rubrics.contains(&rubric) {
+                    self.rubrics.entry(rubric).or_default().news.insert(
+                        number,
+                        NewsItem {
+                            received: time,
+                            text,
+                        },
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        Callsign, OutgoingCallBuilder, Transmitter, TransmitterGroup,
+        skyper::SkyperMessage,
+        test_util::transmitter,
+        transmitter::{
+            ServerMessage, TransmitterClient, TransmitterConfigBuilder, TransmitterServer,
+        },
+    };
+
+    fn alpha_page(ric: u32, text: &str) -> pocsag::Page {
+        pocsag::Page::new(
+            ric,
+            pocsag::Function::Alphanumeric,
+            pocsag::Content::Alphanumeric(text.to_string()),
+        )
+        .unwrap()
+    }
+
+    fn pager(mode: PagerMode, rubrics: Vec<u8>) -> VirtualPager {
+        VirtualPager::new(
+            VirtualPagerConfigBuilder::default()
+                .rics(vec![1234567, 8])
+                .mode(mode)
+                .rubrics(rubrics)
+                .inbox_capacity(3)
+                .build()
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn only_own_rics_are_shown() {
+        let mut pager = pager(PagerMode::Alphanumeric, vec![]);
+
+        let codewords = pocsag::encode(&[
+            alpha_page(1234567, "for me"),
+            alpha_page(1234566, "not for me"),
+            alpha_page(8, "also for me"),
+        ]);
+        let received = pager.receive_codewords(&codewords);
+
+        assert_eq!(received.len(), 2);
+        assert_eq!(pager.inbox()[0].text, "for me");
+        assert_eq!(pager.inbox()[1].text, "also for me");
+    }
+
+    #[test]
+    fn numeric_mode() {
+        let mut pager = pager(PagerMode::Numeric, vec![]);
+
+        pager.receive_page(
+            &pocsag::Page::new(
+                8,
+                pocsag::Function::Numeric,
+                pocsag::Content::Numeric("0123-456".to_string()),
+            )
+            .unwrap(),
+        );
+        pager.receive_page(&alpha_page(8, "A"));
+
+        assert_eq!(pager.inbox()[0].text, "0123-456");
+        assert_eq!(pager.inbox()[1].text, "14000");
+    }
+
+    #[test]
+    fn duplicates_are_suppressed_within_window() {
+        let mut pager = pager(PagerMode::Alphanumeric, vec![]);
+        let codewords = pocsag::encode(&[alpha_page(8, "again")]);
+        let start = DateTime::from_timestamp(0, 0).unwrap();
+
+        pager.receive_codewords_at(&codewords, start);
+        pager.receive_codewords_at(&codewords, start + TimeDelta::minutes(4));
+        assert_eq!(pager.inbox().len(), 1);
+        assert_eq!(pager.suppressed_duplicates(), 1);
+
+        pager.receive_codewords_at(&codewords, start + TimeDelta::minutes(6));
+        assert_eq!(pager.inbox().len(), 2);
+    }
+
+    #[test]
+    fn inbox_capacity() {
+        let mut pager = pager(PagerMode::Alphanumeric, vec![]);
+        for i in 0..5 {
+            pager.receive_page(&alpha_page(8, &format!("message {i}")));
+        }
+
+        let texts: Vec<_> = pager.inbox().iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(texts, vec!["message 2", "message 3", "message 4"]);
+    }
+
+    #[test]
+    fn skyper_rubrics() {
+        let mut pager = pager(PagerMode::Alphanumeric, vec![2]);
+
+        for msg in [
+            SkyperMessage::rubric(2, "DX").unwrap(),
+            SkyperMessage::rubric(3, "Weather").unwrap(),
+            SkyperMessage::news(2, 1, "first").unwrap(),
+            SkyperMessage::news(2, 1, "replaced").unwrap(),
+            SkyperMessage::news(2, 4, "fourth").unwrap(),
+            SkyperMessage::news(3, 1, "not subscribed").unwrap(),
+        ] {
+            assert!(pager.receive_page(&msg.to_page()).is_empty());
+        }
+
+        let rubric = pager.rubric(2).unwrap();
+        assert_eq!(rubric.label.as_deref(), Some("DX"));
+        assert_eq!(rubric.news[&1].text, "replaced");
+        assert_eq!(rubric.news[&4].text, "fourth");
+        assert!(pager.rubric(3).is_none());
+        assert!(pager.inbox().is_empty());
+    }
+
+    #[tokio::test]
+    async fn call_through_transmitter_protocol() {
+        let transmitter = Transmitter {
+            timeslots: "0123456789ABCDEF".to_string(),
+            ..transmitter("tx", "key")
+        };
+
+        let server = TransmitterServer::new(&[transmitter]);
+        server.add_transmitter_group(&TransmitterGroup {
+            name: "local".to_string(),
+            description: String::new(),
+            transmitters: vec!["tx".to_string()],
+            owners: vec![],
+        });
+        server.add_callsign(
+            &Callsign {
+                name: "m0nxn".to_string(),
+                description: String::new(),
+                numeric: false,
+                owners: vec![],
+            },
+            &[1234567],
+        );
+        server
+            .queue_call(
+                &OutgoingCallBuilder::default()
+                    .text("M0NXN: this is a test".to_string())
+                    .recipients(vec!["m0nxn".to_string()])
+                    .transmitter_groups(vec!["local".to_string()])
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+
+        let (client_stream, server_stream) = tokio::io::duplex(1024);
+        tokio::spawn(async move { server.handle_connection(server_stream).await });
+
+        let config = TransmitterConfigBuilder::default()
+            .name("tx".to_string())
+            .auth_key("key".to_string())
+            .build()
+            .unwrap();
+        let mut client = TransmitterClient::login(client_stream, &config)
+            .await
+            .unwrap();
+
+        let mut pager = pager(PagerMode::Alphanumeric, vec![]);
+        while pager.inbox().is_empty() {
+            if let Some(ServerMessage::Message { message, .. }) =
+                client.next_message().await.unwrap()
+            {
+                pager.receive_message(&message).unwrap();
+            }
+        }
+
+        assert_eq!(pager.inbox()[0].ric, 1234567);
+        assert_eq!(pager.inbox()[0].text, "M0NXN: this is a test");
+    }
+}
@@ -0,0 +1,254 @@
+use super::{Content, Function, IDLE_CODEWORD, NUMERIC_ALPHABET, Page, SYNC_CODEWORD, bch_encode};
+
+/// A page recovered from a transmission, before its content has been interpreted.
+///
+/// How the content is interpreted depends on the receiving pager, a numeric pager will show any
+/// page as numeric text regardless of the function bits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedPage {
+    pub ric: u32,
+    pub function: Function,
+
+    /// The 20 data bits of each message codeword.
+    pub data: Vec<u32>,
+}
+
+impl DecodedPage {
+    /// Interprets the content as characters of the POCSAG numeric alphabet.
+    ///
+    /// Trailing padding is removed.
+    pub fn numeric_text(&self) -> String {
+        let text: String = self
+            .bits()
+            .collect::<Vec<_>>()
+            .as_chunks::<4>()
+            .0
+            .iter()
+            .map(|bits| NUMERIC_ALPHABET[from_lsb_first(bits) as usize])
+            .collect();
+        text.trim_end_matches(' ').to_string()
+    }
+
+    /// Interprets the content as 7 bit characters.
+    ///
+    /// Trailing padding is removed.
+    pub fn alphanumeric_text(&self) -> String {
+        let text: String = self
+            .bits()
+            .collect::<Vec<_>>()
+            .as_chunks::<7>()
+            .0
+            .iter()
+            .map(|bits| from_lsb_first(bits) as u8 as char)
+            .collect();
+        text.trim_end_matches('\0').to_string()
+    }
+
+    /// Interprets the content based on the function bits, in the same way as [`Page::from_call`].
+    pub fn to_page(&self) -> Page {
+        let content = match self.function {
+            Function::Numeric => Content::Numeric(self.numeric_text()),
+            Function::Tone if self.data.is_empty() => Content::Tone,
+            _ => Content::Alphanumeric(self.alphanumeric_text()),
+        };
+
+        Page {
+            ric: self.ric,
+            function: self.function,
+            content,
+        }
+    }
+
+    fn bits(&self) -> impl Iterator<Item = u32> + '_ {
+        self.data
+            .iter()
+            .flat_map(|data| (0..20).rev().map(move |i| (data >> i) & 1))
+    }
+}
+
+fn from_lsb_first(bits: &[u32]) -> u32 {
+    bits.iter()
+        .enumerate()
+        .fold(0, |value, (i, bit)| value | (bit << i))
+}
+
+/// Checks a codeword, correcting a single bit error if required.
+///
+/// Returns `None` if the codeword cannot be corrected.
+pub fn correct_codeword(codeword: u32) -> Option<u32> {
+    let valid = |c: u32| bch_encode(c >> 11) == c;
+
+    if valid(codeword) {
+        return Some(codeword);
+    }
+
+    (0..32)
+        .map(|i| codeword ^ (1 << i))
+        .find(|candidate| valid(*candidate))
+}
+
+/// Decodes the pages in a transmission.
+///
+/// The input is searched for synchronisation codewords, so it may contain the preamble or other
+/// data between batches.
+/// Codewords with single bit errors are corrected, a page containing a codeword that cannot be
+/// corrected is truncated at that point.
+pub fn decode(codewords: &[u32]) -> Vec<DecodedPage> {
+    let mut pages = Vec::new();
+    let mut current: Option<DecodedPage> = None;
+
+    let mut i = 0;
+    while i < codewords.len() {
+        if codewords[i] != SYNC_CODEWORD {
+            i += 1;
+            continue;
+        }
+
+        let batch = &codewords[i + 1..(i + 1 + super::BATCH_LENGTH).min(codewords.len())];
+        for (position, codeword) in batch.iter().enumerate() {
+            match correct_codeword(*codeword) {
+                Some(IDLE_CODEWORD) | None => pages.extend(current.take()),
+                Some(codeword) if codeword >> 31 == 0 => {
+                    pages.extend(current.take());
+                    current = Some(DecodedPage {
+                        ric: (((codeword >> 13) & 0x3_FFFF) << 3) | (position as u32 / 2),
+                        function: function_from_bits((codeword >> 11) & 0b11),
+                        data: Vec::new(),
+                    });
+                }
+                Some(codeword) => {
+                    if let Some(page) = &mut current {
+                        page.data.push((codeword >> 11) & 0xF_FFFF);
+                    }
+                }
+            }
+        }
+
+        i += 1 + batch.len();
+
+        // A page can only continue into the next batch if it immediately follows
+        if codewords.get(i) != Some(&SYNC_CODEWORD) {
+            pages.extend(current.take());
+        }
+    }
+
+    pages.extend(current);
+    pages
+}
+
+fn function_from_bits(bits: u32) -> Function {
+    match bits {
+        0 => Function::Numeric,
+        1 => Function::Tone,
+        2 => Function::Activation,
+        _ => Function::Alphanumeric,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pocsag::encode;
+
+    #[test]
+    fn round_trip() {
+        let pages = vec![
+            Page::new(
+                1234567,
+                Function::Alphanumeric,
+                Content::Alphanumeric("M0NXN: this is a test".to_string()),
+            )
+            .unwrap(),
+            Page::new(
+                2000,
+                Function::Numeric,
+                Content::Numeric("0123 456".to_string()),
+            )
+            .unwrap(),
+            Page::new(8, Function::Tone, Content::Tone).unwrap(),
+            Page::new(
+                15,
+                Function::Activation,
+                Content::Alphanumeric(
+                    "a longer message that definitely spans more than one batch of codewords"
+                        .to_string(),
+                ),
+            )
+            .unwrap(),
+        ];
+
+        let decoded: Vec<Page> = decode(&encode(&pages))
+            .iter()
+            .map(|p| p.to_page())
+            .collect();
+        assert_eq!(decoded, pages);
+    }
+
+    #[test]
+    fn numeric_alphabet_round_trip() {
+        let page = Page::new(
+            3,
+            Function::Numeric,
+            Content::Numeric("0123456789*U -][".to_string()),
+        )
+        .unwrap();
+        assert_eq!(
+            decode(&encode(std::slice::from_ref(&page)))[0].to_page(),
+            page
+        );
+    }
+
+    #[test]
+    fn single_bit_errors_are_corrected() {
+        let page = Page::new(
+            42,
+            Function::Alphanumeric,
+            Content::Alphanumeric("hello".to_string()),
+        )
+        .unwrap();
+
+        let mut codewords = encode(std::slice::from_ref(&page));
+        for (i, codeword) in codewords.iter_mut().skip(19).enumerate() {
+            *codeword ^= 1 << (i % 32);
+        }
+
+        assert_eq!(decode(&codewords)[0].to_page(), page);
+    }
+
+    #[test]
+    fn uncorrectable_codeword_truncates_page() {
+        let page = Page::new(
+            0,
+            Function::Alphanumeric,
+            Content::Alphanumeric("hello world".to_string()),
+        )
+        .unwrap();
+
+        let mut codewords = encode(&[page]);
+        // Address is at 19, corrupt the second message codeword
+        codewords[21] ^= 0b111;
+
+        let decoded = decode(&codewords);
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].data.len(), 1);
+    }
+
+    #[test]
+    fn numeric_interpretation_of_alphanumeric_page() {
+        let page = Page::new(
+            0,
+            Function::Alphanumeric,
+            Content::Alphanumeric("A".to_string()),
+        )
+        .unwrap();
+
+        // 'A' = 1000001 LSB first, padded with zeros: 1000 0010 0000 ...
+        let decoded = &decode(&encode(&[page]))[0];
+        assert_eq!(decoded.numeric_text(), "14000");
+    }
+
+    #[test]
+    fn no_sync() {
+        assert!(decode(&[0xAAAA_AAAA; 10]).is_empty());
+    }
+}
@@ -0,0 +1,477 @@
+//! POCSAG encoding, decoding and waveform synthesis.
+//!
+//! This follows the POCSAG standard (ITU-R M.584) as used by DAPNET: a 576 bit preamble followed by
+//! batches of a synchronisation codeword and eight frames of two codewords each.
+
+mod decoder;
+mod waveform;
+
+pub use self::{
+    decoder::{DecodedPage, correct_codeword, decode},
+    waveform::{
+        Modulation, WaveformOptions, WaveformOptionsBuilder, WaveformOptionsBuilderError,
+        synthesize, write_wav,
+    },
+};
+
+use crate::OutgoingCall;
+
+/// Codeword marking the start of every batch.
+pub const SYNC_CODEWORD: u32 = 0x7CD2_15D8;
+
+/// Codeword used to fill frames that carry no data.
+pub const IDLE_CODEWORD: u32 = 0x7A89_C197;
+
+/// Number of preamble bits sent before the first batch.
+pub const PREAMBLE_LENGTH: usize = 576;
+
+/// Number of codewords in a batch, excluding the synchronisation codeword.
+pub const BATCH_LENGTH: usize = 16;
+
+/// Largest RIC that can be encoded in an address codeword.
+pub const MAX_RIC: u32 = (1 << 21) - 1;
+
+/// Characters that can be sent to numeric pagers, indexed by their 4 bit code.
+pub const NUMERIC_ALPHABET: [char; 16] = [
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', '*', 'U', ' ', '-', ']', '[',
+];
+
+/// Generator polynomial of the BCH(31,21) code protecting each codeword.
+const BCH_POLYNOMIAL: u32 = 0x769;
+
+/// Function bits of an address codeword.
+///
+/// Naming follows the DAPNET core, which uses the function bits to select how the message content
+/// is encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Numeric = 0,
+    Tone = 1,
+    Activation = 2,
+    Alphanumeric = 3,
+}
+
+/// Content of a single page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Content {
+    /// Address only, no message codewords are sent.
+    Tone,
+
+    /// Text using the POCSAG numeric alphabet.
+    Numeric(String),
+
+    /// Text using 7 bit characters.
+    ///
+    /// The characters are sent as their ASCII codes, text for pagers with a different character
+    /// set should first be mapped with [`PagerCharset::encode`](crate::PagerCharset::encode).
+    Alphanumeric(String),
+}
+
+/// A single page addressed to one RIC.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page {
+    ric: u32,
+    function: Function,
+    content: Content,
+}
+
+impl Page {
+    /// Fails if the RIC is out of range or alphanumeric content is not 7 bit ASCII, as it could
+    /// not be sent unchanged.
+    pub fn new(ric: u32, function: Function, content: Content) -> crate::Result<Self> {
+        if ric > MAX_RIC {
+            return Err(crate::Error::InvalidRic(ric));
+        }
+        if let Content::Alphanumeric(text) = &content
+            && !text.is_ascii()
+        {
+            return Err(crate::Error::NonAsciiText(text.clone()));
+        }
+
+        Ok(Self {
+            ric,
+            function,
+            content,
+        })
+    }
+
+    /// Creates a page carrying the text of a call.
+    ///
+    /// The content encoding is selected from the function bits in the same way the DAPNET core does:
+    /// [`Function::Numeric`] sends numeric content, [`Function::Tone`] sends no content and anything
+    /// else sends alphanumeric content.
+    pub fn from_call(call: &OutgoingCall, ric: u32, function: Function) -> crate::Result<Self> {
+        let content = match function {
+            Function::Numeric => Content::Numeric(call.text.clone()),
+            Function::Tone => Content::Tone,
+            Function::Activation | Function::Alphanumeric => {
+                Content::Alphanumeric(call.text.clone())
+            }
+        };
+
+        Self::new(ric, function, content)
+    }
+
+    pub fn ric(&self) -> u32 {
+        self.ric
+    }
+
+    pub fn function(&self) -> Function {
+        self.function
+    }
+
+    pub fn content(&self) -> &Content {
+        &self.content
+    }
+
+    /// The frame (0-7) within a batch in which the address codeword must be sent.
+    pub fn frame(&self) -> usize {
+        (self.ric & 0b111) as usize
+    }
+
+    fn address_codeword(&self) -> u32 {
+        let data = ((self.ric >> 3) << 2) | self.function as u32;
+        bch_encode(data)
+    }
+
+    fn message_codewords(&self) -> Vec<u32> {
+        match &self.content {
+            Content::Tone => Vec::new(),
+            Content::Numeric(text) => pack_bits(text.chars().map(|c| (numeric_value(c), 4)), 0xC),
+            Content::Alphanumeric(text) => pack_bits(text.chars().map(|c| (c as u32, 7)), 0),
+        }
+    }
+}
+
+/// Adds the BCH check bits and even parity bit to 21 bits of data.
+pub fn bch_encode(data: u32) -> u32 {
+    let data = data & 0x1F_FFFF;
+
+    let mut remainder = data << 10;
+    for i in (0..21).rev() {
+        if remainder & (1 << (i + 10)) != 0 {
+            remainder ^= BCH_POLYNOMIAL << i;
+        }
+    }
+
+    let codeword = (data << 11) | (remainder << 1);
+    codeword | (codeword.count_ones() & 1)
+}
+
+/// Encodes pages into a complete transmission, including the preamble.
+///
+/// Each element is one 32 bit word, transmitted most significant bit first.
+/// The preamble is represented as words of alternating ones and zeros.
+pub fn encode(pages: &[Page]) -> Vec<u32> {
+    let mut codewords = vec![0xAAAA_AAAA; PREAMBLE_LENGTH / 32];
+
+    for page in pages {
+        let start = page.frame() * 2;
+
+        // Finish the current batch if the frame for this address has already passed
+        if batch_open(&codewords) && batch_used(&codewords) > start {
+            fill_batch(&mut codewords);
+        }
+
+        // Pad up to the start of the frame for this address
+        while batch_used(&codewords) < start {
+            push_codeword(&mut codewords, IDLE_CODEWORD);
+        }
+
+        push_codeword(&mut codewords, page.address_codeword());
+        for codeword in page.message_codewords() {
+            push_codeword(&mut codewords, codeword);
+        }
+    }
+
+    fill_batch(&mut codewords);
+
+    codewords
+}
+
+/// Number of words (including the synchronisation codeword) in the current batch.
+fn batch_position(codewords: &[u32]) -> usize {
+    (codewords.len() - PREAMBLE_LENGTH / 32) % (BATCH_LENGTH + 1)
+}
+
+fn batch_open(codewords: &[u32]) -> bool {
+    batch_position(codewords) != 0
+}
+
+/// Number of codewords already used in the current (or next) batch.
+fn batch_used(codewords: &[u32]) -> usize {
+    batch_position(codewords).saturating_sub(1)
+}
+
+fn push_codeword(codewords: &mut Vec<u32>, codeword: u32) {
+    if !batch_open(codewords) {
+        codewords.push(SYNC_CODEWORD);
+    }
+    codewords.push(codeword);
+}
+
+fn fill_batch(codewords: &mut Vec<u32>) {
+    while batch_open(codewords) {
+        codewords.push(IDLE_CODEWORD);
+    }
+}
+
+/// Renders a call into a transmission for a single RIC.
+///
+/// Example:
+/// ```
+/// # use dapnet_api::{OutgoingCallBuilder, pocsag};
+/// let call = OutgoingCallBuilder::default()
+///     .text("M0NXN: this is a test".to_string())
+///     .recipients(vec!["m0nxn".to_string()])
+///     .transmitter_groups(vec!["uk-all".to_string()])
+///     .build()
+///     .unwrap();
+///
+/// let samples = pocsag::render_call(
+///     &call,
+///     1234567,
+///     pocsag::Function::Alphanumeric,
+///     &pocsag::WaveformOptions::default(),
+/// )
+/// .unwrap();
+/// ```
+pub fn render_call(
+    call: &OutgoingCall,
+    ric: u32,
+    function: Function,
+    options: &WaveformOptions,
+) -> crate::Result<Vec<f32>> {
+    let page = Page::from_call(call, ric, function)?;
+    Ok(synthesize(&encode(&[page]), options))
+}
+
+/// Maps a character to its value in the POCSAG numeric alphabet.
+///
+/// Characters outside the alphabet are sent as a space.
+fn numeric_value(c: char) -> u32 {
+    NUMERIC_ALPHABET.iter().position(|n| *n == c).unwrap_or(0xC) as u32
+}
+
+/// Packs characters into the data bits of message codewords.
+///
+/// Each character is sent least significant bit first, the final codeword is filled with repeats
+/// of `padding`.
+fn pack_bits(chars: impl Iterator<Item = (u32, u32)>, padding: u32) -> Vec<u32> {
+    let mut codewords = Vec::new();
+    let mut data = 0u32;
+    let mut bits = 0;
+
+    let mut push_bit = |bit: u32, data: &mut u32, bits: &mut u32| {
+        *data = (*data << 1) | bit;
+        *bits += 1;
+        if *bits == 20 {
+            codewords.push(bch_encode((1 << 20) | *data));
+            *data = 0;
+            *bits = 0;
+        }
+    };
+
+    for (value, width) in chars {
+        for i in 0..width {
+            push_bit((value >> i) & 1, &mut data, &mut bits);
+        }
+    }
+
+    let width = if padding == 0 { 1 } else { 4 };
+    let mut i = 0;
+    while bits != 0 {
+        push_bit((padding >> (i % width)) & 1, &mut data, &mut bits);
+        i += 1;
+    }
+
+    codewords
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::OutgoingCallBuilder;
+
+    /// Set this environment variable to regenerate the golden files instead of checking them.
+    const UPDATE_GOLDEN: &str = "DAPNET_UPDATE_GOLDEN";
+
+    fn check_golden(name: &str, expected: &[u8], actual: &[u8]) {
+        if std::env::var_os(UPDATE_GOLDEN).is_some() {
+            let path = format!("{}/testdata/pocsag/{name}", env!("CARGO_MANIFEST_DIR"));
+            std::fs::write(path, actual).unwrap();
+        } else {
+            assert!(
+                expected == actual,
+                "output does not match golden file {name}"
+            );
+        }
+    }
+
+    fn test_call(text: &str) -> OutgoingCall {
+        OutgoingCallBuilder::default()
+            .text(text.to_string())
+            .recipients(vec!["m0nxn".to_string()])
+            .transmitter_groups(vec!["all".to_string()])
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn bch_encode_sync_codeword() {
+        assert_eq!(bch_encode(SYNC_CODEWORD >> 11), SYNC_CODEWORD);
+    }
+
+    #[test]
+    fn bch_encode_idle_codeword() {
+        assert_eq!(bch_encode(IDLE_CODEWORD >> 11), IDLE_CODEWORD);
+    }
+
+    #[test]
+    fn ric_out_of_range() {
+        assert!(Page::new(MAX_RIC + 1, Function::Tone, Content::Tone).is_err());
+        assert!(Page::new(MAX_RIC, Function::Tone, Content::Tone).is_ok());
+    }
+
+    #[test]
+    fn alphanumeric_must_be_ascii() {
+        let page = |text: &str| {
+            Page::new(
+                8,
+                Function::Alphanumeric,
+                Content::Alphanumeric(text.to_string()),
+            )
+        };
+        assert!(matches!(page("Grüße"), Err(crate::Error::NonAsciiText(_))));
+        assert!(page(&crate::PagerCharset::Din66003.encode("Grüße")).is_ok());
+    }
+
+    #[test]
+    fn tone_page_layout() {
+        let page = Page::new((0b1010 << 3) | 0b011, Function::Tone, Content::Tone).unwrap();
+        let codewords = encode(&[page]);
+
+        assert_eq!(codewords.len(), 18 + 1 + BATCH_LENGTH);
+        assert!(codewords[..18].iter().all(|c| *c == 0xAAAA_AAAA));
+        assert_eq!(codewords[18], SYNC_CODEWORD);
+
+        let batch = &codewords[19..];
+        for (i, codeword) in batch.iter().enumerate() {
+            if i == 6 {
+                assert_eq!(*codeword, bch_encode((0b1010 << 2) | 1));
+            } else {
+                assert_eq!(*codeword, IDLE_CODEWORD);
+            }
+        }
+    }
+
+    #[test]
+    fn message_spans_batches() {
+        let page = Page::new(
+            7,
+            Function::Alphanumeric,
+            Content::Alphanumeric("forty characters that cross two batches.".into()),
+        )
+        .unwrap();
+        let codewords = encode(&[page]);
+
+        // 40 characters * 7 bits = 280 bits = 14 message codewords
+        let batches: Vec<_> = codewords[18..].chunks(BATCH_LENGTH + 1).collect();
+        assert_eq!(batches.len(), 2);
+        assert!(batches.iter().all(|b| b.len() == BATCH_LENGTH + 1));
+        assert!(batches.iter().all(|b| b[0] == SYNC_CODEWORD));
+        assert!(batches[0][1..15].iter().all(|c| *c == IDLE_CODEWORD));
+        assert_eq!(batches[0][15] >> 31, 0);
+        assert_eq!(batches[0][16] >> 31, 1);
+        assert!(batches[1][1..14].iter().all(|c| c >> 31 == 1));
+        assert!(batches[1][14..].iter().all(|c| *c == IDLE_CODEWORD));
+    }
+
+    #[test]
+    fn second_page_starts_new_batch_when_frame_has_passed() {
+        let pages = [
+            Page::new(
+                5,
+                Function::Alphanumeric,
+                Content::Alphanumeric("hi".into()),
+            )
+            .unwrap(),
+            Page::new(1, Function::Tone, Content::Tone).unwrap(),
+        ];
+        let codewords = encode(&pages);
+
+        assert_eq!(codewords.len(), 18 + 2 * (BATCH_LENGTH + 1));
+        assert_eq!(codewords[18 + 17], SYNC_CODEWORD);
+        assert_eq!(codewords[18 + 17 + 1 + 2], bch_encode(1));
+    }
+
+    #[test]
+    fn numeric_padding() {
+        let page = Page::new(8, Function::Numeric, Content::Numeric("12".into())).unwrap();
+        let codewords = encode(&[page]);
+
+        // "1" = 0001 -> 1000, "2" = 0010 -> 0100, then 3 spaces 1100 -> 0011
+        let expected = bch_encode((1 << 20) | 0b1000_0100_0011_0011_0011);
+        assert_eq!(codewords[18 + 1 + 1], expected);
+    }
+
+    #[test]
+    fn golden_codewords() {
+        let call = test_call("M0NXN: this is a test");
+        let page = Page::from_call(&call, 1234567, Function::Alphanumeric).unwrap();
+
+        let actual: String = encode(&[page])
+            .iter()
+            .map(|c| format!("{c:08X}\n"))
+            .collect();
+
+        check_golden(
+            "alphanumeric.txt",
+            include_bytes!("../../testdata/pocsag/alphanumeric.txt"),
+            actual.as_bytes(),
+        );
+    }
+
+    #[test]
+    fn golden_baseband_wav() {
+        let options = WaveformOptionsBuilder::default()
+            .sample_rate(9600)
+            .build()
+            .unwrap();
+        let samples =
+            render_call(&test_call("0123 456"), 2000, Function::Numeric, &options).unwrap();
+
+        let mut wav = Vec::new();
+        write_wav(&samples, options.sample_rate(), &mut wav).unwrap();
+
+        check_golden(
+            "numeric_baseband.wav",
+            include_bytes!("../../testdata/pocsag/numeric_baseband.wav"),
+            &wav,
+        );
+    }
+
+    #[test]
+    fn golden_audio_wav() {
+        let options = WaveformOptionsBuilder::default()
+            .baud_rate(512)
+            .sample_rate(8000)
+            .modulation(Modulation::Audio {
+                centre_frequency: 1500.0,
+            })
+            .deviation(600.0)
+            .build()
+            .unwrap();
+        let samples =
+            render_call(&test_call("Hi"), 1234567, Function::Alphanumeric, &options).unwrap();
+
+        let mut wav = Vec::new();
+        write_wav(&samples, options.sample_rate(), &mut wav).unwrap();
+
+        check_golden(
+            "alphanumeric_audio.wav",
+            include_bytes!("../../testdata/pocsag/alphanumeric_audio.wav"),
+            &wav,
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use derive_builder::Builder;
+use std::{f32::consts::TAU, io::Write};
+
+/// How the bit stream is turned into samples.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Modulation {
+    /// NRZ baseband, suitable for the external FM input of a signal generator or transmitter.
+    ///
+    /// Samples are scaled such that a deviation of `full_scale_deviation` Hz is a sample value of 1.0.
+    Baseband { full_scale_deviation: f32 },
+
+    /// Continuous phase FSK audio centred on `centre_frequency` Hz.
+    Audio { centre_frequency: f32 },
+}
+
+/// Options that control how a transmission is synthesized.
+#[derive(Debug, Clone, PartialEq, Builder)]
+#[builder(build_fn(validate = "Self::validate"))]
+pub struct WaveformOptions {
+    /// Bit rate of the transmission, DAPNET uses 1200 baud.
+    #[builder(default = "1200")]
+    baud_rate: u32,
+
+    /// Sample rate of the generated signal in Hz.
+    #[builder(default = "48000")]
+    sample_rate: u32,
+
+    /// Frequency deviation in Hz.
+    #[builder(default = "4500.0")]
+    deviation: f32,
+
+    #[builder(default = "Modulation::Baseband { full_scale_deviation: 5000.0 }")]
+    modulation: Modulation,
+
+    /// Swap the frequencies used for ones and zeros.
+    ///
+    /// By default a one is sent as a negative deviation, as specified by POCSAG.
+    #[builder(default = "false")]
+    invert: bool,
+}
+
+impl WaveformOptionsBuilder {
+    fn validate(&self) -> Result<(), String> {
+        if self.baud_rate == Some(0) || self.sample_rate == Some(0) {
+            Err("Baud rate and sample rate must not be zero".to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for WaveformOptions {
+    fn default() -> Self {
+        Self {
+            baud_rate: 1200,
+            sample_rate: 48000,
+            deviation: 4500.0,
+            modulation: Modulation::Baseband {
+                full_scale_deviation: 5000.0,
+            },
+            invert: false,
+        }
+    }
+}
+
+impl WaveformOptions {
+    pub fn baud_rate(&self) -> u32 {
+        self.baud_rate
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
+
+/// Synthesizes the signal for a sequence of 32 bit words, sent most significant bit first.
+pub fn synthesize(codewords: &[u32], options: &WaveformOptions) -> Vec<f32> {
+    let bits: Vec<bool> = codewords
+        .iter()
+        .flat_map(|word| (0..32).rev().map(move |i| (word >> i) & 1 == 1))
+        .collect();
+
+    let sample_count = bits.len() as u64 * options.sample_rate as u64 / options.baud_rate as u64;
+
+    let mut phase = 0.0f32;
+
+    (0..sample_count)
+        .map(|n| {
+            let bit = bits[(n * options.baud_rate as u64 / options.sample_rate as u64) as usize];
+            let deviation = if bit != options.invert {
+                -options.deviation
+            } else {
+                options.deviation
+            };
+
+            match options.modulation {
+                Modulation::Baseband {
+                    full_scale_deviation,
+                } => (deviation / full_scale_deviation).clamp(-1.0, 1.0),
+                Modulation::Audio { centre_frequency } => {
+                    phase = (phase
+                        + TAU * (centre_frequency + deviation) / options.sample_rate as f32)
+                        % TAU;
+                    phase.sin()
+                }
+            }
+        })
+        .collect()
+}
+
+/// Writes samples as a mono 16 bit PCM WAV file.
+pub fn write_wav<W: Write>(
+    samples: &[f32],
+    sample_rate: u32,
+    mut writer: W,
+) -> std::io::Result<()> {
+    let data_length = samples.len() as u32 * 2;
+
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(36 + data_length).to_le_bytes())?;
+    writer.write_all(b"WAVE")?;
+
+    writer.write_all(b"fmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?;
+    writer.write_all(&1u16.to_le_bytes())?; // PCM
+    writer.write_all(&1u16.to_le_bytes())?; // Mono
+    writer.write_all(&sample_rate.to_le_bytes())?;
+    writer.write_all(&(sample_rate * 2).to_le_bytes())?;
+    writer.write_all(&2u16.to_le_bytes())?;
+    writer.write_all(&16u16.to_le_bytes())?;
+
+    writer.write_all(b"data")?;
+    writer.write_all(&data_length.to_le_bytes())?;
+    for sample in samples {
+        let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+        writer.write_all(&sample.to_le_bytes())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn options_builder_default_matches_options_default() {
+        let a = WaveformOptionsBuilder::default().build().unwrap();
+        let b = WaveformOptions::default();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn zero_rates_are_rejected() {
+        assert!(
+            WaveformOptionsBuilder::default()
+                .baud_rate(0)
+                .build()
+                .is_err()
+        );
+        assert!(
+            WaveformOptionsBuilder::default()
+                .sample_rate(0)
+                .build()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn baseband_levels() {
+        let options = WaveformOptionsBuilder::default()
+            .baud_rate(1)
+            .sample_rate(2)
+            .deviation(2500.0)
+            .build()
+            .unwrap();
+
+        let samples = synthesize(&[0x8000_0001], &options);
+        assert_eq!(samples.len(), 64);
+        assert_eq!(&samples[0..2], &[-0.5, -0.5]);
+        assert!(samples[2..62].iter().all(|s| *s == 0.5));
+        assert_eq!(&samples[62..], &[-0.5, -0.5]);
+    }
+
+    #[test]
+    fn baseband_inverted() {
+        let options = WaveformOptionsBuilder::default()
+            .baud_rate(1)
+            .sample_rate(1)
+            .invert(true)
+            .build()
+            .unwrap();
+
+        let samples = synthesize(&[0x8000_0000], &options);
+        assert_eq!(samples[0], 0.9);
+        assert_eq!(samples[1], -0.9);
+    }
+
+    #[test]
+    fn fractional_samples_per_bit() {
+        let options = WaveformOptionsBuilder::default()
+            .baud_rate(512)
+            .sample_rate(48000)
+            .build()
+            .unwrap();
+
+        let samples = synthesize(&[0; 16], &options);
+        assert_eq!(samples.len(), 48000);
+    }
+
+    #[test]
+    fn wav_header() {
+        let mut wav = Vec::new();
+        write_wav(&[0.0, 1.0, -1.0], 8000, &mut wav).unwrap();
+
+        assert_eq!(wav.len(), 44 + 6);
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(&wav[8..12], b"WAVE");
+        assert_eq!(u32::from_le_bytes(wav[24..28].try_into().unwrap()), 8000);
+        assert_eq!(u32::from_le_bytes(wav[40..44].try_into().unwrap()), 6);
+        assert_eq!(&wav[44..], &[0x00, 0x00, 0xFF, 0x7F, 0x01, 0x80]);
+    }
+}
@@ -0,0 +1,518 @@
+use crate::{OutgoingCall, OutgoingNews};
+use derive_builder::Builder;
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+use tokio::time::Instant;
+
+/// A token bucket rate: up to `burst` sends at once, refilled evenly over `per`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rate {
+    burst: u32,
+    per: Duration,
+}
+
+impl Rate {
+    /// Allows `burst` sends in any period of length `per`.
+    ///
+    /// # Panics
+    /// If `burst` or `per` are zero.
+    pub fn new(burst: u32, per: Duration) -> Self {
+        assert!(burst > 0, "rate burst must not be zero");
+        assert!(!per.is_zero(), "rate period must not be zero");
+        Self { burst, per }
+    }
+
+    pub fn per_minute(burst: u32) -> Self {
+        Self::new(burst, Duration::from_secs(60))
+    }
+
+    pub fn per_hour(burst: u32) -> Self {
+        Self::new(burst, Duration::from_secs(60 * 60))
+    }
+
+    /// Time taken to refill one token.
+    fn interval(&self) -> Duration {
+        self.per / self.burst
+    }
+}
+
+/// Rate limits for one kind of message.
+///
+/// For news the rubric takes the place of the recipient and there are no transmitter groups.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RateLimitBudget {
+    global: Option<Rate>,
+    per_recipient: Option<Rate>,
+    per_transmitter_group: Option<Rate>,
+}
+
+impl RateLimitBudget {
+    /// Creates a budget with no limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits all messages sent by the client.
+    pub fn with_global(mut self, rate: Rate) -> Self {
+        self.global = Some(rate);
+        self
+    }
+
+    /// Limits messages to each recipient (or rubric) separately.
+    pub fn with_per_recipient(mut self, rate: Rate) -> Self {
+        self.per_recipient = Some(rate);
+        self
+    }
+
+    /// Limits messages sent with each transmitter group separately.
+    pub fn with_per_transmitter_group(mut self, rate: Rate) -> Self {
+        self.per_transmitter_group = Some(rate);
+        self
+    }
+
+    fn buckets(&self, kind: Kind, recipients: &[String], groups: &[String]) -> Vec<(Key, Rate)> {
+        let scoped = |rate: Option<Rate>, names: &[String], scope: fn(String) -> Scope| {
+            rate.into_iter()
+                .flat_map(move |rate| {
+                    names
+                        .iter()
+                        .map(move |name| ((kind, scope(name.to_lowercase())), rate))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        self.global
+            .map(|rate| ((kind, Scope::Global), rate))
+            .into_iter()
+            .chain(scoped(self.per_recipient, recipients, Scope::Recipient))
+            .chain(scoped(
+                self.per_transmitter_group,
+                groups,
+                Scope::TransmitterGroup,
+            ))
+            .collect()
+    }
+}
+
+/// What to do when a message would exceed a rate limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitAction {
+    /// Wait until the message can be sent.
+    Wait,
+
+    /// Return [`Error::RateLimited`](crate::Error::RateLimited) without sending the message.
+    Reject,
+}
+
+/// How emergency calls are rate limited.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmergencyPolicy {
+    /// Emergency calls are limited in the same way as other calls.
+    SameAsCalls,
+
+    /// Emergency calls are never limited, but they still use up the call budget.
+    Bypass,
+
+    /// Emergency calls have their own budget and action.
+    Separate(RateLimitBudget, RateLimitAction),
+}
+
+/// Client side rate limits, see [`Client::with_rate_limits`](crate::Client::with_rate_limits).
+///
+/// Example:
+/// ```
+/// # use dapnet_api::{Client, Rate, RateLimitAction, RateLimitBudget, RateLimitsBuilder};
+/// let limits = RateLimitsBuilder::default()
+///     .calls(
+///         RateLimitBudget::new()
+///             .with_global(Rate::per_minute(10))
+///             .with_per_recipient(Rate::per_minute(3))
+///             .with_per_transmitter_group(Rate::per_hour(60)),
+///     )
+///     .news(RateLimitBudget::new().with_per_recipient(Rate::per_hour(4)))
+///     .action(RateLimitAction::Reject)
+///     .build()
+///     .unwrap();
+///
+/// let client = Client::new("m0nxn", "my_super_secret_password").with_rate_limits(limits);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Builder)]
+pub struct RateLimits {
+    /// Limits for calls.
+    #[builder(default)]
+    calls: RateLimitBudget,
+
+    /// Limits for news, the rubric counts as the recipient.
+    #[builder(default)]
+    news: RateLimitBudget,
+
+    #[builder(default = "RateLimitAction::Wait")]
+    action: RateLimitAction,
+
+    #[builder(default = "EmergencyPolicy::SameAsCalls")]
+    emergency: EmergencyPolicy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Kind {
+    Calls,
+    EmergencyCalls,
+    News,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Scope {
+    Global,
+    Recipient(String),
+    TransmitterGroup(String),
+}
+
+type Key = (Kind, Scope);
+
+fn describe((kind, scope): &Key) -> String {
+    let kind = match kind {
+        Kind::Calls => "calls",
+        Kind::EmergencyCalls => "emergency calls",
+        Kind::News => "news",
+    };
+    match scope {
+        Scope::Global => kind.to_string(),
+        Scope::Recipient(name) => format!("{kind} to {name}"),
+        Scope::TransmitterGroup(name) => format!("{kind} via transmitter group {name}"),
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, rate: Rate, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() / rate.interval().as_secs_f64())
+            .min(rate.burst as f64);
+        self.updated = now;
+    }
+
+    /// Time until a token is available.
+    fn wait(&self, rate: Rate) -> Duration {
+        if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            rate.interval().mul_f64(1.0 - self.tokens)
+        }
+    }
+}
+
+/// Token buckets shared by a client and its clones.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    limits: RateLimits,
+    buckets: Mutex<HashMap<Key, Bucket>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(limits: RateLimits) -> Self {
+        Self {
+            limits,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Waits until a call can be sent, or fails if it cannot be sent now and the policy is to
+    /// reject.
+    pub(crate) async fn acquire_call(&self, call: &OutgoingCall) -> crate::Result<()> {
+        let limits = &self.limits;
+        let (kind, budget, action, enforce) = match (&limits.emergency, call.emergency) {
+            (EmergencyPolicy::Bypass, true) => (Kind::Calls, &limits.calls, limits.action, false),
+            (EmergencyPolicy::Separate(budget, action), true) => {
+                (Kind::EmergencyCalls, budget, *action, true)
+            }
+            _ => (Kind::Calls, &limits.calls, limits.action, true),
+        };
+
+        let buckets = budget.buckets(kind, &call.recipients, &call.transmitter_groups);
+        self.acquire(&buckets, action, enforce).await
+    }
+
+    pub(crate) async fn acquire_news(&self, news: &OutgoingNews) -> crate::Result<()> {
+        let buckets = self
+            .limits
+            .news
+            .buckets(Kind::News, std::slice::from_ref(&news.rubric), &[]);
+        self.acquire(&buckets, self.limits.action, true).await
+    }
+
+    async fn acquire(
+        &self,
+        buckets: &[(Key, Rate)],
+        action: RateLimitAction,
+        enforce: bool,
+    ) -> crate::Result<()> {
+        loop {
+            match self.try_acquire(buckets, enforce) {
+                None => return Ok(()),
+                Some((key, wait)) => match action {
+                    RateLimitAction::Wait => tokio::time::sleep(wait).await,
+                    RateLimitAction::Reject => {
+                        return Err(crate::Error::RateLimited {
+                            limit: describe(key),
+                            retry_after: wait,
+                        });
+                    }
+                },
+            }
+        }
+    }
+
+    /// Takes a token from every bucket if they all have one, otherwise returns the bucket that
+    /// will take longest to have one and how long that is.
+    ///
+    /// When not enforcing, tokens are always taken and buckets may go into debt.
+    fn try_acquire<'a>(
+        &self,
+        buckets: &'a [(Key, Rate)],
+        enforce: bool,
+    ) -> Option<(&'a Key, Duration)> {
+        let now = Instant::now();
+        let mut state = self.buckets.lock().unwrap();
+
+        let mut longest: Option<(&Key, Duration)> = None;
+        for (key, rate) in buckets {
+            let bucket = state.entry(key.clone()).or_insert_with(|| Bucket {
+                tokens: rate.burst as f64,
+                updated: now,
+            });
+            bucket.refill(*rate, now);
+
+            let wait = bucket.wait(*rate);
+            if !wait.is_zero() && longest.is_none_or(|(_, longest)| wait > longest) {
+                longest = Some((key, wait));
+            }
+        }
+
+        if enforce && longest.is_some() {
+            return longest;
+        }
+
+        for (key, _) in buckets {
+            state.get_mut(key).unwrap().tokens -= 1.0;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{OutgoingCallBuilder, OutgoingNewsBuilder};
+
+    fn call(recipients: &[&str], groups: &[&str], emergency: bool) -> OutgoingCall {
+        OutgoingCallBuilder::default()
+            .text("test".to_string())
+            .recipients(recipients.iter().map(|r| r.to_string()).collect())
+            .transmitter_groups(groups.iter().map(|g| g.to_string()).collect())
+            .emergency(emergency)
+            .build()
+            .unwrap()
+    }
+
+    fn limiter(calls: RateLimitBudget, emergency: EmergencyPolicy) -> RateLimiter {
+        RateLimiter::new(
+            RateLimitsBuilder::default()
+                .calls(calls)
+                .news(RateLimitBudget::new().with_global(Rate::per_minute(1)))
+                .action(RateLimitAction::Reject)
+                .emergency(emergency)
+                .build()
+                .unwrap(),
+        )
+    }
+
+    fn retry_after(result: crate::Result<()>) -> (String, Duration) {
+        match result {
+            Err(crate::Error::RateLimited { limit, retry_after }) => (limit, retry_after),
+            other => panic!("expected rate limit, got {other:?}"),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn global_burst_and_refill() {
+        let limiter = limiter(
+            RateLimitBudget::new().with_global(Rate::per_minute(2)),
+            EmergencyPolicy::SameAsCalls,
+        );
+        let call = call(&["m0nxn"], &["uk-all"], false);
+
+        limiter.acquire_call(&call).await.unwrap();
+        limiter.acquire_call(&call).await.unwrap();
+        assert_eq!(
+            retry_after(limiter.acquire_call(&call).await),
+            ("calls".to_string(), Duration::from_secs(30))
+        );
+
+        tokio::time::advance(Duration::from_secs(20)).await;
+        let (_, wait) = retry_after(limiter.acquire_call(&call).await);
+        assert_eq!(wait.as_secs(), 10);
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        limiter.acquire_call(&call).await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn per_recipient_and_group() {
+        let limiter = limiter(
+            RateLimitBudget::new()
+                .with_per_recipient(Rate::per_minute(1))
+                .with_per_transmitter_group(Rate::per_minute(2)),
+            EmergencyPolicy::SameAsCalls,
+        );
+
+        limiter
+            .acquire_call(&call(&["m0nxn"], &["uk-all"], false))
+            .await
+            .unwrap();
+        assert_eq!(
+            retry_after(
+                limiter
+                    .acquire_call(&call(&["M0NXN"], &["uk-se"], false))
+                    .await
+            )
+            .0,
+            "calls to m0nxn"
+        );
+
+        limiter
+            .acquire_call(&call(&["m0abc"], &["uk-all"], false))
+            .await
+            .unwrap();
+        assert_eq!(
+            retry_after(
+                limiter
+                    .acquire_call(&call(&["m0def"], &["uk-all"], false))
+                    .await
+            )
+            .0,
+            "calls via transmitter group uk-all"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rejected_calls_do_not_use_tokens() {
+        let limiter = limiter(
+            RateLimitBudget::new()
+                .with_global(Rate::per_minute(2))
+                .with_per_recipient(Rate::per_minute(1)),
+            EmergencyPolicy::SameAsCalls,
+        );
+
+        limiter
+            .acquire_call(&call(&["m0nxn"], &["uk-all"], false))
+            .await
+            .unwrap();
+        assert!(
+            limiter
+                .acquire_call(&call(&["m0nxn"], &["uk-all"], false))
+                .await
+                .is_err()
+        );
+        limiter
+            .acquire_call(&call(&["m0abc"], &["uk-all"], false))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn wait() {
+        let limiter = RateLimiter::new(
+            RateLimitsBuilder::default()
+                .calls(RateLimitBudget::new().with_global(Rate::per_minute(1)))
+                .build()
+                .unwrap(),
+        );
+        let call = call(&["m0nxn"], &["uk-all"], false);
+
+        let start = Instant::now();
+        limiter.acquire_call(&call).await.unwrap();
+        limiter.acquire_call(&call).await.unwrap();
+        limiter.acquire_call(&call).await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_secs(120));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn emergency_bypass() {
+        let limiter = limiter(
+            RateLimitBudget::new().with_global(Rate::per_minute(1)),
+            EmergencyPolicy::Bypass,
+        );
+
+        limiter
+            .acquire_call(&call(&["m0nxn"], &["uk-all"], true))
+            .await
+            .unwrap();
+        limiter
+            .acquire_call(&call(&["m0nxn"], &["uk-all"], true))
+            .await
+            .unwrap();
+
+        // The emergency calls used up the budget for normal calls
+        let (_, wait) = retry_after(
+            limiter
+                .acquire_call(&call(&["m0nxn"], &["uk-all"], false))
+                .await,
+        );
+        assert_eq!(wait, Duration::from_secs(120));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn emergency_separate_budget() {
+        let limiter = limiter(
+            RateLimitBudget::new().with_global(Rate::per_minute(1)),
+            EmergencyPolicy::Separate(
+                RateLimitBudget::new().with_global(Rate::per_minute(2)),
+                RateLimitAction::Reject,
+            ),
+        );
+
+        limiter
+            .acquire_call(&call(&["m0nxn"], &["uk-all"], false))
+            .await
+            .unwrap();
+        for _ in 0..2 {
+            limiter
+                .acquire_call(&call(&["m0nxn"], &["uk-all"], true))
+                .await
+                .unwrap();
+        }
+        assert_eq!(
+            retry_after(
+                limiter
+                    .acquire_call(&call(&["m0nxn"], &["uk-all"], true))
+                    .await
+            )
+            .0,
+            "emergency calls"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn news_budget_is_separate() {
+        let limiter = limiter(
+            RateLimitBudget::new().with_global(Rate::per_minute(1)),
+            EmergencyPolicy::SameAsCalls,
+        );
+        let news = OutgoingNewsBuilder::default()
+            .rubric("dx".to_string())
+            .text("test".to_string())
+            .build()
+            .unwrap();
+
+        limiter
+            .acquire_call(&call(&["m0nxn"], &["uk-all"], false))
+            .await
+            .unwrap();
+        limiter.acquire_news(&news).await.unwrap();
+        assert_eq!(retry_after(limiter.acquire_news(&news).await).0, "news");
+    }
+}
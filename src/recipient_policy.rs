@@ -0,0 +1,629 @@
+use crate::OutgoingCall;
+use chrono::{DateTime, NaiveTime, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::Mutex,
+};
+
+/// A daily period during which a recipient does not want to be paged.
+///
+/// The period may span midnight, e.g. 22:00 to 07:00.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietHours {
+    start: NaiveTime,
+    end: NaiveTime,
+    timezone: Tz,
+}
+
+impl QuietHours {
+    pub fn new(start: NaiveTime, end: NaiveTime, timezone: Tz) -> Self {
+        Self {
+            start,
+            end,
+            timezone,
+        }
+    }
+
+    /// Returns when the quiet hours end if `now` is within them.
+    pub fn end_after(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let local = now.with_timezone(&self.timezone);
+        let time = local.time();
+
+        let (quiet, end_tomorrow) = if self.start <= self.end {
+            (self.start <= time && time < self.end, false)
+        } else {
+            (time >= self.start || time < self.end, time >= self.start)
+        };
+        if !quiet {
+            return None;
+        }
+
+        let mut date = local.date_naive();
+        if end_tomorrow {
+            date = date.succ_opt()?;
+        }
+        let end = date.and_time(self.end);
+
+        // The end may fall in a gap when clocks go forward, in which case it ends an hour later
+        self.timezone
+            .from_local_datetime(&end)
+            .earliest()
+            .or_else(|| {
+                self.timezone
+                    .from_local_datetime(&(end + TimeDelta::hours(1)))
+                    .earliest()
+            })
+            .map(|end| end.with_timezone(&Utc))
+    }
+}
+
+/// Which restrictions emergency calls are exempt from.
+///
+/// Allowed senders always apply.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmergencyOverride {
+    /// Emergency calls are treated like any other call.
+    Never,
+
+    /// Emergency calls are sent during quiet hours.
+    QuietHours,
+
+    /// Emergency calls are sent during quiet hours and do not count towards the hourly limit.
+    #[default]
+    QuietHoursAndLimit,
+}
+
+/// Delivery rules for a recipient.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RecipientPolicy {
+    quiet_hours: Option<QuietHours>,
+    max_pages_per_hour: Option<u32>,
+    allowed_senders: Vec<String>,
+    emergency_override: EmergencyOverride,
+}
+
+impl RecipientPolicy {
+    /// Creates a policy with no restrictions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The hourly limit that applies to `call`, if any.
+    fn limit(&self, call: &OutgoingCall) -> Option<u32> {
+        let exempt =
+            call.emergency && self.emergency_override == EmergencyOverride::QuietHoursAndLimit;
+        self.max_pages_per_hour.filter(|_| !exempt)
+    }
+
+    pub fn with_quiet_hours(mut self, quiet_hours: QuietHours) -> Self {
+        self.quiet_hours = Some(quiet_hours);
+        self
+    }
+
+    /// Limits the pages sent to the recipient in any one hour period.
+    ///
+    /// A limit of 0 blocks every page the emergency override does not exempt, these are dropped
+    /// rather than deferred.
+    pub fn with_max_pages_per_hour(mut self, max: u32) -> Self {
+        self.max_pages_per_hour = Some(max);
+        self
+    }
+
+    /// Only accepts calls sent by these DAPNET users, by default calls from anyone are accepted.
+    pub fn with_allowed_senders(mut self, senders: Vec<String>) -> Self {
+        self.allowed_senders = senders.iter().map(|s| s.to_lowercase()).collect();
+        self
+    }
+
+    pub fn with_emergency_override(mut self, emergency_override: EmergencyOverride) -> Self {
+        self.emergency_override = emergency_override;
+        self
+    }
+}
+
+/// What to do with a call to a recipient during their quiet hours or over their hourly limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HeldCallAction {
+    /// Do not send the call to the recipient.
+    #[default]
+    Drop,
+
+    /// Keep the call and send it to the recipient once allowed, see
+    /// [`Client::send_deferred_calls`](crate::Client::send_deferred_calls).
+    Defer,
+}
+
+/// Delivery rules for each recipient, keyed by callsign.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RecipientPolicies {
+    policies: HashMap<String, RecipientPolicy>,
+    default: Option<RecipientPolicy>,
+    held: HeldCallAction,
+}
+
+impl RecipientPolicies {
+    /// Creates an empty set of policies, allowing all calls.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_policy(mut self, callsign: &str, policy: RecipientPolicy) -> Self {
+        self.policies.insert(callsign.to_lowercase(), policy);
+        self
+    }
+
+    /// Policy for recipients that do not have their own.
+    pub fn with_default(mut self, policy: RecipientPolicy) -> Self {
+        self.default = Some(policy);
+        self
+    }
+
+    pub fn with_held_calls(mut self, action: HeldCallAction) -> Self {
+        self.held = action;
+        self
+    }
+
+    fn policy(&self, callsign: &str) -> Option<&RecipientPolicy> {
+        self.policies
+            .get(&callsign.to_lowercase())
+            .or(self.default.as_ref())
+    }
+}
+
+/// Why a call was not sent to a recipient.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyViolation {
+    QuietHours {
+        until: DateTime<Utc>,
+    },
+    HourlyLimit {
+        until: DateTime<Utc>,
+    },
+    SenderNotAllowed,
+
+    /// The recipient's hourly limit is 0.
+    PagesNotAllowed,
+}
+
+impl PolicyViolation {
+    /// When the call could be sent to the recipient, if ever.
+    pub fn until(&self) -> Option<DateTime<Utc>> {
+        match self {
+            Self::QuietHours { until } | Self::HourlyLimit { until } => Some(*until),
+            Self::SenderNotAllowed | Self::PagesNotAllowed => None,
+        }
+    }
+}
+
+impl std::fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::QuietHours { until } => write!(f, "quiet hours until {until}"),
+            Self::HourlyLimit { until } => write!(f, "hourly limit reached until {until}"),
+            Self::SenderNotAllowed => write!(f, "sender not allowed"),
+            Self::PagesNotAllowed => write!(f, "no pages allowed"),
+        }
+    }
+}
+
+/// Outcome of checking a call against the policies of its recipients.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PolicyDecision {
+    /// Recipients the call can be sent to now.
+    pub allowed: Vec<String>,
+
+    /// Recipients the call was not sent to.
+    pub held: Vec<(String, PolicyViolation)>,
+}
+
+impl PolicyDecision {
+    /// The call limited to the allowed recipients, if there are any.
+    pub fn allowed_call(&self, call: &OutgoingCall) -> Option<OutgoingCall> {
+        (!self.allowed.is_empty()).then(|| OutgoingCall {
+            recipients: self.allowed.clone(),
+            ..call.clone()
+        })
+    }
+
+    /// Calls to send later, one for each time recipients become available.
+    fn deferred_calls(&self, call: &OutgoingCall) -> Vec<(DateTime<Utc>, OutgoingCall)> {
+        let mut by_time = BTreeMap::<DateTime<Utc>, Vec<String>>::new();
+        for (recipient, violation) in &self.held {
+            if let Some(until) = violation.until() {
+                by_time.entry(until).or_default().push(recipient.clone());
+            }
+        }
+        by_time
+            .into_iter()
+            .map(|(until, recipients)| {
+                (
+                    until,
+                    OutgoingCall {
+                        recipients,
+                        ..call.clone()
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+/// Applies [`RecipientPolicies`] to calls, keeping track of pages sent and calls deferred.
+#[derive(Debug)]
+pub(crate) struct PolicyEnforcer {
+    policies: RecipientPolicies,
+    sent: Mutex<HashMap<String, VecDeque<DateTime<Utc>>>>,
+    deferred: Mutex<Vec<(DateTime<Utc>, OutgoingCall)>>,
+}
+
+impl PolicyEnforcer {
+    pub(crate) fn new(policies: RecipientPolicies) -> Self {
+        Self {
+            policies,
+            sent: Mutex::new(HashMap::new()),
+            deferred: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Decides which recipients `call` can be sent to.
+    ///
+    /// Pages only count towards hourly limits once recorded with
+    /// [`record_sent`](Self::record_sent).
+    /// Recipients held by quiet hours or the hourly limit are deferred if the policies say so.
+    pub(crate) fn check(
+        &self,
+        call: &OutgoingCall,
+        sender: &str,
+        now: DateTime<Utc>,
+    ) -> PolicyDecision {
+        let decision = self.evaluate(call, sender, now);
+        if self.policies.held == HeldCallAction::Defer {
+            self.deferred
+                .lock()
+                .unwrap()
+                .extend(decision.deferred_calls(call));
+        }
+        decision
+    }
+
+    fn evaluate(&self, call: &OutgoingCall, sender: &str, now: DateTime<Utc>) -> PolicyDecision {
+        let hour_ago = now - TimeDelta::hours(1);
+        let mut sent = self.sent.lock().unwrap();
+        let mut decision = PolicyDecision::default();
+
+        for recipient in &call.recipients {
+            let Some(policy) = self.policies.policy(recipient) else {
+                decision.allowed.push(recipient.clone());
+                continue;
+            };
+
+            if !policy.allowed_senders.is_empty()
+                && !policy.allowed_senders.contains(&sender.to_lowercase())
+            {
+                decision
+                    .held
+                    .push((recipient.clone(), PolicyViolation::SenderNotAllowed));
+                continue;
+            }
+
+            let bypass_quiet =
+                call.emergency && policy.emergency_override != EmergencyOverride::Never;
+
+            if !bypass_quiet && let Some(until) = policy.quiet_hours.and_then(|q| q.end_after(now))
+            {
+                decision
+                    .held
+                    .push((recipient.clone(), PolicyViolation::QuietHours { until }));
+                continue;
+            }
+
+            let times = sent.entry(recipient.to_lowercase()).or_default();
+            while times.front().is_some_and(|&time| time <= hour_ago) {
+                times.pop_front();
+            }
+            if let Some(max) = policy.limit(call)
+                && times.len() >= max as usize
+            {
+                let violation = if max == 0 {
+                    PolicyViolation::PagesNotAllowed
+                } else {
+                    PolicyViolation::HourlyLimit {
+                        until: times[times.len() - max as usize] + TimeDelta::hours(1),
+                    }
+                };
+                decision.held.push((recipient.clone(), violation));
+                continue;
+            }
+
+            decision.allowed.push(recipient.clone());
+        }
+
+        decision
+    }
+
+    /// Counts a call that was sent towards the hourly limits of its recipients.
+    pub(crate) fn record_sent(&self, call: &OutgoingCall, now: DateTime<Utc>) {
+        let mut sent = self.sent.lock().unwrap();
+        for recipient in &call.recipients {
+            if self
+                .policies
+                .policy(recipient)
+                .is_some_and(|policy| policy.limit(call).is_some())
+            {
+                sent.entry(recipient.to_lowercase())
+                    .or_default()
+                    .push_back(now);
+            }
+        }
+    }
+
+    /// True if any recipients held by `decision` were deferred.
+    pub(crate) fn defers(&self, decision: &PolicyDecision) -> bool {
+        self.policies.held == HeldCallAction::Defer
+            && decision
+                .held
+                .iter()
+                .any(|(_, violation)| violation.until().is_some())
+    }
+
+    /// Removes and returns the deferred calls that are due.
+    pub(crate) fn take_due(&self, now: DateTime<Utc>) -> Vec<(DateTime<Utc>, OutgoingCall)> {
+        let mut deferred = self.deferred.lock().unwrap();
+        let (due, waiting) = std::mem::take(&mut *deferred)
+            .into_iter()
+            .partition(|(until, _)| *until <= now);
+        *deferred = waiting;
+        due
+    }
+
+    /// Defers calls again, e.g. ones taken by [`take_due`](Self::take_due) that failed to send.
+    pub(crate) fn restore(&self, calls: impl IntoIterator<Item = (DateTime<Utc>, OutgoingCall)>) {
+        self.deferred.lock().unwrap().extend(calls);
+    }
+
+    pub(crate) fn deferred(&self) -> Vec<(DateTime<Utc>, OutgoingCall)> {
+        self.deferred.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::OutgoingCallBuilder;
+
+    fn call(recipients: &[&str], emergency: bool) -> OutgoingCall {
+        OutgoingCallBuilder::default()
+            .text("M0NXN: test".to_string())
+            .recipients(recipients.iter().map(|r| r.to_string()).collect())
+            .transmitter_groups(vec!["uk-all".to_string()])
+            .emergency(emergency)
+            .build()
+            .unwrap()
+    }
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    /// Checks a call and records it as sent to the allowed recipients.
+    fn send(enforcer: &PolicyEnforcer, call: &OutgoingCall, now: DateTime<Utc>) -> PolicyDecision {
+        let decision = enforcer.check(call, "m0nxn", now);
+        if let Some(allowed) = decision.allowed_call(call) {
+            enforcer.record_sent(&allowed, now);
+        }
+        decision
+    }
+
+    fn night() -> QuietHours {
+        QuietHours::new(
+            NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+            NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+            chrono_tz::Europe::London,
+        )
+    }
+
+    #[test]
+    fn quiet_hours_spanning_midnight() {
+        let quiet = night();
+
+        // 22:30 BST
+        assert_eq!(
+            quiet.end_after(utc("2024-07-01T21:30:00Z")),
+            Some(utc("2024-07-02T06:00:00Z"))
+        );
+        // 03:00 BST
+        assert_eq!(
+            quiet.end_after(utc("2024-07-02T02:00:00Z")),
+            Some(utc("2024-07-02T06:00:00Z"))
+        );
+        // 12:00 BST
+        assert_eq!(quiet.end_after(utc("2024-07-02T11:00:00Z")), None);
+        // 22:30 GMT
+        assert_eq!(
+            quiet.end_after(utc("2024-01-01T22:30:00Z")),
+            Some(utc("2024-01-02T07:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn quiet_hours_within_day() {
+        let quiet = QuietHours::new(
+            NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+            chrono_tz::America::New_York,
+        );
+
+        assert_eq!(
+            quiet.end_after(utc("2024-01-02T15:00:00Z")),
+            Some(utc("2024-01-02T22:00:00Z"))
+        );
+        assert_eq!(quiet.end_after(utc("2024-01-02T23:00:00Z")), None);
+    }
+
+    #[test]
+    fn split_by_quiet_hours() {
+        let enforcer = PolicyEnforcer::new(
+            RecipientPolicies::new()
+                .with_policy("M0ABC", RecipientPolicy::new().with_quiet_hours(night())),
+        );
+
+        let call = call(&["m0nxn", "m0abc"], false);
+        let decision = enforcer.check(&call, "m0nxn", utc("2024-07-02T02:00:00Z"));
+        assert_eq!(decision.allowed, vec!["m0nxn"]);
+        assert_eq!(
+            decision.held,
+            vec![(
+                "m0abc".to_string(),
+                PolicyViolation::QuietHours {
+                    until: utc("2024-07-02T06:00:00Z")
+                }
+            )]
+        );
+        assert_eq!(
+            decision.allowed_call(&call).unwrap().recipients,
+            vec!["m0nxn"]
+        );
+
+        // Dropped by default
+        assert!(enforcer.deferred().is_empty());
+    }
+
+    #[test]
+    fn emergency_override() {
+        let policies = |emergency_override| {
+            PolicyEnforcer::new(
+                RecipientPolicies::new().with_default(
+                    RecipientPolicy::new()
+                        .with_quiet_hours(night())
+                        .with_max_pages_per_hour(1)
+                        .with_emergency_override(emergency_override),
+                ),
+            )
+        };
+        let daytime = utc("2024-07-02T12:00:00Z");
+        let nighttime = utc("2024-07-02T02:00:00Z");
+        let emergency = call(&["m0abc"], true);
+
+        let enforcer = policies(EmergencyOverride::Never);
+        assert!(send(&enforcer, &emergency, nighttime).allowed.is_empty());
+
+        let enforcer = policies(EmergencyOverride::QuietHours);
+        assert_eq!(send(&enforcer, &emergency, nighttime).allowed.len(), 1);
+        assert!(send(&enforcer, &emergency, nighttime).allowed.is_empty());
+
+        let enforcer = policies(EmergencyOverride::QuietHoursAndLimit);
+        assert_eq!(send(&enforcer, &emergency, nighttime).allowed.len(), 1);
+        assert_eq!(send(&enforcer, &emergency, nighttime).allowed.len(), 1);
+
+        // Emergency calls did not use up the limit
+        assert_eq!(
+            send(&enforcer, &call(&["m0abc"], false), daytime)
+                .allowed
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn hourly_limit_and_deferral() {
+        let enforcer = PolicyEnforcer::new(
+            RecipientPolicies::new()
+                .with_default(RecipientPolicy::new().with_max_pages_per_hour(2))
+                .with_held_calls(HeldCallAction::Defer),
+        );
+        let call = call(&["m0abc"], false);
+
+        let start = utc("2024-07-02T12:00:00Z");
+        assert_eq!(send(&enforcer, &call, start).allowed.len(), 1);
+        let later = start + TimeDelta::minutes(10);
+        assert_eq!(send(&enforcer, &call, later).allowed.len(), 1);
+
+        let decision = enforcer.check(&call, "m0nxn", start + TimeDelta::minutes(20));
+        assert_eq!(
+            decision.held,
+            vec![(
+                "m0abc".to_string(),
+                PolicyViolation::HourlyLimit {
+                    until: start + TimeDelta::hours(1)
+                }
+            )]
+        );
+
+        assert!(enforcer.take_due(start + TimeDelta::minutes(59)).is_empty());
+        let due = enforcer.take_due(start + TimeDelta::hours(1));
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].1.recipients, vec!["m0abc"]);
+        assert!(enforcer.deferred().is_empty());
+
+        // The first page has aged out of the hour
+        assert_eq!(
+            send(&enforcer, &call, start + TimeDelta::hours(1))
+                .allowed
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn only_sent_pages_count() {
+        let enforcer = PolicyEnforcer::new(
+            RecipientPolicies::new()
+                .with_default(RecipientPolicy::new().with_max_pages_per_hour(1)),
+        );
+        let call = call(&["m0abc"], false);
+        let now = utc("2024-07-02T12:00:00Z");
+
+        // Checked but never sent, e.g. the request failed
+        assert_eq!(enforcer.check(&call, "m0nxn", now).allowed.len(), 1);
+        assert_eq!(send(&enforcer, &call, now).allowed.len(), 1);
+        assert!(send(&enforcer, &call, now).allowed.is_empty());
+    }
+
+    #[test]
+    fn zero_hourly_limit() {
+        let enforcer = PolicyEnforcer::new(
+            RecipientPolicies::new()
+                .with_default(RecipientPolicy::new().with_max_pages_per_hour(0))
+                .with_held_calls(HeldCallAction::Defer),
+        );
+        let now = utc("2024-07-02T12:00:00Z");
+
+        let decision = enforcer.check(&call(&["m0abc"], false), "m0nxn", now);
+        assert_eq!(
+            decision.held,
+            vec![("m0abc".to_string(), PolicyViolation::PagesNotAllowed)]
+        );
+        assert!(!enforcer.defers(&decision));
+        assert!(enforcer.deferred().is_empty());
+
+        // Still exempt by the default emergency override
+        assert_eq!(
+            send(&enforcer, &call(&["m0abc"], true), now).allowed.len(),
+            1
+        );
+    }
+
+    #[test]
+    fn allowed_senders() {
+        let enforcer = PolicyEnforcer::new(
+            RecipientPolicies::new()
+                .with_policy(
+                    "m0abc",
+                    RecipientPolicy::new().with_allowed_senders(vec!["M0NXN".to_string()]),
+                )
+                .with_held_calls(HeldCallAction::Defer),
+        );
+        let call = call(&["m0abc"], true);
+        let now = utc("2024-07-02T12:00:00Z");
+
+        assert_eq!(enforcer.check(&call, "m0nxn", now).allowed, vec!["m0abc"]);
+        assert_eq!(
+            enforcer.check(&call, "m0def", now).held,
+            vec![("m0abc".to_string(), PolicyViolation::SenderNotAllowed)]
+        );
+
+        // Never deferred
+        assert!(enforcer.deferred().is_empty());
+    }
+}
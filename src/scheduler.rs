@@ -0,0 +1,613 @@
+use crate::{Client, OutboxMessage};
+use chrono::{DateTime, TimeDelta, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf, str::FromStr, sync::Mutex, time::Duration};
+
+/// Most occurrences of a job run to catch up after downtime, see [`MissedRunPolicy::RunAll`].
+const MAX_CATCH_UP_RUNS: usize = 100;
+
+/// When a job runs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Schedule {
+    /// Once at the given time.
+    At(DateTime<Utc>),
+
+    /// Whenever a cron expression matches in a time zone.
+    ///
+    /// Expressions have fields for seconds, minutes, hours, day of month, month, day of week and
+    /// optionally year, e.g. `0 0 19 * * Wed` for 19:00 every Wednesday.
+    Cron { expression: String, timezone: Tz },
+}
+
+impl Schedule {
+    /// Runs whenever `expression` matches in `timezone`.
+    pub fn cron(expression: &str, timezone: Tz) -> crate::Result<Self> {
+        parse_cron(expression)?;
+        Ok(Self::Cron {
+            expression: expression.to_string(),
+            timezone,
+        })
+    }
+
+    /// Times the job runs after `after`, in order.
+    fn occurrences_after(
+        &self,
+        after: DateTime<Utc>,
+    ) -> crate::Result<Box<dyn Iterator<Item = DateTime<Utc>>>> {
+        Ok(match self {
+            Self::At(time) => Box::new((*time > after).then_some(*time).into_iter()),
+            Self::Cron {
+                expression,
+                timezone,
+            } => Box::new(
+                parse_cron(expression)?
+                    .after_owned(after.with_timezone(timezone))
+                    .map(|time| time.with_timezone(&Utc)),
+            ),
+        })
+    }
+}
+
+fn parse_cron(expression: &str) -> crate::Result<cron::Schedule> {
+    cron::Schedule::from_str(expression).map_err(|e| {
+        crate::Error::ScheduleError(format!("invalid cron expression \"{expression}\": {e}"))
+    })
+}
+
+/// What to do about runs of a job that were missed, e.g. because the scheduler was not running.
+///
+/// A run is missed if it is more than the scheduler's grace period late, see
+/// [`Scheduler::with_grace`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedRunPolicy {
+    /// Do not run missed runs.
+    #[default]
+    Skip,
+
+    /// Run once if any runs were missed.
+    RunOnce,
+
+    /// Run every missed run, up to 100.
+    RunAll,
+}
+
+/// A call or news item sent on a schedule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledJob {
+    pub name: String,
+    pub schedule: Schedule,
+    pub message: OutboxMessage,
+    pub missed_runs: MissedRunPolicy,
+
+    /// Time of the next run, `None` once a one-off job has run.
+    pub next_run: Option<DateTime<Utc>>,
+
+    pub last_run: Option<DateTime<Utc>>,
+
+    /// Error from the most recent run, if it failed.
+    pub last_error: Option<String>,
+}
+
+impl ScheduledJob {
+    pub fn new(name: &str, schedule: Schedule, message: OutboxMessage) -> Self {
+        Self {
+            name: name.to_string(),
+            schedule,
+            message,
+            missed_runs: MissedRunPolicy::default(),
+            next_run: None,
+            last_run: None,
+            last_error: None,
+        }
+    }
+
+    pub fn with_missed_runs(mut self, policy: MissedRunPolicy) -> Self {
+        self.missed_runs = policy;
+        self
+    }
+
+    /// Works out how many times the job should run at `now` and when it next runs.
+    fn due(
+        &self,
+        now: DateTime<Utc>,
+        grace: TimeDelta,
+    ) -> crate::Result<(usize, Option<DateTime<Utc>>)> {
+        let Some(next_run) = self.next_run.filter(|&next_run| next_run <= now) else {
+            return Ok((0, self.next_run));
+        };
+
+        let mut occurrences = std::iter::once(next_run)
+            .chain(self.schedule.occurrences_after(next_run)?)
+            .peekable();
+        let mut on_time = 0;
+        let mut missed = 0;
+        while let Some(time) = occurrences.next_if(|&time| time <= now) {
+            if time + grace >= now {
+                on_time += 1;
+            } else {
+                missed += 1;
+            }
+        }
+        let next_run = occurrences.next();
+
+        let runs = match self.missed_runs {
+            MissedRunPolicy::Skip => on_time,
+            MissedRunPolicy::RunOnce => (on_time + missed).min(1),
+            MissedRunPolicy::RunAll => on_time + missed,
+        };
+        Ok((runs.min(MAX_CATCH_UP_RUNS), next_run))
+    }
+}
+
+/// Storage for the jobs of a [`Scheduler`], so that they and their run times survive restarts.
+pub trait JobStore: Send + Sync {
+    fn load(&self) -> crate::Result<Vec<ScheduledJob>>;
+    fn save(&self, jobs: &[ScheduledJob]) -> crate::Result<()>;
+}
+
+/// Keeps jobs in memory only.
+#[derive(Debug, Default)]
+pub struct MemoryJobStore(Mutex<Vec<ScheduledJob>>);
+
+impl JobStore for MemoryJobStore {
+    fn load(&self) -> crate::Result<Vec<ScheduledJob>> {
+        Ok(self.0.lock().unwrap().clone())
+    }
+
+    fn save(&self, jobs: &[ScheduledJob]) -> crate::Result<()> {
+        *self.0.lock().unwrap() = jobs.to_vec();
+        Ok(())
+    }
+}
+
+/// Keeps jobs in a JSON file, which is created if it does not exist.
+#[derive(Debug, Clone)]
+pub struct FileJobStore {
+    path: PathBuf,
+}
+
+impl FileJobStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl JobStore for FileJobStore {
+    fn load(&self) -> crate::Result<Vec<ScheduledJob>> {
+        match std::fs::read(&self.path) {
+            Ok(contents) => serde_json::from_slice(&contents).map_err(|e| {
+                crate::Error::ScheduleError(format!(
+                    "invalid job store {}: {e}",
+                    self.path.display()
+                ))
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&self, jobs: &[ScheduledJob]) -> crate::Result<()> {
+        crate::fs::write_atomically(&self.path, &serde_json::to_vec_pretty(jobs).unwrap())?;
+        Ok(())
+    }
+}
+
+/// Sends calls and news through a [`Client`] on a schedule.
+///
+/// Jobs are kept in a [`JobStore`] along with when they next run, so runs missed while the
+/// scheduler was not running are handled according to each job's [`MissedRunPolicy`].
+/// A job that fails to send records the error and runs again at its next scheduled time.
+///
+/// Example:
+/// ```no_run
+/// # use dapnet_api::{Client, FileJobStore, OutboxMessage, OutgoingCallBuilder, Schedule,
+/// #     ScheduledJob, Scheduler};
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let client = Client::new("m0nxn", "my_super_secret_password");
+/// let scheduler = Scheduler::new(client, FileJobStore::new("jobs.json")).unwrap();
+///
+/// scheduler
+///     .add_job(ScheduledJob::new(
+///         "net-reminder",
+///         Schedule::cron("0 0 19 * * Wed", chrono_tz::Europe::London).unwrap(),
+///         OutboxMessage::Call(
+///             OutgoingCallBuilder::default()
+///                 .text("M0NXN: club net at 20:00 on GB3XX".to_string())
+///                 .recipients(vec!["m0nxn".to_string()])
+///                 .transmitter_groups(vec!["uk-all".to_string()])
+///                 .build()
+///                 .unwrap(),
+///         ),
+///     ))
+///     .unwrap();
+///
+/// scheduler.run().await.unwrap();
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Scheduler<S: JobStore> {
+    client: Client,
+    store: S,
+    grace: Duration,
+    jobs: Mutex<BTreeMap<String, ScheduledJob>>,
+    running: tokio::sync::Mutex<()>,
+    changed: tokio::sync::Notify,
+}
+
+impl<S: JobStore> Scheduler<S> {
+    /// Creates a scheduler, loading any existing jobs from `store`.
+    ///
+    /// Jobs with an invalid cron expression are kept but never run, with the error in
+    /// `last_error`.
+    pub fn new(client: Client, store: S) -> crate::Result<Self> {
+        let jobs = store
+            .load()?
+            .into_iter()
+            .map(|mut job| {
+                if let Schedule::Cron { expression, .. } = &job.schedule
+                    && let Err(e) = parse_cron(expression)
+                {
+                    job.last_error = Some(e.to_string());
+                    job.next_run = None;
+                }
+                (job.name.clone(), job)
+            })
+            .collect();
+
+        Ok(Self {
+            client,
+            store,
+            grace: Duration::from_secs(5 * 60),
+            jobs: Mutex::new(jobs),
+            running: tokio::sync::Mutex::new(()),
+            changed: tokio::sync::Notify::new(),
+        })
+    }
+
+    /// Sets how late a run can be before it counts as missed, by default 5 minutes.
+    pub fn with_grace(mut self, grace: Duration) -> Self {
+        self.grace = grace;
+        self
+    }
+
+    /// Adds a job, replacing any job with the same name.
+    ///
+    /// A one-off job whose time has already passed runs once straight away, whatever its
+    /// [`MissedRunPolicy`].
+    pub fn add_job(&self, job: ScheduledJob) -> crate::Result<()> {
+        self.add_job_at(job, Utc::now())
+    }
+
+    fn add_job_at(&self, mut job: ScheduledJob, now: DateTime<Utc>) -> crate::Result<()> {
+        job.next_run = match job.schedule {
+            Schedule::At(time) => Some(time.max(now)),
+            _ => job.schedule.occurrences_after(now)?.next(),
+        };
+
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.insert(job.name.clone(), job);
+        self.save(&jobs)?;
+        drop(jobs);
+
+        self.changed.notify_one();
+        Ok(())
+    }
+
+    /// Removes a job, returning it if it existed.
+    pub fn remove_job(&self, name: &str) -> crate::Result<Option<ScheduledJob>> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = jobs.remove(name);
+        if job.is_some() {
+            self.save(&jobs)?;
+        }
+        Ok(job)
+    }
+
+    pub fn job(&self, name: &str) -> Option<ScheduledJob> {
+        self.jobs.lock().unwrap().get(name).cloned()
+    }
+
+    pub fn jobs(&self) -> Vec<ScheduledJob> {
+        self.jobs.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Runs the jobs that are due, returning how many messages were sent.
+    pub async fn run_due(&self) -> crate::Result<usize> {
+        self.run_due_at(Utc::now()).await
+    }
+
+    async fn run_due_at(&self, now: DateTime<Utc>) -> crate::Result<usize> {
+        let _running = self.running.lock().await;
+        let grace = TimeDelta::from_std(self.grace).unwrap_or(TimeDelta::MAX);
+
+        let mut sent = 0;
+        for mut job in self.jobs() {
+            match job.due(now, grace) {
+                Ok((_, next_run)) if job.next_run == next_run => continue,
+                Ok((runs, next_run)) => {
+                    for _ in 0..runs {
+                        let result = match &job.message {
+                            OutboxMessage::Call(call) => self.client.new_call(call).await,
+                            OutboxMessage::News(news) => self.client.new_news(news).await,
+                        };
+                        job.last_run = Some(now);
+                        job.last_error = result.as_ref().err().map(ToString::to_string);
+                        if result.is_err() {
+                            break;
+                        }
+                        sent += 1;
+                    }
+                    job.next_run = next_run;
+                }
+                // A job whose schedule can't be evaluated stops, without stopping the others
+                Err(e) => {
+                    job.last_error = Some(e.to_string());
+                    job.next_run = None;
+                }
+            }
+
+            // The job may have been replaced or removed while it was running
+            let mut jobs = self.jobs.lock().unwrap();
+            if let Some(current) = jobs.get_mut(&job.name)
+                && current.schedule == job.schedule
+            {
+                *current = job;
+                self.save(&jobs)?;
+            }
+        }
+
+        Ok(sent)
+    }
+
+    /// Runs jobs as they become due, only returning if the store fails.
+    pub async fn run(&self) -> crate::Result<()> {
+        loop {
+            self.run_due().await?;
+
+            let next_run = self.jobs().into_iter().filter_map(|job| job.next_run).min();
+            match next_run {
+                Some(next_run) => {
+                    let wait = (next_run - Utc::now()).to_std().unwrap_or_default();
+                    tokio::select! {
+                        _ = self.changed.notified() => {}
+                        _ = tokio::time::sleep(wait) => {}
+                    }
+                }
+                None => self.changed.notified().await,
+            }
+        }
+    }
+
+    fn save(&self, jobs: &BTreeMap<String, ScheduledJob>) -> crate::Result<()> {
+        self.store.save(&jobs.values().cloned().collect::<Vec<_>>())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{OutgoingNewsBuilder, test_util::unreachable_client};
+
+    fn news() -> OutboxMessage {
+        OutboxMessage::News(
+            OutgoingNewsBuilder::default()
+                .rubric("contest".to_string())
+                .text("M0NXN: 80m CW contest this weekend".to_string())
+                .build()
+                .unwrap(),
+        )
+    }
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    fn weekly() -> Schedule {
+        Schedule::cron("0 0 19 * * Wed", chrono_tz::Europe::London).unwrap()
+    }
+
+    #[test]
+    fn cron_in_timezone() {
+        let summer = weekly()
+            .occurrences_after(utc("2024-07-01T00:00:00Z"))
+            .unwrap()
+            .take(2)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summer,
+            vec![utc("2024-07-03T18:00:00Z"), utc("2024-07-10T18:00:00Z")]
+        );
+
+        let winter = weekly()
+            .occurrences_after(utc("2024-01-01T00:00:00Z"))
+            .unwrap()
+            .next();
+        assert_eq!(winter, Some(utc("2024-01-03T19:00:00Z")));
+    }
+
+    #[test]
+    fn invalid_cron() {
+        assert!(Schedule::cron("every wednesday", chrono_tz::UTC).is_err());
+    }
+
+    #[test]
+    fn one_off() {
+        let mut job = ScheduledJob::new("once", Schedule::At(utc("2024-07-01T12:00:00Z")), news());
+        job.next_run = Some(utc("2024-07-01T12:00:00Z"));
+        let grace = TimeDelta::minutes(5);
+
+        assert_eq!(
+            job.due(utc("2024-07-01T11:59:59Z"), grace).unwrap(),
+            (0, Some(utc("2024-07-01T12:00:00Z")))
+        );
+        assert_eq!(
+            job.due(utc("2024-07-01T12:00:30Z"), grace).unwrap(),
+            (1, None)
+        );
+    }
+
+    #[test]
+    fn missed_runs() {
+        let job = |policy| {
+            let mut job = ScheduledJob::new("weekly", weekly(), news()).with_missed_runs(policy);
+            job.next_run = Some(utc("2024-07-03T18:00:00Z"));
+            job
+        };
+        let grace = TimeDelta::minutes(5);
+
+        // Down for two weeks, back up just after the third run
+        let now = utc("2024-07-17T18:01:00Z");
+        let next = Some(utc("2024-07-24T18:00:00Z"));
+        assert_eq!(
+            job(MissedRunPolicy::Skip).due(now, grace).unwrap(),
+            (1, next)
+        );
+        assert_eq!(
+            job(MissedRunPolicy::RunOnce).due(now, grace).unwrap(),
+            (1, next)
+        );
+        assert_eq!(
+            job(MissedRunPolicy::RunAll).due(now, grace).unwrap(),
+            (3, next)
+        );
+
+        // Back up between runs
+        let now = utc("2024-07-20T00:00:00Z");
+        assert_eq!(
+            job(MissedRunPolicy::Skip).due(now, grace).unwrap(),
+            (0, next)
+        );
+        assert_eq!(
+            job(MissedRunPolicy::RunOnce).due(now, grace).unwrap(),
+            (1, next)
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_runs_are_recorded() {
+        let scheduler = Scheduler::new(unreachable_client(), MemoryJobStore::default()).unwrap();
+
+        scheduler
+            .add_job_at(
+                ScheduledJob::new("weekly", weekly(), news()),
+                utc("2024-07-01T00:00:00Z"),
+            )
+            .unwrap();
+        assert_eq!(
+            scheduler.job("weekly").unwrap().next_run,
+            Some(utc("2024-07-03T18:00:00Z"))
+        );
+
+        assert_eq!(
+            scheduler
+                .run_due_at(utc("2024-07-03T18:00:00Z"))
+                .await
+                .unwrap(),
+            0
+        );
+        let job = scheduler.job("weekly").unwrap();
+        assert!(job.last_error.is_some());
+        assert_eq!(job.last_run, Some(utc("2024-07-03T18:00:00Z")));
+        assert_eq!(job.next_run, Some(utc("2024-07-10T18:00:00Z")));
+
+        // Persisted
+        assert_eq!(
+            scheduler.store.load().unwrap()[0].next_run,
+            Some(utc("2024-07-10T18:00:00Z"))
+        );
+    }
+
+    #[tokio::test]
+    async fn past_one_off_job_runs_once() {
+        let scheduler = Scheduler::new(unreachable_client(), MemoryJobStore::default()).unwrap();
+        let now = utc("2024-07-01T12:00:00Z");
+
+        scheduler
+            .add_job_at(
+                ScheduledJob::new("once", Schedule::At(utc("2024-07-01T11:00:00Z")), news()),
+                now,
+            )
+            .unwrap();
+        assert_eq!(scheduler.job("once").unwrap().next_run, Some(now));
+
+        scheduler.run_due_at(now).await.unwrap();
+        let job = scheduler.job("once").unwrap();
+        assert_eq!(job.last_run, Some(now));
+        assert_eq!(job.next_run, None);
+    }
+
+    #[tokio::test]
+    async fn invalid_cron_does_not_stop_other_jobs() {
+        let invalid = |name: &str| {
+            let mut job = ScheduledJob::new(
+                name,
+                Schedule::Cron {
+                    expression: "every wednesday".to_string(),
+                    timezone: chrono_tz::UTC,
+                },
+                news(),
+            );
+            job.next_run = Some(utc("2024-07-01T00:00:00Z"));
+            job
+        };
+        let store = MemoryJobStore::default();
+        store.save(&[invalid("loaded")]).unwrap();
+
+        let scheduler = Scheduler::new(unreachable_client(), store).unwrap();
+        let loaded = scheduler.job("loaded").unwrap();
+        assert!(
+            loaded
+                .last_error
+                .unwrap()
+                .contains("invalid cron expression")
+        );
+        assert_eq!(loaded.next_run, None);
+
+        // Jobs are not expected to become invalid after loading, but shouldn't stop the scheduler
+        scheduler
+            .jobs
+            .lock()
+            .unwrap()
+            .insert("running".to_string(), invalid("running"));
+        scheduler
+            .add_job_at(
+                ScheduledJob::new("weekly", weekly(), news()),
+                utc("2024-07-01T00:00:00Z"),
+            )
+            .unwrap();
+
+        scheduler
+            .run_due_at(utc("2024-07-03T18:00:00Z"))
+            .await
+            .unwrap();
+        let running = scheduler.job("running").unwrap();
+        assert!(running.last_error.is_some());
+        assert_eq!(running.next_run, None);
+        assert_eq!(
+            scheduler.job("weekly").unwrap().last_run,
+            Some(utc("2024-07-03T18:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn file_store() {
+        let path = std::env::temp_dir().join(format!("dapnet-jobs-{}.json", std::process::id()));
+        let store = FileJobStore::new(&path);
+        assert!(store.load().unwrap().is_empty());
+
+        store
+            .save(&[ScheduledJob::new("weekly", weekly(), news())
+                .with_missed_runs(MissedRunPolicy::RunOnce)])
+            .unwrap();
+        let loaded = store.load();
+        std::fs::remove_file(&path).unwrap();
+
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded[0].schedule, weekly());
+        assert_eq!(loaded[0].missed_runs, MissedRunPolicy::RunOnce);
+    }
+}
@@ -0,0 +1,86 @@
+use serde::{Deserialize, Deserializer};
+use zeroize::Zeroize;
+
+/// A password or key that is redacted when formatted and wiped from memory when dropped.
+///
+/// Example:
+/// ```
+/// # use dapnet_api::Secret;
+/// let secret = Secret::from("my_super_secret_password");
+/// assert_eq!(format!("{secret:?}"), "Secret([REDACTED])");
+/// assert_eq!(secret.to_string(), "[REDACTED]");
+/// assert_eq!(secret.expose_secret(), "my_super_secret_password");
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(secret: String) -> Self {
+        Self(secret)
+    }
+
+    /// The secret itself, take care not to log or otherwise leak it.
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for Secret {
+    fn from(secret: String) -> Self {
+        Self(secret)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(secret: &str) -> Self {
+        Self(secret.to_string())
+    }
+}
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Secret([REDACTED])")
+    }
+}
+
+impl std::fmt::Display for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[REDACTED]")
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn redacted() {
+        let secret = Secret::from("hunter2");
+        assert!(!format!("{secret:?} {secret} {secret:#?}").contains("hunter2"));
+    }
+
+    #[test]
+    fn redacted_in_containing_types() {
+        let transmitter = crate::test_util::transmitter("tx-a", "hunter2");
+        assert_eq!(
+            transmitter.auth_key.as_ref().map(Secret::expose_secret),
+            Some("hunter2")
+        );
+        assert!(!format!("{transmitter:#?}").contains("hunter2"));
+
+        let client = crate::Client::new("m0nxn", "hunter2");
+        assert!(!format!("{client:#?}").contains("hunter2"));
+    }
+}
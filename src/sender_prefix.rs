@@ -0,0 +1,188 @@
+use crate::message_sanitization::truncate;
+
+/// Maximum length of call and news text, including the prefix.
+const MAX_LENGTH: usize = 80;
+
+/// How a [`SenderPrefix`] is applied to outgoing messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SenderPrefixMode {
+    /// Add the prefix to messages that do not already start with it.
+    Insert,
+
+    /// Reject messages that do not start with the prefix.
+    Strict,
+}
+
+/// Identification of the sender at the start of every message, e.g. "M0NXN: ".
+///
+/// Example:
+/// ```
+/// # use dapnet_api::{SenderPrefix, SenderPrefixMode};
+/// let prefix = SenderPrefix::new("M0NXN", SenderPrefixMode::Insert);
+/// assert_eq!(prefix.apply("test").unwrap(), "M0NXN: test");
+/// assert_eq!(prefix.apply("m0nxn: test").unwrap(), "m0nxn: test");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SenderPrefix {
+    callsign: String,
+    separator: String,
+    mode: SenderPrefixMode,
+}
+
+impl SenderPrefix {
+    /// Creates a prefix of the callsign followed by ": ".
+    pub fn new(callsign: &str, mode: SenderPrefixMode) -> Self {
+        Self {
+            callsign: callsign.to_string(),
+            separator: ": ".to_string(),
+            mode,
+        }
+    }
+
+    /// Sets what separates the callsign from the message.
+    ///
+    /// When checking for an existing prefix, surrounding whitespace in the separator is ignored,
+    /// and a separator that ends in a letter or digit, e.g. " de ", must be followed by
+    /// whitespace or the end of the message.
+    pub fn with_separator(mut self, separator: &str) -> Self {
+        self.separator = separator.to_string();
+        self
+    }
+
+    /// The prefix that is inserted.
+    pub fn prefix(&self) -> String {
+        format!("{}{}", self.callsign, self.separator)
+    }
+
+    pub fn mode(&self) -> SenderPrefixMode {
+        self.mode
+    }
+
+    /// Returns the rest of the message if it starts with the callsign (in any case) and the
+    /// separator.
+    fn strip<'a>(&self, msg: &'a str) -> Option<&'a str> {
+        let msg = msg.trim_start();
+
+        let callsign = msg.get(..self.callsign.len())?;
+        if !callsign.eq_ignore_ascii_case(&self.callsign) {
+            return None;
+        }
+
+        let rest = &msg[self.callsign.len()..];
+        let rest = match self.separator.trim() {
+            "" => rest.strip_prefix(char::is_whitespace)?,
+            separator => {
+                let rest = rest.trim_start().strip_prefix(separator)?;
+                // A word separator such as "de" must not match the start of a longer word
+                if separator.ends_with(char::is_alphanumeric)
+                    && !rest.is_empty()
+                    && !rest.starts_with(char::is_whitespace)
+                {
+                    return None;
+                }
+                rest
+            }
+        };
+        Some(rest.trim_start())
+    }
+
+    pub fn is_present(&self, msg: &str) -> bool {
+        self.strip(msg).is_some()
+    }
+
+    /// Applies the prefix to a message.
+    ///
+    /// When a prefix is inserted the message body is truncated so that the result fits within
+    /// 80 characters, the prefix itself is never truncated.
+    pub fn apply(&self, msg: &str) -> crate::Result<String> {
+        if self.is_present(msg) {
+            return Ok(msg.to_string());
+        }
+
+        match self.mode {
+            SenderPrefixMode::Insert => {
+                let prefix = self.prefix();
+                let length = MAX_LENGTH.saturating_sub(prefix.chars().count());
+                Ok(format!(
+                    "{prefix}{}",
+                    truncate(msg.to_string(), length, "...")
+                ))
+            }
+            SenderPrefixMode::Strict => Err(crate::Error::MissingSenderPrefix(self.prefix())),
+        }
+    }
+
+    /// The part of a message after the prefix, in strict mode the prefix must be present.
+    pub(crate) fn body<'a>(&self, msg: &'a str) -> crate::Result<&'a str> {
+        match (self.strip(msg), self.mode) {
+            (Some(body), _) => Ok(body),
+            (None, SenderPrefixMode::Insert) => Ok(msg),
+            (None, SenderPrefixMode::Strict) => {
+                Err(crate::Error::MissingSenderPrefix(self.prefix()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn insert() {
+        let prefix = SenderPrefix::new("M0NXN", SenderPrefixMode::Insert);
+        assert_eq!(prefix.apply("hello").unwrap(), "M0NXN: hello");
+        assert_eq!(prefix.apply("M0NXN: hello").unwrap(), "M0NXN: hello");
+        assert_eq!(prefix.apply("M0NXN:hello").unwrap(), "M0NXN:hello");
+        assert_eq!(
+            prefix.apply("M0NXNX: hello").unwrap(),
+            "M0NXN: M0NXNX: hello"
+        );
+        assert_eq!(
+            prefix.apply("DL1ABC: hello").unwrap(),
+            "M0NXN: DL1ABC: hello"
+        );
+    }
+
+    #[test]
+    fn insert_truncates_body() {
+        let prefix = SenderPrefix::new("M0NXN", SenderPrefixMode::Insert);
+        let msg = prefix.apply(&"x".repeat(80)).unwrap();
+        assert_eq!(msg.chars().count(), 80);
+        assert_eq!(msg, format!("M0NXN: {}...", "x".repeat(70)));
+    }
+
+    #[test]
+    fn strict() {
+        let prefix = SenderPrefix::new("M0NXN", SenderPrefixMode::Strict);
+        assert_eq!(prefix.apply("m0nxn: hello").unwrap(), "m0nxn: hello");
+        assert!(matches!(
+            prefix.apply("hello"),
+            Err(crate::Error::MissingSenderPrefix(_))
+        ));
+        assert!(prefix.apply("DL1ABC: hello").is_err());
+    }
+
+    #[test]
+    fn custom_separator() {
+        let prefix = SenderPrefix::new("M0NXN", SenderPrefixMode::Strict).with_separator(" de ");
+        assert!(prefix.apply("M0NXN de hello").is_ok());
+        assert!(prefix.apply("M0NXN: hello").is_err());
+        assert!(!prefix.is_present("M0NXN delta down"));
+        assert!(prefix.is_present("M0NXN de"));
+
+        let prefix = SenderPrefix::new("M0NXN", SenderPrefixMode::Insert).with_separator(" ");
+        assert_eq!(prefix.apply("M0NXN hello").unwrap(), "M0NXN hello");
+        assert_eq!(prefix.apply("M0NXNhello").unwrap(), "M0NXN M0NXNhello");
+    }
+
+    #[test]
+    fn body() {
+        let prefix = SenderPrefix::new("M0NXN", SenderPrefixMode::Insert);
+        assert_eq!(prefix.body("M0NXN:  hello").unwrap(), "hello");
+        assert_eq!(prefix.body("hello").unwrap(), "hello");
+
+        let prefix = SenderPrefix::new("M0NXN", SenderPrefixMode::Strict);
+        assert!(prefix.body("hello").is_err());
+    }
+}
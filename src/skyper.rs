@@ -0,0 +1,319 @@
+//! Encoding of rubrics and news for Skyper pagers.
+//!
+//! This is the scheme the DAPNET core applies before rubrics and news are transmitted:
+//!
+//! - Rubric names are sent to [`RUBRIC_RIC`] as `'1'`, the rubric number offset by `0x1F`, the
+//!   maximum number of news items offset by `0x20` and then the label.
+//! - News items are sent to [`NEWS_RIC`] as the rubric number offset by `0x1F`, the news number
+//!   offset by `0x20` and then the text.
+//!
+//! In both cases every character of the label/text is offset by one.
+
+use crate::{News, OutgoingNews, Rubric, pocsag};
+
+/// RIC that rubric names are sent to.
+pub const RUBRIC_RIC: u32 = 4512;
+
+/// RIC that news items are sent to.
+pub const NEWS_RIC: u32 = 4520;
+
+/// Number of news items a Skyper pager stores per rubric.
+pub const MAX_NEWS: u8 = 10;
+
+/// Largest rubric number that can be encoded.
+pub const MAX_RUBRIC: u8 = 95;
+
+const RUBRIC_OFFSET: u32 = 0x1F;
+const NEWS_OFFSET: u32 = 0x20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkyperMessage {
+    /// Name of a rubric, shown in the rubric list of the pager.
+    Rubric { number: u8, label: String },
+
+    /// Content of one news item within a rubric.
+    News {
+        rubric: u8,
+        number: u8,
+        text: String,
+    },
+}
+
+impl SkyperMessage {
+    pub fn rubric(number: u8, label: &str) -> crate::Result<Self> {
+        check_rubric_number(number)?;
+        check_text(label)?;
+
+        Ok(Self::Rubric {
+            number,
+            label: label.to_string(),
+        })
+    }
+
+    pub fn news(rubric: u8, number: u8, text: &str) -> crate::Result<Self> {
+        check_rubric_number(rubric)?;
+        check_news_number(number)?;
+        check_text(text)?;
+
+        Ok(Self::News {
+            rubric,
+            number,
+            text: text.to_string(),
+        })
+    }
+
+    /// Creates the message that announces the name of a rubric.
+    pub fn from_rubric(rubric: &Rubric) -> crate::Result<Self> {
+        Self::rubric(rubric_number(rubric)?, &rubric.label)
+    }
+
+    /// Creates the message for a news item that is about to be sent to `rubric`.
+    pub fn from_outgoing_news(news: &OutgoingNews, rubric: &Rubric) -> crate::Result<Self> {
+        Self::from_parts(rubric, &news.rubric, Some(news.number), &news.text)
+    }
+
+    /// Creates the message for a news item that has already been sent to `rubric`.
+    pub fn from_news(news: &News, rubric: &Rubric) -> crate::Result<Self> {
+        Self::from_parts(rubric, &news.rubric, news.number, &news.text)
+    }
+
+    fn from_parts(
+        rubric: &Rubric,
+        rubric_name: &str,
+        number: Option<i8>,
+        text: &str,
+    ) -> crate::Result<Self> {
+        if rubric.name != rubric_name {
+            return Err(crate::Error::SkyperError(format!(
+                "news is for rubric {rubric_name}, not {}",
+                rubric.name
+            )));
+        }
+
+        let number = u8::try_from(number.unwrap_or(1))
+            .map_err(|_| crate::Error::SkyperError(format!("invalid news number {number:?}")))?;
+
+        Self::news(rubric_number(rubric)?, number, text)
+    }
+
+    /// The RIC this message is sent to.
+    pub fn ric(&self) -> u32 {
+        match self {
+            Self::Rubric { .. } => RUBRIC_RIC,
+            Self::News { .. } => NEWS_RIC,
+        }
+    }
+
+    /// Encodes the message into the text that is transmitted.
+    pub fn encode(&self) -> String {
+        let (header, text) = match self {
+            Self::Rubric { number, label } => (
+                vec![
+                    '1' as u32,
+                    *number as u32 + RUBRIC_OFFSET,
+                    MAX_NEWS as u32 + NEWS_OFFSET,
+                ],
+                label,
+            ),
+            Self::News {
+                rubric,
+                number,
+                text,
+            } => (
+                vec![*rubric as u32 + RUBRIC_OFFSET, *number as u32 + NEWS_OFFSET],
+                text,
+            ),
+        };
+
+        header
+            .into_iter()
+            .chain(text.chars().map(|c| c as u32 + 1))
+            .map(|c| char::from_u32(c).unwrap())
+            .collect()
+    }
+
+    /// Creates the alphanumeric page that carries this message.
+    pub fn to_page(&self) -> pocsag::Page {
+        pocsag::Page::new(
+            self.ric(),
+            pocsag::Function::Alphanumeric,
+            pocsag::Content::Alphanumeric(self.encode()),
+        )
+        .unwrap()
+    }
+
+    /// Decodes the text of a message received on `ric`.
+    pub fn decode(ric: u32, text: &str) -> crate::Result<Self> {
+        let chars: Vec<u32> = text.chars().map(|c| c as u32).collect();
+
+        let shift = |chars: &[u32]| -> crate::Result<String> {
+            chars
+                .iter()
+                .map(|c| {
+                    c.checked_sub(1).and_then(char::from_u32).ok_or_else(|| {
+                        crate::Error::SkyperError(format!("invalid character {c:#x}"))
+                    })
+                })
+                .collect()
+        };
+
+        let header_value = |c: Option<&u32>, offset: u32| -> crate::Result<u8> {
+            c.and_then(|c| c.checked_sub(offset))
+                .and_then(|c| u8::try_from(c).ok())
+                .ok_or_else(|| crate::Error::SkyperError("truncated or invalid header".to_string()))
+        };
+
+        match ric {
+            RUBRIC_RIC => {
+                if chars.first() != Some(&('1' as u32)) {
+                    return Err(crate::Error::SkyperError(
+                        "rubric message must start with '1'".to_string(),
+                    ));
+                }
+                let number = header_value(chars.get(1), RUBRIC_OFFSET)?;
+                header_value(chars.get(2), NEWS_OFFSET)?;
+                Self::rubric(number, &shift(&chars[3..])?)
+            }
+            NEWS_RIC => {
+                let rubric = header_value(chars.first(), RUBRIC_OFFSET)?;
+                let number = header_value(chars.get(1), NEWS_OFFSET)?;
+                Self::news(rubric, number, &shift(&chars[2..])?)
+            }
+            _ => Err(crate::Error::SkyperError(format!(
+                "RIC {ric} does not carry Skyper rubric messages"
+            ))),
+        }
+    }
+
+    /// Decodes a received page.
+    pub fn decode_page(page: &pocsag::Page) -> crate::Result<Self> {
+        match page.content() {
+            pocsag::Content::Alphanumeric(text) => Self::decode(page.ric(), text),
+            _ => Err(crate::Error::SkyperError(
+                "Skyper messages must be alphanumeric".to_string(),
+            )),
+        }
+    }
+}
+
+fn rubric_number(rubric: &Rubric) -> crate::Result<u8> {
+    u8::try_from(rubric.number)
+        .map_err(|_| crate::Error::SkyperError(format!("invalid rubric number {}", rubric.number)))
+}
+
+fn check_rubric_number(number: u8) -> crate::Result<()> {
+    if (1..=MAX_RUBRIC).contains(&number) {
+        Ok(())
+    } else {
+        Err(crate::Error::SkyperError(format!(
+            "rubric number {number} must be between 1 and {MAX_RUBRIC}"
+        )))
+    }
+}
+
+fn check_news_number(number: u8) -> crate::Result<()> {
+    if (1..=MAX_NEWS).contains(&number) {
+        Ok(())
+    } else {
+        Err(crate::Error::SkyperError(format!(
+            "news number {number} must be between 1 and {MAX_NEWS}"
+        )))
+    }
+}
+
+fn check_text(text: &str) -> crate::Result<()> {
+    match text.chars().find(|c| !c.is_ascii() || *c == '\x7F') {
+        Some(c) => Err(crate::Error::SkyperError(format!(
+            "character {c:?} cannot be encoded"
+        ))),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::OutgoingNewsBuilder;
+
+    fn test_rubric() -> Rubric {
+        Rubric {
+            name: "dx-news".to_string(),
+            label: "DX News".to_string(),
+            number: 3,
+            transmitter_groups: vec!["all".to_string()],
+            owners: vec!["m0nxn".to_string()],
+        }
+    }
+
+    #[test]
+    fn encode_rubric() {
+        let msg = SkyperMessage::from_rubric(&test_rubric()).unwrap();
+        assert_eq!(msg.ric(), RUBRIC_RIC);
+        assert_eq!(msg.encode(), "1\"*EY!Ofxt");
+    }
+
+    #[test]
+    fn encode_news() {
+        let news = OutgoingNewsBuilder::default()
+            .rubric("dx-news".to_string())
+            .text("Hello".to_string())
+            .number(2)
+            .build()
+            .unwrap();
+
+        let msg = SkyperMessage::from_outgoing_news(&news, &test_rubric()).unwrap();
+        assert_eq!(msg.ric(), NEWS_RIC);
+        assert_eq!(msg.encode(), "\"\"Ifmmp");
+    }
+
+    #[test]
+    fn news_for_wrong_rubric() {
+        let news = OutgoingNewsBuilder::default()
+            .rubric("other".to_string())
+            .text("Hello".to_string())
+            .build()
+            .unwrap();
+
+        assert!(SkyperMessage::from_outgoing_news(&news, &test_rubric()).is_err());
+    }
+
+    #[test]
+    fn invalid_numbers() {
+        assert!(SkyperMessage::rubric(0, "x").is_err());
+        assert!(SkyperMessage::rubric(MAX_RUBRIC + 1, "x").is_err());
+        assert!(SkyperMessage::news(1, 0, "x").is_err());
+        assert!(SkyperMessage::news(1, MAX_NEWS + 1, "x").is_err());
+    }
+
+    #[test]
+    fn non_ascii_text() {
+        assert!(SkyperMessage::news(1, 1, "Grüße").is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let messages = [
+            SkyperMessage::rubric(1, "Weather").unwrap(),
+            SkyperMessage::rubric(MAX_RUBRIC, "~ edge ~").unwrap(),
+            SkyperMessage::news(7, MAX_NEWS, "Contest this weekend: 80m SSB").unwrap(),
+            SkyperMessage::news(1, 1, "").unwrap(),
+        ];
+
+        for msg in messages {
+            assert_eq!(
+                SkyperMessage::decode(msg.ric(), &msg.encode()).unwrap(),
+                msg
+            );
+            assert_eq!(SkyperMessage::decode_page(&msg.to_page()).unwrap(), msg);
+        }
+    }
+
+    #[test]
+    fn decode_invalid() {
+        assert!(SkyperMessage::decode(1234, "1\"*EY").is_err());
+        assert!(SkyperMessage::decode(RUBRIC_RIC, "2\"*EY").is_err());
+        assert!(SkyperMessage::decode(RUBRIC_RIC, "1").is_err());
+        assert!(SkyperMessage::decode(NEWS_RIC, "\"").is_err());
+        assert!(SkyperMessage::decode(NEWS_RIC, "\"\x20").is_err());
+    }
+}
@@ -0,0 +1,555 @@
+use crate::{
+    MessageSanitizationOptions, OutgoingCallBuilder, OutgoingNewsBuilder,
+    message_sanitization::{sanitize_message_with_overflow, truncate},
+};
+use chrono::{DateTime, FixedOffset, Utc, format::StrftimeItems};
+use std::{collections::BTreeMap, str::FromStr};
+
+/// A value that can be substituted into a [`MessageTemplate`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateValue {
+    Text(String),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    Time(DateTime<FixedOffset>),
+}
+
+impl std::fmt::Display for TemplateValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Text(v) => write!(f, "{v}"),
+            Self::Integer(v) => write!(f, "{v}"),
+            Self::Float(v) => write!(f, "{v}"),
+            Self::Bool(v) => write!(f, "{v}"),
+            Self::Time(v) => write!(f, "{}", v.format(DEFAULT_TIME_FORMAT)),
+        }
+    }
+}
+
+impl From<&str> for TemplateValue {
+    fn from(v: &str) -> Self {
+        Self::Text(v.to_string())
+    }
+}
+
+impl From<String> for TemplateValue {
+    fn from(v: String) -> Self {
+        Self::Text(v)
+    }
+}
+
+impl From<i64> for TemplateValue {
+    fn from(v: i64) -> Self {
+        Self::Integer(v)
+    }
+}
+
+impl From<i32> for TemplateValue {
+    fn from(v: i32) -> Self {
+        Self::Integer(v.into())
+    }
+}
+
+impl From<u32> for TemplateValue {
+    fn from(v: u32) -> Self {
+        Self::Integer(v.into())
+    }
+}
+
+impl From<f64> for TemplateValue {
+    fn from(v: f64) -> Self {
+        Self::Float(v)
+    }
+}
+
+impl From<bool> for TemplateValue {
+    fn from(v: bool) -> Self {
+        Self::Bool(v)
+    }
+}
+
+impl From<DateTime<Utc>> for TemplateValue {
+    fn from(v: DateTime<Utc>) -> Self {
+        Self::Time(v.fixed_offset())
+    }
+}
+
+impl From<DateTime<FixedOffset>> for TemplateValue {
+    fn from(v: DateTime<FixedOffset>) -> Self {
+        Self::Time(v)
+    }
+}
+
+/// Named values used to render a [`MessageTemplate`].
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TemplateVariables {
+    values: BTreeMap<String, TemplateValue>,
+}
+
+impl TemplateVariables {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, name: &str, value: impl Into<TemplateValue>) -> Self {
+        self.insert(name, value);
+        self
+    }
+
+    pub fn insert(&mut self, name: &str, value: impl Into<TemplateValue>) {
+        self.values.insert(name.to_string(), value.into());
+    }
+
+    pub fn get(&self, name: &str) -> Option<&TemplateValue> {
+        self.values.get(name)
+    }
+}
+
+/// Format used for times that have no format filter.
+const DEFAULT_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Filter {
+    Upper,
+    Lower,
+    Trim,
+    Truncate(usize),
+    Default(String),
+    Round(usize),
+    TimeFormat(String),
+}
+
+impl Filter {
+    fn parse(filter: &str) -> crate::Result<Self> {
+        let filter = filter.trim();
+        let error = |msg: &str| crate::Error::TemplateError(format!("{msg}: \"{filter}\""));
+
+        // Time formats may contain ':', so are recognised before splitting off the argument
+        if filter.starts_with('%') {
+            if StrftimeItems::new(filter).parse().is_err() {
+                return Err(error("invalid time format"));
+            }
+            return Ok(Self::TimeFormat(filter.to_string()));
+        }
+
+        let (name, argument) = match filter.split_once(':') {
+            Some((name, argument)) => (name.trim(), Some(argument)),
+            None => (filter, None),
+        };
+        let number = || {
+            argument
+                .and_then(|a| a.trim().parse().ok())
+                .ok_or_else(|| error("filter requires a number"))
+        };
+
+        match name {
+            "upper" => Ok(Self::Upper),
+            "lower" => Ok(Self::Lower),
+            "trim" => Ok(Self::Trim),
+            "trunc" => Ok(Self::Truncate(number()?)),
+            "round" => Ok(Self::Round(number()?)),
+            "default" => Ok(Self::Default(argument.unwrap_or_default().to_string())),
+            _ => Err(error("unknown filter")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Variable { name: String, filters: Vec<Filter> },
+}
+
+/// A message with placeholders that are filled in from [`TemplateVariables`].
+///
+/// Placeholders are written as `{{name}}`, optionally followed by filters separated by `|`:
+///
+/// - `upper`, `lower` and `trim` change the case or remove surrounding whitespace
+/// - `trunc:N` keeps at most the first `N` characters
+/// - `round:N` formats a number with `N` decimal places
+/// - `default:text` is used when the variable is not set
+/// - a [chrono format string](chrono::format::strftime) starting with `%` formats a time, e.g.
+///   `%H:%M`, times without a format use `%Y-%m-%d %H:%M`
+///
+/// Times are formatted in the offset they were given in and without any locale.
+///
+/// Example:
+/// ```
+/// # use chrono::{TimeZone, Utc};
+/// # use dapnet_api::{MessageTemplate, TemplateVariables};
+/// let template =
+///     MessageTemplate::parse("{{callsign}}: {{alert|upper|trunc:40}} @{{time|%H:%M}}").unwrap();
+///
+/// let variables = TemplateVariables::new()
+///     .with("callsign", "M0NXN")
+///     .with("alert", "disk full on db-01")
+///     .with("time", Utc.with_ymd_and_hms(2024, 6, 1, 14, 5, 0).unwrap());
+///
+/// assert_eq!(
+///     template.render(&variables).unwrap(),
+///     "M0NXN: DISK FULL ON DB-01 @14:05"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageTemplate {
+    parts: Vec<Part>,
+}
+
+/// The result of rendering a template as a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedMessage {
+    /// The sanitized message text.
+    pub text: String,
+
+    /// Number of characters by which the message exceeded the maximum length before it was
+    /// truncated, zero if it fit.
+    pub overflow: usize,
+}
+
+impl MessageTemplate {
+    pub fn parse(template: &str) -> crate::Result<Self> {
+        let mut parts = Vec::new();
+        let mut rest = template;
+
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                parts.push(Part::Literal(rest[..start].to_string()));
+            }
+
+            let end = rest[start..].find("}}").ok_or_else(|| {
+                crate::Error::TemplateError(format!(
+                    "unclosed placeholder at \"{}\"",
+                    &rest[start..]
+                ))
+            })? + start;
+
+            let mut segments = rest[start + 2..end].split('|');
+            let name = segments.next().unwrap_or_default().trim();
+            if name.is_empty()
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+            {
+                return Err(crate::Error::TemplateError(format!(
+                    "invalid variable name \"{name}\""
+                )));
+            }
+
+            parts.push(Part::Variable {
+                name: name.to_string(),
+                filters: segments.map(Filter::parse).collect::<crate::Result<_>>()?,
+            });
+
+            rest = &rest[end + 2..];
+        }
+
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+
+        Ok(Self { parts })
+    }
+
+    /// Names of the variables used in the template, in order of first use.
+    pub fn variables(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        for part in &self.parts {
+            if let Part::Variable { name, .. } = part
+                && !names.contains(&name.as_str())
+            {
+                names.push(name.as_str());
+            }
+        }
+        names
+    }
+
+    /// Fills in the template, without any sanitization.
+    pub fn render(&self, variables: &TemplateVariables) -> crate::Result<String> {
+        let mut result = String::new();
+
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => result.push_str(text),
+                Part::Variable { name, filters } => {
+                    result.push_str(&render_variable(name, filters, variables.get(name))?)
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Fills in the template and sanitizes the result using [`sanitize_message`](crate::sanitize_message).
+    pub fn render_message(
+        &self,
+        variables: &TemplateVariables,
+        options: &MessageSanitizationOptions,
+    ) -> crate::Result<RenderedMessage> {
+        let (text, overflow) = sanitize_message_with_overflow(self.render(variables)?, options);
+        Ok(RenderedMessage { text, overflow })
+    }
+}
+
+impl FromStr for MessageTemplate {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+fn render_variable(
+    name: &str,
+    filters: &[Filter],
+    value: Option<&TemplateValue>,
+) -> crate::Result<String> {
+    let mut value = value.cloned();
+    let mut text: Option<String> = None;
+
+    for filter in filters {
+        // Typed filters must come before any that convert the value to text
+        match (filter, &value, &text) {
+            (Filter::Default(default), None, None) => {
+                value = Some(TemplateValue::Text(default.clone()));
+                continue;
+            }
+            (Filter::Default(_), _, _) => continue,
+            (Filter::TimeFormat(format), Some(TemplateValue::Time(time)), None) => {
+                text = Some(time.format(format).to_string());
+                continue;
+            }
+            (Filter::Round(places), Some(TemplateValue::Float(v)), None) => {
+                text = Some(format!("{v:.places$}"));
+                continue;
+            }
+            (Filter::Round(_), Some(TemplateValue::Integer(v)), None) => {
+                text = Some(v.to_string());
+                continue;
+            }
+            (Filter::TimeFormat(_) | Filter::Round(_), Some(_), _) => {
+                return Err(crate::Error::TemplateError(format!(
+                    "filter {filter:?} cannot be applied to variable \"{name}\""
+                )));
+            }
+            _ => {}
+        }
+
+        let current = match text.take() {
+            Some(text) => text,
+            None => value.as_ref().ok_or_else(|| missing(name))?.to_string(),
+        };
+
+        text = Some(match filter {
+            Filter::Upper => current.to_uppercase(),
+            Filter::Lower => current.to_lowercase(),
+            Filter::Trim => current.trim().to_string(),
+            Filter::Truncate(length) => truncate(current, *length, ""),
+            _ => current,
+        });
+    }
+
+    match (text, value) {
+        (Some(text), _) => Ok(text),
+        (None, Some(value)) => Ok(value.to_string()),
+        (None, None) => Err(missing(name)),
+    }
+}
+
+fn missing(name: &str) -> crate::Error {
+    crate::Error::TemplateError(format!("variable \"{name}\" is not set"))
+}
+
+impl OutgoingCallBuilder {
+    /// Sets the text of the call by rendering a template.
+    ///
+    /// The rendered text is sanitized, so it is truncated rather than making the call invalid
+    /// if it is too long.
+    pub fn text_from_template(
+        &mut self,
+        template: &MessageTemplate,
+        variables: &TemplateVariables,
+        options: &MessageSanitizationOptions,
+    ) -> crate::Result<&mut Self> {
+        let rendered = template.render_message(variables, options)?;
+        Ok(self.text(rendered.text))
+    }
+}
+
+impl OutgoingNewsBuilder {
+    /// Sets the text of the news by rendering a template.
+    ///
+    /// The rendered text is sanitized, so it is truncated rather than making the news invalid
+    /// if it is too long.
+    pub fn text_from_template(
+        &mut self,
+        template: &MessageTemplate,
+        variables: &TemplateVariables,
+        options: &MessageSanitizationOptions,
+    ) -> crate::Result<&mut Self> {
+        let rendered = template.render_message(variables, options)?;
+        Ok(self.text(rendered.text))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::MessageSanitizationOptionsBuilder;
+    use chrono::TimeZone;
+
+    fn variables() -> TemplateVariables {
+        TemplateVariables::new()
+            .with("callsign", "M0NXN")
+            .with("alert", "  disk full on db-01  ")
+            .with("count", 3)
+            .with("load", 1.23456)
+            .with("firing", true)
+            .with(
+                "time",
+                FixedOffset::east_opt(3600)
+                    .unwrap()
+                    .with_ymd_and_hms(2024, 6, 1, 14, 5, 9)
+                    .unwrap(),
+            )
+    }
+
+    fn render(template: &str) -> crate::Result<String> {
+        MessageTemplate::parse(template)?.render(&variables())
+    }
+
+    #[test]
+    fn literal_only() {
+        assert_eq!(render("no placeholders").unwrap(), "no placeholders");
+        assert_eq!(render("").unwrap(), "");
+    }
+
+    #[test]
+    fn typed_values() {
+        assert_eq!(
+            render("{{callsign}} {{count}} {{load}} {{firing}} {{time}}").unwrap(),
+            "M0NXN 3 1.23456 true 2024-06-01 14:05"
+        );
+    }
+
+    #[test]
+    fn filters() {
+        assert_eq!(
+            render("{{ alert | trim | upper }}!").unwrap(),
+            "DISK FULL ON DB-01!"
+        );
+        assert_eq!(render("{{callsign|lower}}").unwrap(), "m0nxn");
+        assert_eq!(render("[{{alert|trim|trunc:9}}]").unwrap(), "[disk full]");
+        assert_eq!(
+            render("{{load|round:1}} {{count|round:2}}").unwrap(),
+            "1.2 3"
+        );
+        assert_eq!(
+            render("{{time|%H:%M:%S %z}} on {{ time | %a %d %b }}").unwrap(),
+            "14:05:09 +0100 on Sat 01 Jun"
+        );
+    }
+
+    #[test]
+    fn default_filter() {
+        assert_eq!(render("{{missing|default:n/a|upper}}").unwrap(), "N/A");
+        assert_eq!(render("{{callsign|default:n/a}}").unwrap(), "M0NXN");
+        assert_eq!(render("{{missing|default}}").unwrap(), "");
+    }
+
+    #[test]
+    fn missing_variable() {
+        assert!(matches!(
+            render("{{missing}}"),
+            Err(crate::Error::TemplateError(_))
+        ));
+        assert!(matches!(
+            render("{{missing|upper}}"),
+            Err(crate::Error::TemplateError(_))
+        ));
+    }
+
+    #[test]
+    fn filter_of_wrong_type() {
+        assert!(render("{{callsign|%H:%M}}").is_err());
+        assert!(render("{{callsign|round:2}}").is_err());
+        assert!(render("{{time|upper|%H}}").is_err());
+    }
+
+    #[test]
+    fn parse_errors() {
+        for template in [
+            "{{callsign",
+            "{{}}",
+            "{{call sign}}",
+            "{{callsign|shout}}",
+            "{{callsign|trunc}}",
+            "{{callsign|trunc:x}}",
+            "{{time|%Q}}",
+        ] {
+            assert!(
+                matches!(
+                    MessageTemplate::parse(template),
+                    Err(crate::Error::TemplateError(_))
+                ),
+                "{template}"
+            );
+        }
+    }
+
+    #[test]
+    fn variables_in_template() {
+        let template = MessageTemplate::parse("{{a}} {{b|upper}} {{a}}").unwrap();
+        assert_eq!(template.variables(), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn render_message_reports_overflow() {
+        let template = MessageTemplate::parse("{{callsign}}: {{alert|trim}}").unwrap();
+        let options = MessageSanitizationOptionsBuilder::default()
+            .max_length(16)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            template.render_message(&variables(), &options).unwrap(),
+            RenderedMessage {
+                text: "M0NXN: disk f...".to_string(),
+                overflow: 9,
+            }
+        );
+    }
+
+    #[test]
+    fn call_builder_from_template() {
+        let call = OutgoingCallBuilder::default()
+            .recipients(vec!["m0nxn".to_string()])
+            .transmitter_groups(vec!["all".to_string()])
+            .text_from_template(
+                &MessageTemplate::parse("{{callsign}}: {{alert|trim}} x{{count}}").unwrap(),
+                &variables(),
+                &MessageSanitizationOptions::default(),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(call.text, "M0NXN: disk full on db-01 x3");
+    }
+
+    #[test]
+    fn news_builder_from_template() {
+        let news = OutgoingNewsBuilder::default()
+            .rubric("wx".to_string())
+            .text_from_template(
+                &MessageTemplate::parse("{{time|%H:%M}} load {{load|round:2}}").unwrap(),
+                &variables(),
+                &MessageSanitizationOptions::default(),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(news.text, "14:05 load 1.23");
+    }
+}
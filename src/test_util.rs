@@ -0,0 +1,89 @@
+//! Helpers shared by the tests of several modules.
+
+use crate::{Client, Transmitter};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Online transmitter owned by m0nxn using timeslots 0 to 3.
+pub(crate) fn transmitter(name: &str, auth_key: &str) -> Transmitter {
+    serde_json::from_value(serde_json::json!({
+        "name": name,
+        "usage": "WIDERANGE",
+        "longitude": "0",
+        "latitude": "0",
+        "timeSlot": "0123",
+        "ownerNames": ["m0nxn"],
+        "status": "ONLINE",
+        "callCount": 0,
+        "authKey": auth_key,
+        "power": "10",
+        "antennaAboveGroundLevel": 10,
+        "antennaType": "OMNI",
+        "antennaDirection": 0.0,
+        "antennaGainDbi": 0.0,
+        "identificationAddress": 8,
+        "lastUpdate": "2024-01-01T00:00:00Z",
+    }))
+    .unwrap()
+}
+
+/// Client whose requests all fail, as nothing is listening on the discard port.
+pub(crate) fn unreachable_client() -> Client {
+    Client::new("m0nxn", "hunter2").with_api_url("http://127.0.0.1:9/".parse().unwrap())
+}
+
+/// Minimal HTTP server that replies with the given statuses and JSON bodies in turn, recording
+/// request paths and bodies.
+pub(crate) async fn server(
+    responses: Vec<(u16, &'static str)>,
+) -> (url::Url, Arc<Mutex<Vec<(String, String)>>>) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap())
+        .parse()
+        .unwrap();
+    let requests = Arc::new(Mutex::new(Vec::new()));
+
+    tokio::spawn({
+        let requests = requests.clone();
+        async move {
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0; 4096];
+                loop {
+                    let n = stream.read(&mut buffer).await.unwrap();
+                    request.extend_from_slice(&buffer[..n]);
+                    let text = String::from_utf8_lossy(&request).to_string();
+                    if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                        let length = head
+                            .lines()
+                            .find_map(|line| {
+                                line.to_lowercase()
+                                    .strip_prefix("content-length: ")
+                                    .map(|l| l.parse::<usize>().unwrap())
+                            })
+                            .unwrap_or(0);
+                        if body.len() >= length {
+                            let path = head.split(' ').nth(1).unwrap().to_string();
+                            requests.lock().unwrap().push((path, body.to_string()));
+                            break;
+                        }
+                    }
+                }
+                stream
+                    .write_all(
+                        format!(
+                            "HTTP/1.1 {status} X\r\nContent-Type: application/json\r\n\
+                             Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                            body.len()
+                        )
+                        .as_bytes(),
+                    )
+                    .await
+                    .unwrap();
+            }
+        }
+    });
+
+    (url, requests)
+}
@@ -0,0 +1,233 @@
+use super::{ClientMessage, DEFAULT_PORT, ServerMessage, Timeslots, TransmitterConfig};
+use chrono::Utc;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    net::{TcpStream, ToSocketAddrs},
+};
+
+/// Connection from a transmitter to a DAPNET core node.
+///
+/// Time synchronisation, timeslot assignment and message acknowledgement are handled
+/// automatically, every line received from the core is also returned to the caller.
+///
+/// Example:
+/// ```no_run
+/// # use dapnet_api::{Client, transmitter::{ServerMessage, TransmitterClient}};
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let client = Client::new("m0nxn", "my_super_secret_password");
+/// let mut transmitter = TransmitterClient::connect_via_api(&client, "m0nxn-tx")
+///     .await
+///     .unwrap();
+///
+/// while let Some(msg) = transmitter.next_message().await.unwrap() {
+///     if let ServerMessage::Message { message, .. } = msg {
+///         println!("{}: {}", message.ric, message.text);
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct TransmitterClient<S = TcpStream> {
+    stream: BufReader<S>,
+    timeslots: Timeslots,
+    time_correction: i32,
+}
+
+impl TransmitterClient<TcpStream> {
+    /// Connects and logs in to a core node.
+    pub async fn connect<A: ToSocketAddrs>(
+        addr: A,
+        config: &TransmitterConfig,
+    ) -> crate::Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        Self::login(stream, config).await
+    }
+
+    /// Connects a transmitter owned by the API user to the node it is assigned to.
+    ///
+    /// The login details and node are retrieved from the API, the node is connected to on
+    /// [`DEFAULT_PORT`].
+    pub async fn connect_via_api(client: &crate::Client, name: &str) -> crate::Result<Self> {
+        let transmitter = client
+            .get_transmitter(name)
+            .await?
+            .ok_or_else(|| crate::Error::NotFound(format!("transmitter {name}")))?;
+        let config = TransmitterConfig::from_transmitter(&transmitter)?;
+
+        let node_name = transmitter
+            .node
+            .ok_or_else(|| crate::Error::NotFound(format!("node of transmitter {name}")))?;
+        let node = client
+            .get_node(&node_name)
+            .await?
+            .ok_or_else(|| crate::Error::NotFound(format!("node {node_name}")))?;
+        let connection = node
+            .connection
+            .ok_or_else(|| crate::Error::NotFound(format!("address of node {node_name}")))?;
+
+        Self::connect((connection.ip, DEFAULT_PORT), &config).await
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> TransmitterClient<S> {
+    /// Logs in over an already established connection.
+    pub async fn login(stream: S, config: &TransmitterConfig) -> crate::Result<Self> {
+        let mut client = Self {
+            stream: BufReader::new(stream),
+            timeslots: Timeslots::default(),
+            time_correction: 0,
+        };
+
+        client.send(&config.login_message()).await?;
+
+        Ok(client)
+    }
+
+    /// Timeslots most recently assigned by the core.
+    pub fn timeslots(&self) -> Timeslots {
+        self.timeslots
+    }
+
+    /// Clock correction most recently sent by the core, in units of 100ms.
+    pub fn time_correction(&self) -> i32 {
+        self.time_correction
+    }
+
+    /// Waits for the next line from the core and responds to it.
+    ///
+    /// Returns `None` when the core closes the connection.
+    pub async fn next_message(&mut self) -> crate::Result<Option<ServerMessage>> {
+        let mut line = String::new();
+        if self.stream.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+
+        let msg = match line.trim_end_matches(['\r', '\n']).parse::<ServerMessage>() {
+            Ok(msg) => msg,
+            Err(e) => {
+                self.send(&ClientMessage::Nack).await?;
+                return Err(e);
+            }
+        };
+
+        match &msg {
+            ServerMessage::TimeSync(server) => {
+                self.send(&ClientMessage::TimeSync {
+                    server: *server,
+                    client: super::protocol_time(Utc::now()),
+                })
+                .await?;
+                self.send(&ClientMessage::Ack).await?;
+            }
+            ServerMessage::TimeCorrection(correction) => {
+                self.time_correction = *correction;
+                self.send(&ClientMessage::Ack).await?;
+            }
+            ServerMessage::Timeslots(timeslots) => {
+                self.timeslots = *timeslots;
+                self.send(&ClientMessage::Ack).await?;
+            }
+            ServerMessage::Message { id, message } => {
+                if let Err(e) = message.to_page() {
+                    self.send(&ClientMessage::Nack).await?;
+                    return Err(e);
+                }
+                self.send(&ClientMessage::MessageAck {
+                    next_id: id.wrapping_add(1),
+                })
+                .await?;
+            }
+        }
+
+        Ok(Some(msg))
+    }
+
+    async fn send(&mut self, msg: &ClientMessage) -> crate::Result<()> {
+        let stream = self.stream.get_mut();
+        stream.write_all(format!("{msg}\r\n").as_bytes()).await?;
+        stream.flush().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        pocsag,
+        transmitter::{Message, MessageType, Speed, TransmitterConfigBuilder},
+    };
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, DuplexStream};
+
+    async fn read_line(stream: &mut BufReader<DuplexStream>) -> String {
+        let mut line = String::new();
+        stream.read_line(&mut line).await.unwrap();
+        line.trim_end().to_string()
+    }
+
+    #[tokio::test]
+    async fn session() {
+        let (client_stream, server_stream) = tokio::io::duplex(1024);
+        let mut server = BufReader::new(server_stream);
+
+        let config = TransmitterConfigBuilder::default()
+            .name("m0nxn-tx".to_string())
+            .auth_key("secret".to_string())
+            .device_type("UniPager".to_string())
+            .device_version("1.0.2".to_string())
+            .build()
+            .unwrap();
+        let mut client = TransmitterClient::login(client_stream, &config)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            read_line(&mut server).await,
+            "[UniPager v1.0.2 m0nxn-tx secret]"
+        );
+
+        let message = Message {
+            message_type: MessageType::Alphanumeric,
+            speed: Speed::Baud1200,
+            ric: 1234567,
+            function: pocsag::Function::Alphanumeric,
+            text: "hello".to_string(),
+        };
+        server
+            .get_mut()
+            .write_all(b"2:1234\r\n3:-0001\r\n4:01\r\n#FF 6:1:12D687:3:hello\r\nbad\r\n")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            client.next_message().await.unwrap(),
+            Some(ServerMessage::TimeSync(0x1234))
+        );
+        assert!(read_line(&mut server).await.starts_with("2:1234:"));
+        assert_eq!(read_line(&mut server).await, "+");
+
+        assert_eq!(
+            client.next_message().await.unwrap(),
+            Some(ServerMessage::TimeCorrection(-1))
+        );
+        assert_eq!(client.time_correction(), -1);
+        assert_eq!(read_line(&mut server).await, "+");
+
+        client.next_message().await.unwrap();
+        assert_eq!(client.timeslots(), "01".parse().unwrap());
+        assert_eq!(read_line(&mut server).await, "+");
+
+        assert_eq!(
+            client.next_message().await.unwrap(),
+            Some(ServerMessage::Message { id: 0xFF, message })
+        );
+        assert_eq!(read_line(&mut server).await, "#00 +");
+
+        assert!(client.next_message().await.is_err());
+        assert_eq!(read_line(&mut server).await, "-");
+
+        drop(server);
+        assert_eq!(client.next_message().await.unwrap(), None);
+    }
+}
@@ -0,0 +1,209 @@
+//! The DAPNET transmitter protocol.
+//!
+//! [`TransmitterClient`] implements the transmitter side of the protocol and [`TransmitterServer`]
+//! emulates the core side, e.g. for testing transmitters and gateways locally.
+//!
+//! Transmitters (e.g. UniPager) hold a TCP connection to a DAPNET core node.
+//! The protocol is line based:
+//!
+//! - The transmitter logs in with `[<device type> v<device version> <name> <auth key>]`.
+//! - The core synchronises time with `2:<time>`, to which the transmitter replies with
+//!   `2:<core time>:<transmitter time>` and `+`. Times are in units of 100ms, as 4 hex digits.
+//! - The core sends a clock correction with `3:<+/-><correction>`, acknowledged with `+`.
+//! - The core assigns timeslots with `4:<slots>` (e.g. `4:0123`), acknowledged with `+`.
+//! - Pages are sent as numbered frames `#<id> <type>:<speed>:<RIC>:<function>:<text>`, which are
+//!   acknowledged with `#<id + 1> +`.
+
+mod client;
+mod protocol;
+mod server;
+
+pub use self::{
+    client::TransmitterClient,
+    protocol::{ClientMessage, Message, MessageType, ServerMessage, Speed},
+    server::{Delivery, TransmitterServer},
+};
+
+use crate::{Secret, Transmitter};
+use chrono::{DateTime, Utc};
+use derive_builder::Builder;
+use std::{fmt, str::FromStr};
+
+/// Port DAPNET core nodes accept transmitter connections on.
+pub const DEFAULT_PORT: u16 = 43434;
+
+/// Number of timeslots in a cycle.
+pub const TIMESLOT_COUNT: u8 = 16;
+
+/// Details a transmitter identifies itself with when logging in.
+#[derive(Debug, Clone, PartialEq, Eq, Builder)]
+#[builder(build_fn(validate = "Self::validate"))]
+pub struct TransmitterConfig {
+    /// Name of the transmitter
+    name: String,
+
+    /// Key used to authenticate the transmitter
+    #[builder(setter(into))]
+    auth_key: Secret,
+
+    #[builder(default = "\"dapnet-api-rust\".to_string()")]
+    device_type: String,
+
+    #[builder(default = "env!(\"CARGO_PKG_VERSION\").to_string()")]
+    device_version: String,
+}
+
+impl TransmitterConfigBuilder {
+    fn validate(&self) -> Result<(), String> {
+        let fields = [
+            self.name.as_deref(),
+            self.auth_key.as_ref().map(Secret::expose_secret),
+            self.device_type.as_deref(),
+            self.device_version.as_deref(),
+        ];
+
+        if fields.iter().flatten().any(|f| f.contains(' ')) {
+            Err("Login fields must not contain spaces".to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl TransmitterConfig {
+    /// Creates the login details for a transmitter retrieved from the API.
+    ///
+    /// The auth key is only provided when the API user owns the transmitter.
+    pub fn from_transmitter(transmitter: &Transmitter) -> crate::Result<Self> {
+        let auth_key = transmitter
+            .auth_key
+            .clone()
+            .ok_or_else(|| crate::Error::NotFound(format!("auth key of {}", transmitter.name)))?;
+
+        let mut builder = TransmitterConfigBuilder::default();
+        builder.name(transmitter.name.clone()).auth_key(auth_key);
+        if let Some(device_type) = &transmitter.device_type {
+            builder.device_type(device_type.clone());
+        }
+        if let Some(device_version) = &transmitter.device_version {
+            builder.device_version(device_version.clone());
+        }
+
+        builder
+            .build()
+            .map_err(|e| crate::Error::ProtocolError(e.to_string()))
+    }
+
+    pub(crate) fn login_message(&self) -> ClientMessage {
+        ClientMessage::Login {
+            device_type: self.device_type.clone(),
+            device_version: self.device_version.clone(),
+            name: self.name.clone(),
+            auth_key: self.auth_key.clone(),
+        }
+    }
+}
+
+/// Set of timeslots a transmitter may transmit in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Timeslots(u16);
+
+impl Timeslots {
+    pub fn all() -> Self {
+        Self(u16::MAX)
+    }
+
+    pub fn contains(&self, slot: u8) -> bool {
+        slot < TIMESLOT_COUNT && self.0 & (1 << slot) != 0
+    }
+
+    pub fn insert(&mut self, slot: u8) {
+        if slot < TIMESLOT_COUNT {
+            self.0 |= 1 << slot;
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..TIMESLOT_COUNT).filter(|slot| self.contains(*slot))
+    }
+
+    /// Checks if transmission is allowed at a given time.
+    pub fn is_active(&self, time: DateTime<Utc>) -> bool {
+        self.contains(timeslot_at(time))
+    }
+}
+
+impl FromStr for Timeslots {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut slots = Self::default();
+        for c in s.chars() {
+            let slot = c
+                .to_digit(16)
+                .ok_or_else(|| crate::Error::ProtocolError(format!("invalid timeslots {s:?}")))?;
+            slots.insert(slot as u8);
+        }
+        Ok(slots)
+    }
+}
+
+impl fmt::Display for Timeslots {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for slot in self.iter() {
+            write!(f, "{slot:X}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Time as used by the protocol, in units of 100ms wrapping every 65536 units.
+pub fn protocol_time(time: DateTime<Utc>) -> u16 {
+    (time.timestamp_millis() / 100) as u16
+}
+
+/// The timeslot active at a given time, each slot is 6.4 seconds long.
+pub fn timeslot_at(time: DateTime<Utc>) -> u8 {
+    ((time.timestamp_millis() / 100 / 64) % TIMESLOT_COUNT as i64) as u8
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn timeslots_parse_and_format() {
+        let slots: Timeslots = "0f37".parse().unwrap();
+        assert_eq!(slots.iter().collect::<Vec<_>>(), vec![0, 3, 7, 15]);
+        assert_eq!(slots.to_string(), "037F");
+        assert!("012G".parse::<Timeslots>().is_err());
+        assert_eq!(Timeslots::all().to_string(), "0123456789ABCDEF");
+    }
+
+    #[test]
+    fn timeslot_timing() {
+        let epoch = DateTime::from_timestamp(0, 0).unwrap();
+        assert_eq!(timeslot_at(epoch), 0);
+        assert_eq!(timeslot_at(epoch + chrono::Duration::milliseconds(6399)), 0);
+        assert_eq!(timeslot_at(epoch + chrono::Duration::milliseconds(6400)), 1);
+        assert_eq!(
+            timeslot_at(epoch + chrono::Duration::milliseconds(6400 * 17)),
+            1
+        );
+
+        let slots: Timeslots = "1".parse().unwrap();
+        assert!(!slots.is_active(epoch));
+        assert!(slots.is_active(epoch + chrono::Duration::seconds(7)));
+    }
+
+    #[test]
+    fn config_rejects_spaces() {
+        assert!(
+            TransmitterConfigBuilder::default()
+                .name("m0nxn tx".to_string())
+                .auth_key("secret".to_string())
+                .build()
+                .is_err()
+        );
+    }
+}
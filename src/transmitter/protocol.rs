@@ -0,0 +1,392 @@
+use super::Timeslots;
+use crate::{Secret, pocsag};
+use std::{fmt, str::FromStr};
+
+/// Bit rate a message is to be transmitted at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Speed {
+    Baud512 = 0,
+    Baud1200 = 1,
+    Baud2400 = 2,
+}
+
+impl Speed {
+    pub fn baud_rate(&self) -> u32 {
+        match self {
+            Self::Baud512 => 512,
+            Self::Baud1200 => 1200,
+            Self::Baud2400 => 2400,
+        }
+    }
+}
+
+/// Type field of a message frame, selects how the text is encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageType {
+    Numeric = 5,
+    Alphanumeric = 6,
+}
+
+/// A page to be transmitted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub message_type: MessageType,
+    pub speed: Speed,
+    pub ric: u32,
+    pub function: pocsag::Function,
+    pub text: String,
+}
+
+impl Message {
+    /// Creates a 1200 baud message for a page.
+    pub fn from_page(page: &pocsag::Page) -> Self {
+        let (message_type, text) = match page.content() {
+            pocsag::Content::Tone => (MessageType::Alphanumeric, String::new()),
+            pocsag::Content::Numeric(text) => (MessageType::Numeric, text.clone()),
+            pocsag::Content::Alphanumeric(text) => (MessageType::Alphanumeric, text.clone()),
+        };
+
+        Self {
+            message_type,
+            speed: Speed::Baud1200,
+            ric: page.ric(),
+            function: page.function(),
+            text,
+        }
+    }
+
+    /// Converts the message into the page that is to be transmitted.
+    pub fn to_page(&self) -> crate::Result<pocsag::Page> {
+        let content = match (self.function, self.message_type) {
+            (pocsag::Function::Tone, _) => pocsag::Content::Tone,
+            (_, MessageType::Numeric) => pocsag::Content::Numeric(self.text.clone()),
+            (_, MessageType::Alphanumeric) => pocsag::Content::Alphanumeric(self.text.clone()),
+        };
+
+        pocsag::Page::new(self.ric, self.function, content)
+    }
+}
+
+/// Lines sent from the core to a transmitter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerMessage {
+    /// Request for the current time of the transmitter, contains the time of the core.
+    TimeSync(u16),
+
+    /// Correction to apply to the transmitter clock, in units of 100ms.
+    TimeCorrection(i32),
+
+    /// Timeslots the transmitter may transmit in.
+    Timeslots(Timeslots),
+
+    /// A numbered message frame.
+    Message { id: u8, message: Message },
+}
+
+/// Lines sent from a transmitter to the core.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientMessage {
+    /// First line sent after connecting.
+    Login {
+        device_type: String,
+        device_version: String,
+        name: String,
+        auth_key: Secret,
+    },
+
+    /// Reply to [`ServerMessage::TimeSync`].
+    TimeSync { server: u16, client: u16 },
+
+    /// Acknowledgement of a command.
+    Ack,
+
+    /// Negative acknowledgement of a command.
+    Nack,
+
+    /// Acknowledgement of a message frame, carries the ID of the next expected frame.
+    MessageAck { next_id: u8 },
+}
+
+fn protocol_error(line: &str) -> crate::Error {
+    crate::Error::ProtocolError(format!("invalid line {line:?}"))
+}
+
+fn function_from_bits(bits: u8) -> Option<pocsag::Function> {
+    match bits {
+        0 => Some(pocsag::Function::Numeric),
+        1 => Some(pocsag::Function::Tone),
+        2 => Some(pocsag::Function::Activation),
+        3 => Some(pocsag::Function::Alphanumeric),
+        _ => None,
+    }
+}
+
+impl fmt::Display for ServerMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TimeSync(time) => write!(f, "2:{time:04x}"),
+            Self::TimeCorrection(correction) => {
+                let sign = if *correction < 0 { '-' } else { '+' };
+                write!(f, "3:{sign}{:04x}", correction.unsigned_abs())
+            }
+            Self::Timeslots(slots) => write!(f, "4:{slots}"),
+            Self::Message { id, message } => write!(
+                f,
+                "#{id:02X} {}:{}:{:X}:{}:{}",
+                message.message_type as u8,
+                message.speed as u8,
+                message.ric,
+                message.function as u8,
+                message.text
+            ),
+        }
+    }
+}
+
+impl FromStr for ServerMessage {
+    type Err = crate::Error;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        if let Some(frame) = line.strip_prefix('#') {
+            let (id, frame) = frame.split_once(' ').ok_or_else(|| protocol_error(line))?;
+            let id = u8::from_str_radix(id, 16).map_err(|_| protocol_error(line))?;
+
+            let mut fields = frame.splitn(5, ':');
+            let mut next = || fields.next().ok_or_else(|| protocol_error(line));
+
+            let message_type = match next()? {
+                "5" => MessageType::Numeric,
+                "6" => MessageType::Alphanumeric,
+                _ => return Err(protocol_error(line)),
+            };
+            let speed = match next()? {
+                "0" => Speed::Baud512,
+                "1" => Speed::Baud1200,
+                "2" => Speed::Baud2400,
+                _ => return Err(protocol_error(line)),
+            };
+            let ric = u32::from_str_radix(next()?, 16).map_err(|_| protocol_error(line))?;
+            let function = next()?
+                .parse()
+                .ok()
+                .and_then(function_from_bits)
+                .ok_or_else(|| protocol_error(line))?;
+            let text = next()?.to_string();
+
+            return Ok(Self::Message {
+                id,
+                message: Message {
+                    message_type,
+                    speed,
+                    ric,
+                    function,
+                    text,
+                },
+            });
+        }
+
+        let (command, value) = line.split_once(':').ok_or_else(|| protocol_error(line))?;
+        match command {
+            "2" => u16::from_str_radix(value, 16)
+                .map(Self::TimeSync)
+                .map_err(|_| protocol_error(line)),
+            "3" => {
+                let (sign, value) = value
+                    .split_at_checked(1)
+                    .ok_or_else(|| protocol_error(line))?;
+                // from_str_radix would accept a second sign
+                if value.starts_with(['+', '-']) {
+                    return Err(protocol_error(line));
+                }
+                let value = i32::from_str_radix(value, 16).map_err(|_| protocol_error(line))?;
+                match sign {
+                    "+" => Ok(Self::TimeCorrection(value)),
+                    "-" => Ok(Self::TimeCorrection(-value)),
+                    _ => Err(protocol_error(line)),
+                }
+            }
+            "4" => value.parse().map(Self::Timeslots),
+            _ => Err(protocol_error(line)),
+        }
+    }
+}
+
+impl fmt::Display for ClientMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Login {
+                device_type,
+                device_version,
+                name,
+                auth_key,
+            } => write!(
+                f,
+                "[{device_type} v{device_version} {name} {}]",
+                auth_key.expose_secret()
+            ),
+            Self::TimeSync { server, client } => write!(f, "2:{server:04x}:{client:04x}"),
+            Self::Ack => write!(f, "+"),
+            Self::Nack => write!(f, "-"),
+            Self::MessageAck { next_id } => write!(f, "#{next_id:02X} +"),
+        }
+    }
+}
+
+impl FromStr for ClientMessage {
+    type Err = crate::Error;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        match line {
+            "+" => return Ok(Self::Ack),
+            "-" => return Ok(Self::Nack),
+            _ => {}
+        }
+
+        if let Some(login) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            let fields: Vec<&str> = login.split(' ').collect();
+            return match fields.as_slice() {
+                [device_type, device_version, name, auth_key] => Ok(Self::Login {
+                    device_type: device_type.to_string(),
+                    device_version: device_version
+                        .strip_prefix('v')
+                        .unwrap_or(device_version)
+                        .to_string(),
+                    name: name.to_string(),
+                    auth_key: auth_key.to_string().into(),
+                }),
+                _ => Err(protocol_error(line)),
+            };
+        }
+
+        if let Some(ack) = line.strip_prefix('#').and_then(|l| l.strip_suffix(" +")) {
+            return u8::from_str_radix(ack, 16)
+                .map(|next_id| Self::MessageAck { next_id })
+                .map_err(|_| protocol_error(line));
+        }
+
+        if let Some(sync) = line.strip_prefix("2:") {
+            let (server, client) = sync.split_once(':').ok_or_else(|| protocol_error(line))?;
+            return Ok(Self::TimeSync {
+                server: u16::from_str_radix(server, 16).map_err(|_| protocol_error(line))?,
+                client: u16::from_str_radix(client, 16).map_err(|_| protocol_error(line))?,
+            });
+        }
+
+        Err(protocol_error(line))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn server_message_round_trip() {
+        let messages = [
+            ServerMessage::TimeSync(0xBEEF),
+            ServerMessage::TimeCorrection(-3),
+            ServerMessage::TimeCorrection(0x10),
+            ServerMessage::Timeslots("0123".parse().unwrap()),
+            ServerMessage::Message {
+                id: 0xA4,
+                message: Message {
+                    message_type: MessageType::Alphanumeric,
+                    speed: Speed::Baud1200,
+                    ric: 1234567,
+                    function: pocsag::Function::Alphanumeric,
+                    text: "M0NXN: time is 12:34".to_string(),
+                },
+            },
+        ];
+
+        for msg in messages {
+            assert_eq!(msg.to_string().parse::<ServerMessage>().unwrap(), msg);
+        }
+    }
+
+    #[test]
+    fn server_message_format() {
+        assert_eq!(ServerMessage::TimeSync(0x1A).to_string(), "2:001a");
+        assert_eq!(ServerMessage::TimeCorrection(-2).to_string(), "3:-0002");
+        assert_eq!(
+            ServerMessage::Message {
+                id: 5,
+                message: Message {
+                    message_type: MessageType::Numeric,
+                    speed: Speed::Baud512,
+                    ric: 8,
+                    function: pocsag::Function::Numeric,
+                    text: "123".to_string(),
+                },
+            }
+            .to_string(),
+            "#05 5:0:8:0:123"
+        );
+    }
+
+    #[test]
+    fn server_message_invalid() {
+        for line in [
+            "",
+            "9:1",
+            "#05 7:1:8:0:x",
+            "#05 6:1:8:4:x",
+            "#ZZ 6:1:8:3:x",
+            "3:0001",
+            "3:+-5",
+            "3:--5",
+            "3:++5",
+        ] {
+            assert!(line.parse::<ServerMessage>().is_err(), "{line}");
+        }
+    }
+
+    #[test]
+    fn client_message_round_trip() {
+        let messages = [
+            ClientMessage::Login {
+                device_type: "UniPager".to_string(),
+                device_version: "1.0.2".to_string(),
+                name: "m0nxn-tx".to_string(),
+                auth_key: "secret".into(),
+            },
+            ClientMessage::TimeSync {
+                server: 0x1234,
+                client: 0x1235,
+            },
+            ClientMessage::Ack,
+            ClientMessage::Nack,
+            ClientMessage::MessageAck { next_id: 0 },
+        ];
+
+        for msg in messages {
+            assert_eq!(msg.to_string().parse::<ClientMessage>().unwrap(), msg);
+        }
+    }
+
+    #[test]
+    fn client_login_format() {
+        let msg = ClientMessage::Login {
+            device_type: "UniPager".to_string(),
+            device_version: "1.0.2".to_string(),
+            name: "m0nxn-tx".to_string(),
+            auth_key: "secret".into(),
+        };
+        assert_eq!(msg.to_string(), "[UniPager v1.0.2 m0nxn-tx secret]");
+        assert!(!format!("{msg:?}").contains("secret"));
+    }
+
+    #[test]
+    fn message_page_round_trip() {
+        let page = pocsag::Page::new(
+            2000,
+            pocsag::Function::Numeric,
+            pocsag::Content::Numeric("0123".to_string()),
+        )
+        .unwrap();
+
+        let message = Message::from_page(&page);
+        assert_eq!(message.message_type, MessageType::Numeric);
+        assert_eq!(message.to_page().unwrap(), page);
+    }
+}
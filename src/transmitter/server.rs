@@ -0,0 +1,726 @@
+use super::{ClientMessage, Message, ServerMessage, Timeslots};
+use crate::{
+    Callsign, OutgoingCall, OutgoingNews, Rubric, Secret, Transmitter, TransmitterGroup, pocsag,
+    skyper::SkyperMessage,
+};
+use chrono::{DateTime, Utc};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, Mutex},
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    net::TcpListener,
+    sync::Notify,
+};
+
+/// A message acknowledged by a transmitter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delivery {
+    pub transmitter: String,
+    pub id: u8,
+    pub message: Message,
+    pub acknowledged: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+struct TransmitterState {
+    auth_key: Secret,
+    timeslots: Timeslots,
+    queue: VecDeque<Message>,
+    connected: bool,
+    notify: Arc<Notify>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    transmitters: HashMap<String, TransmitterState>,
+    groups: HashMap<String, Vec<String>>,
+    callsigns: HashMap<String, (Vec<u32>, bool)>,
+    rubrics: HashMap<String, Rubric>,
+    deliveries: Vec<Delivery>,
+}
+
+/// Emulation of the core side of the transmitter protocol.
+///
+/// Transmitters are authenticated against a table of [`Transmitter`] records and assigned the
+/// timeslots from their record.
+/// Calls and news are routed to transmitters using registered transmitter groups, callsigns and
+/// rubrics, then queued until the transmitter is connected.
+///
+/// Example:
+/// ```no_run
+/// # use dapnet_api::{Transmitter, transmitter::TransmitterServer};
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let transmitters: Vec<Transmitter> = Vec::new();
+/// let server = TransmitterServer::new(&transmitters);
+/// let listener = tokio::net::TcpListener::bind("127.0.0.1:43434").await.unwrap();
+/// server.serve(listener).await.unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TransmitterServer {
+    state: Arc<Mutex<State>>,
+}
+
+impl TransmitterServer {
+    /// Creates a server that accepts the given transmitters.
+    ///
+    /// Transmitters without an auth key or with invalid timeslots are ignored.
+    pub fn new(transmitters: &[Transmitter]) -> Self {
+        let server = Self::default();
+        for transmitter in transmitters {
+            let _ = server.add_transmitter(transmitter);
+        }
+        server
+    }
+
+    pub fn add_transmitter(&self, transmitter: &Transmitter) -> crate::Result<()> {
+        let auth_key = transmitter
+            .auth_key
+            .clone()
+            .ok_or_else(|| crate::Error::NotFound(format!("auth key of {}", transmitter.name)))?;
+        let timeslots = transmitter.timeslots.parse()?;
+
+        self.state.lock().unwrap().transmitters.insert(
+            transmitter.name.clone(),
+            TransmitterState {
+                auth_key,
+                timeslots,
+                queue: VecDeque::new(),
+                connected: false,
+                notify: Arc::new(Notify::new()),
+            },
+        );
+
+        Ok(())
+    }
+
+    pub fn add_transmitter_group(&self, group: &TransmitterGroup) {
+        self.state
+            .lock()
+            .unwrap()
+            .groups
+            .insert(group.name.clone(), group.transmitters.clone());
+    }
+
+    /// Registers the RICs of the pagers belonging to a callsign.
+    pub fn add_callsign(&self, callsign: &Callsign, rics: &[u32]) {
+        self.state
+            .lock()
+            .unwrap()
+            .callsigns
+            .insert(callsign.name.clone(), (rics.to_vec(), callsign.numeric));
+    }
+
+    pub fn add_rubric(&self, rubric: &Rubric) {
+        self.state
+            .lock()
+            .unwrap()
+            .rubrics
+            .insert(rubric.name.clone(), rubric.clone());
+    }
+
+    /// Queues a call on every transmitter in its transmitter groups.
+    ///
+    /// Calls are sent as numeric pages to numeric callsigns, or to every recipient if the call was
+    /// built in numeric mode.
+    /// Emergency calls are placed at the front of the queues.
+    pub fn queue_call(&self, call: &OutgoingCall) -> crate::Result<()> {
+        let mut state = self.state.lock().unwrap();
+
+        let mut messages = Vec::new();
+        for recipient in &call.recipients {
+            let (rics, numeric) = state
+                .callsigns
+                .get(recipient)
+                .ok_or_else(|| crate::Error::NotFound(format!("callsign {recipient}")))?;
+
+            for ric in rics {
+                let page = if *numeric || call.numeric {
+                    pocsag::Page::new(
+                        *ric,
+                        pocsag::Function::Numeric,
+                        pocsag::Content::Numeric(call.text.clone()),
+                    )?
+                } else {
+                    pocsag::Page::new(
+                        *ric,
+                        pocsag::Function::Alphanumeric,
+                        pocsag::Content::Alphanumeric(call.text.clone()),
+                    )?
+                };
+                messages.push(Message::from_page(&page));
+            }
+        }
+
+        state.queue(&call.transmitter_groups, &messages, call.emergency)
+    }
+
+    /// Queues the name of a rubric on every transmitter in its transmitter groups.
+    pub fn queue_rubric(&self, name: &str) -> crate::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let rubric = state.rubric(name)?;
+
+        let message = Message::from_page(&SkyperMessage::from_rubric(&rubric)?.to_page());
+        state.queue(&rubric.transmitter_groups, &[message], false)
+    }
+
+    /// Queues a news item on every transmitter in the transmitter groups of its rubric.
+    pub fn queue_news(&self, news: &OutgoingNews) -> crate::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let rubric = state.rubric(&news.rubric)?;
+
+        let message = SkyperMessage::from_outgoing_news(news, &rubric)?;
+        let message = Message::from_page(&message.to_page());
+        state.queue(&rubric.transmitter_groups, &[message], false)
+    }
+
+    /// Messages waiting to be sent to a transmitter.
+    pub fn pending(&self, transmitter: &str) -> Vec<Message> {
+        self.state
+            .lock()
+            .unwrap()
+            .transmitters
+            .get(transmitter)
+            .map(|t| t.queue.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Messages acknowledged by transmitters, in the order they were acknowledged.
+    pub fn deliveries(&self) -> Vec<Delivery> {
+        self.state.lock().unwrap().deliveries.clone()
+    }
+
+    /// Names of the transmitters that are currently logged in.
+    pub fn connected_transmitters(&self) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        let mut names: Vec<_> = state
+            .transmitters
+            .iter()
+            .filter(|(_, t)| t.connected)
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Accepts and handles transmitter connections until an error occurs accepting a connection.
+    pub async fn serve(&self, listener: TcpListener) -> crate::Result<()> {
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let server = self.clone();
+            tokio::spawn(async move {
+                let _ = server.handle_connection(stream).await;
+            });
+        }
+    }
+
+    /// Handles a single transmitter connection until it is closed.
+    pub async fn handle_connection<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        stream: S,
+    ) -> crate::Result<()> {
+        let mut connection = Connection {
+            stream: BufReader::new(stream),
+        };
+
+        let ClientMessage::Login { name, auth_key, .. } = connection.receive().await? else {
+            return Err(crate::Error::ProtocolError("expected login".to_string()));
+        };
+
+        let (timeslots, notify) = {
+            let mut state = self.state.lock().unwrap();
+            match state.transmitters.get_mut(&name) {
+                Some(t) if t.auth_key == auth_key && !t.connected => {
+                    t.connected = true;
+                    (t.timeslots, t.notify.clone())
+                }
+                _ => {
+                    return Err(crate::Error::ProtocolError(format!(
+                        "authentication failed for {name}"
+                    )));
+                }
+            }
+        };
+
+        let result = self
+            .run_session(&mut connection, &name, timeslots, &notify)
+            .await;
+
+        if let Some(t) = self.state.lock().unwrap().transmitters.get_mut(&name) {
+            t.connected = false;
+        }
+
+        result
+    }
+
+    async fn run_session<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        connection: &mut Connection<S>,
+        name: &str,
+        timeslots: Timeslots,
+        notify: &Notify,
+    ) -> crate::Result<()> {
+        let server_time = super::protocol_time(Utc::now());
+        connection
+            .send(&ServerMessage::TimeSync(server_time))
+            .await?;
+        let ClientMessage::TimeSync { client, .. } = connection.receive().await? else {
+            return Err(crate::Error::ProtocolError(
+                "expected time sync".to_string(),
+            ));
+        };
+        connection.expect_ack().await?;
+
+        let correction = server_time.wrapping_sub(client) as i16;
+        connection
+            .send(&ServerMessage::TimeCorrection(correction.into()))
+            .await?;
+        connection.expect_ack().await?;
+
+        connection
+            .send(&ServerMessage::Timeslots(timeslots))
+            .await?;
+        connection.expect_ack().await?;
+
+        let mut id = 0u8;
+        loop {
+            // Messages are taken off the queue while in flight, so calls queued in the meantime
+            // (emergency calls in particular) can't be mistaken for the one being acknowledged.
+            let next = self
+                .state
+                .lock()
+                .unwrap()
+                .transmitters
+                .get_mut(name)
+                .and_then(|t| t.queue.pop_front());
+
+            let Some(message) = next else {
+                tokio::select! {
+                    _ = notify.notified() => {}
+                    result = connection.stream.fill_buf() => {
+                        if result?.is_empty() {
+                            return Err(crate::Error::ProtocolError(
+                                "connection closed by transmitter".to_string(),
+                            ));
+                        }
+                        // Nothing is expected from the transmitter while idle
+                        let line = connection.receive().await?;
+                        return Err(crate::Error::ProtocolError(format!(
+                            "unexpected line while idle: {line:?}"
+                        )));
+                    }
+                }
+                continue;
+            };
+
+            let reply = match connection
+                .send(&ServerMessage::Message {
+                    id,
+                    message: message.clone(),
+                })
+                .await
+            {
+                Ok(()) => connection.receive().await,
+                Err(e) => Err(e),
+            };
+
+            let mut state = self.state.lock().unwrap();
+            match reply {
+                Ok(ClientMessage::MessageAck { next_id }) if next_id == id.wrapping_add(1) => {
+                    state.deliveries.push(Delivery {
+                        transmitter: name.to_string(),
+                        id,
+                        message,
+                        acknowledged: Utc::now(),
+                    });
+                    id = next_id;
+                }
+                reply => {
+                    // Put the message back so it is sent on the next connection
+                    if let Some(t) = state.transmitters.get_mut(name) {
+                        t.queue.push_front(message);
+                    }
+                    return Err(match reply {
+                        Ok(other) => crate::Error::ProtocolError(format!(
+                            "unexpected reply to message {id}: {other:?}"
+                        )),
+                        Err(e) => e,
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl State {
+    fn rubric(&self, name: &str) -> crate::Result<Rubric> {
+        self.rubrics
+            .get(name)
+            .cloned()
+            .ok_or_else(|| crate::Error::NotFound(format!("rubric {name}")))
+    }
+
+    fn queue(&mut self, groups: &[String], messages: &[Message], front: bool) -> crate::Result<()> {
+        let mut transmitters = HashSet::new();
+        for group in groups {
+            let members = self
+                .groups
+                .get(group)
+                .ok_or_else(|| crate::Error::NotFound(format!("transmitter group {group}")))?;
+            transmitters.extend(members.iter().cloned());
+        }
+
+        for name in transmitters {
+            if let Some(t) = self.transmitters.get_mut(&name) {
+                if front {
+                    for message in messages.iter().rev() {
+                        t.queue.push_front(message.clone());
+                    }
+                } else {
+                    t.queue.extend(messages.iter().cloned());
+                }
+                t.notify.notify_one();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct Connection<S> {
+    stream: BufReader<S>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Connection<S> {
+    async fn send(&mut self, msg: &ServerMessage) -> crate::Result<()> {
+        let stream = self.stream.get_mut();
+        stream.write_all(format!("{msg}\r\n").as_bytes()).await?;
+        stream.flush().await?;
+        Ok(())
+    }
+
+    async fn receive(&mut self) -> crate::Result<ClientMessage> {
+        let mut line = String::new();
+        if self.stream.read_line(&mut line).await? == 0 {
+            return Err(crate::Error::ProtocolError(
+                "connection closed by transmitter".to_string(),
+            ));
+        }
+        line.trim_end_matches(['\r', '\n']).parse()
+    }
+
+    async fn expect_ack(&mut self) -> crate::Result<()> {
+        match self.receive().await? {
+            ClientMessage::Ack => Ok(()),
+            other => Err(crate::Error::ProtocolError(format!(
+                "expected acknowledgement, got {other:?}"
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        OutgoingCallBuilder, OutgoingNewsBuilder,
+        test_util::transmitter,
+        transmitter::{MessageType, TransmitterClient, TransmitterConfigBuilder},
+    };
+
+    fn test_server() -> TransmitterServer {
+        let server =
+            TransmitterServer::new(&[transmitter("tx-a", "key-a"), transmitter("tx-b", "key-b")]);
+        server.add_transmitter_group(&TransmitterGroup {
+            name: "uk-all".to_string(),
+            description: String::new(),
+            transmitters: vec!["tx-a".to_string(), "tx-b".to_string()],
+            owners: vec![],
+        });
+        server.add_transmitter_group(&TransmitterGroup {
+            name: "uk-a".to_string(),
+            description: String::new(),
+            transmitters: vec!["tx-a".to_string()],
+            owners: vec![],
+        });
+        server.add_callsign(
+            &Callsign {
+                name: "m0nxn".to_string(),
+                description: String::new(),
+                numeric: false,
+                owners: vec![],
+            },
+            &[1234567, 1234568],
+        );
+        server.add_callsign(
+            &Callsign {
+                name: "m0abc".to_string(),
+                description: String::new(),
+                numeric: true,
+                owners: vec![],
+            },
+            &[2000],
+        );
+        server.add_rubric(&Rubric {
+            name: "dx".to_string(),
+            label: "DX".to_string(),
+            number: 2,
+            transmitter_groups: vec!["uk-a".to_string()],
+            owners: vec![],
+        });
+        server
+    }
+
+    fn test_call(
+        text: &str,
+        recipients: &[&str],
+        groups: &[&str],
+        emergency: bool,
+    ) -> OutgoingCall {
+        OutgoingCallBuilder::default()
+            .text(text.to_string())
+            .recipients(recipients.iter().map(|r| r.to_string()).collect())
+            .transmitter_groups(groups.iter().map(|g| g.to_string()).collect())
+            .emergency(emergency)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn call_routing() {
+        let server = test_server();
+
+        server
+            .queue_call(&test_call("hello", &["m0nxn", "m0abc"], &["uk-a"], false))
+            .unwrap();
+
+        let pending = server.pending("tx-a");
+        assert_eq!(pending.len(), 3);
+        assert_eq!(pending[0].ric, 1234567);
+        assert_eq!(pending[2].ric, 2000);
+        assert_eq!(pending[2].message_type, MessageType::Numeric);
+        assert!(server.pending("tx-b").is_empty());
+
+        server
+            .queue_call(&test_call("urgent", &["m0abc"], &["uk-all"], true))
+            .unwrap();
+        assert_eq!(server.pending("tx-a")[0].text, "urgent");
+        assert_eq!(server.pending("tx-b").len(), 1);
+    }
+
+    #[test]
+    fn unknown_routing() {
+        let server = test_server();
+        assert!(
+            server
+                .queue_call(&test_call("hello", &["nobody"], &["uk-a"], false))
+                .is_err()
+        );
+        assert!(
+            server
+                .queue_call(&test_call("hello", &["m0nxn"], &["nowhere"], false))
+                .is_err()
+        );
+        assert!(server.queue_rubric("nothing").is_err());
+    }
+
+    #[test]
+    fn news_routing() {
+        let server = test_server();
+
+        server.queue_rubric("dx").unwrap();
+        server
+            .queue_news(
+                &OutgoingNewsBuilder::default()
+                    .rubric("dx".to_string())
+                    .text("hello".to_string())
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+
+        let pending = server.pending("tx-a");
+        assert_eq!(pending.len(), 2);
+        assert_eq!(
+            SkyperMessage::decode(pending[0].ric, &pending[0].text).unwrap(),
+            SkyperMessage::rubric(2, "DX").unwrap()
+        );
+        assert_eq!(
+            SkyperMessage::decode(pending[1].ric, &pending[1].text).unwrap(),
+            SkyperMessage::news(2, 1, "hello").unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn session_with_client() {
+        let server = test_server();
+        server
+            .queue_call(&test_call("hello", &["m0nxn"], &["uk-a"], false))
+            .unwrap();
+
+        let (client_stream, server_stream) = tokio::io::duplex(1024);
+        let handle = {
+            let server = server.clone();
+            tokio::spawn(async move { server.handle_connection(server_stream).await })
+        };
+
+        let config = TransmitterConfigBuilder::default()
+            .name("tx-a".to_string())
+            .auth_key("key-a".to_string())
+            .build()
+            .unwrap();
+        let mut client = TransmitterClient::login(client_stream, &config)
+            .await
+            .unwrap();
+
+        let mut received = Vec::new();
+        while received.len() < 2 {
+            if let Some(ServerMessage::Message { message, .. }) =
+                client.next_message().await.unwrap()
+            {
+                received.push(message);
+            }
+        }
+
+        assert_eq!(client.timeslots(), "0123".parse().unwrap());
+        assert_eq!(received[0].ric, 1234567);
+        assert_eq!(received[1].ric, 1234568);
+        assert_eq!(server.connected_transmitters(), vec!["tx-a".to_string()]);
+
+        drop(client);
+        assert!(handle.await.unwrap().is_err());
+
+        let deliveries = server.deliveries();
+        assert_eq!(deliveries.len(), 2);
+        assert_eq!(deliveries[0].id, 0);
+        assert_eq!(deliveries[1].id, 1);
+        assert!(deliveries.iter().all(|d| d.transmitter == "tx-a"));
+        assert!(server.pending("tx-a").is_empty());
+        assert!(server.connected_transmitters().is_empty());
+    }
+
+    #[tokio::test]
+    async fn emergency_call_queued_during_ack() {
+        let server = test_server();
+        server
+            .queue_call(&test_call("hello", &["m0abc"], &["uk-a"], false))
+            .unwrap();
+
+        let (client_stream, server_stream) = tokio::io::duplex(1024);
+        let handle = {
+            let server = server.clone();
+            tokio::spawn(async move { server.handle_connection(server_stream).await })
+        };
+
+        // Drive the transmitter side by hand so the acknowledgement can be held back
+        let mut client = BufReader::new(client_stream);
+        let mut line = String::new();
+        client
+            .get_mut()
+            .write_all(b"[RasPager v1.0 tx-a key-a]\r\n")
+            .await
+            .unwrap();
+        for _ in 0..3 {
+            line.clear();
+            client.read_line(&mut line).await.unwrap();
+            if let Some(server_time) = line.trim_end().strip_prefix("2:") {
+                let reply = format!("2:{server_time}:0000\r\n");
+                client.get_mut().write_all(reply.as_bytes()).await.unwrap();
+            }
+            client.get_mut().write_all(b"+\r\n").await.unwrap();
+        }
+
+        let mut next_message = async |client: &mut BufReader<_>| {
+            line.clear();
+            client.read_line(&mut line).await.unwrap();
+            match line.trim_end().parse().unwrap() {
+                ServerMessage::Message { id, message } => (id, message),
+                other => panic!("unexpected message {other:?}"),
+            }
+        };
+
+        let (id, message) = next_message(&mut client).await;
+        assert_eq!(message.text, "hello");
+        server
+            .queue_call(&test_call("urgent", &["m0abc"], &["uk-a"], true))
+            .unwrap();
+        client
+            .get_mut()
+            .write_all(format!("#{:02X} +\r\n", id + 1).as_bytes())
+            .await
+            .unwrap();
+
+        let (id, message) = next_message(&mut client).await;
+        assert_eq!(message.text, "urgent");
+        client
+            .get_mut()
+            .write_all(format!("#{:02X} +\r\n", id + 1).as_bytes())
+            .await
+            .unwrap();
+
+        drop(client);
+        assert!(handle.await.unwrap().is_err());
+
+        let delivered: Vec<_> = server
+            .deliveries()
+            .into_iter()
+            .map(|d| d.message.text)
+            .collect();
+        assert_eq!(delivered, ["hello", "urgent"]);
+        assert!(server.pending("tx-a").is_empty());
+    }
+
+    #[tokio::test]
+    async fn unacknowledged_message_is_requeued() {
+        let server = test_server();
+        server
+            .queue_call(&test_call("hello", &["m0abc"], &["uk-a"], false))
+            .unwrap();
+
+        let (client_stream, server_stream) = tokio::io::duplex(1024);
+        let handle = {
+            let server = server.clone();
+            tokio::spawn(async move { server.handle_connection(server_stream).await })
+        };
+
+        let config = TransmitterConfigBuilder::default()
+            .name("tx-a".to_string())
+            .auth_key("key-a".to_string())
+            .build()
+            .unwrap();
+        let mut client = TransmitterClient::login(client_stream, &config)
+            .await
+            .unwrap();
+        for _ in 0..3 {
+            client.next_message().await.unwrap();
+        }
+
+        // Disconnect before the message is acknowledged
+        drop(client);
+        assert!(handle.await.unwrap().is_err());
+
+        assert!(server.deliveries().is_empty());
+        assert_eq!(server.pending("tx-a").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn authentication_failure() {
+        let server = test_server();
+
+        let (client_stream, server_stream) = tokio::io::duplex(1024);
+        let config = TransmitterConfigBuilder::default()
+            .name("tx-a".to_string())
+            .auth_key("wrong".to_string())
+            .build()
+            .unwrap();
+        let _client = TransmitterClient::login(client_stream, &config)
+            .await
+            .unwrap();
+
+        assert!(server.handle_connection(server_stream).await.is_err());
+        assert!(server.connected_transmitters().is_empty());
+    }
+}
@@ -1,8 +1,9 @@
+use crate::{MAX_NUMERIC_LENGTH, NumericAlphabet};
 use chrono::{DateTime, Utc};
 use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Builder)]
+#[derive(Debug, Clone, Deserialize, Serialize, Builder)]
 #[builder(build_fn(validate = "Self::validate"))]
 pub struct OutgoingCall {
     /// Message text of the call
@@ -19,14 +20,24 @@ pub struct OutgoingCall {
     /// Flag indicating if this call was sent with high priority
     #[builder(default = "false")]
     pub(crate) emergency: bool,
+
+    /// Flag indicating that this call is for numeric pagers, so the text must only use the
+    /// numeric alphabet
+    #[serde(skip)]
+    #[builder(default = "false")]
+    pub(crate) numeric: bool,
 }
 
 impl OutgoingCallBuilder {
     fn validate(&self) -> Result<(), String> {
         match &self.text {
             Some(text) => {
-                if text.len() > 80 {
+                if text.chars().count() > 80 {
                     Err("Text must be 80 characters or less".to_string())
+                } else if self.numeric == Some(true) && !NumericAlphabet::is_valid(text) {
+                    Err(format!(
+                        "Numeric text must be {MAX_NUMERIC_LENGTH} characters or less of 0-9, space, U, -, [, ] and *"
+                    ))
                 } else {
                     Ok(())
                 }
@@ -36,7 +47,7 @@ impl OutgoingCallBuilder {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Call {
     /// Message text of the call
     pub text: String,
@@ -80,6 +91,20 @@ mod test {
         assert_eq!(call.text, text);
     }
 
+    #[test]
+    fn build_80_multibyte_char() {
+        let text = "ä".repeat(80);
+
+        let call = OutgoingCallBuilder::default()
+            .recipients(vec!["m0nxn".to_string()])
+            .transmitter_groups(vec!["all".to_string()])
+            .text(text.clone())
+            .build()
+            .unwrap();
+
+        assert_eq!(call.text, text);
+    }
+
     #[test]
     #[should_panic]
     fn build_81_char() {
@@ -95,6 +120,31 @@ mod test {
             .unwrap();
     }
 
+    #[test]
+    fn build_numeric() {
+        let call = OutgoingCallBuilder::default()
+            .recipients(vec!["m0nxn".to_string()])
+            .transmitter_groups(vec!["all".to_string()])
+            .text("0161 555-1234".to_string())
+            .numeric(true)
+            .build()
+            .unwrap();
+
+        assert!(call.numeric);
+    }
+
+    #[test]
+    fn build_numeric_with_alpha_text() {
+        let result = OutgoingCallBuilder::default()
+            .recipients(vec!["m0nxn".to_string()])
+            .transmitter_groups(vec!["all".to_string()])
+            .text("call 0161 555-1234".to_string())
+            .numeric(true)
+            .build();
+
+        assert!(result.is_err());
+    }
+
     #[test]
     #[should_panic]
     fn build_no_text() {
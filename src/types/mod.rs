@@ -13,9 +13,11 @@ pub use self::{
     callsigns::Callsign,
     connection::Connection,
     news::{News, OutgoingNews, OutgoingNewsBuilder, OutgoingNewsBuilderError},
-    nodes::Node,
+    nodes::{Node, Status as NodeStatus},
     rubrics::Rubric,
     statistics::Statistics,
     transmitter_groups::TransmitterGroup,
-    transmitters::Transmitter,
+    transmitters::{
+        AntennaType, Status as TransmitterStatus, Transmitter, Usage as TransmitterUsage,
+    },
 };
@@ -2,7 +2,7 @@ use chrono::{DateTime, Utc};
 use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Builder)]
+#[derive(Debug, Clone, Deserialize, Serialize, Builder)]
 #[builder(build_fn(validate = "Self::validate"))]
 pub struct OutgoingNews {
     /// Name of the rubric to send to
@@ -21,7 +21,7 @@ impl OutgoingNewsBuilder {
     fn validate(&self) -> Result<(), String> {
         match &self.text {
             Some(text) => {
-                if text.len() > 80 {
+                if text.chars().count() > 80 {
                     Err("Text must be 80 characters or less".to_string())
                 } else {
                     Ok(())
@@ -32,7 +32,7 @@ impl OutgoingNewsBuilder {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct News {
     /// Name of the rubric to send to
     #[serde(rename = "rubricName")]
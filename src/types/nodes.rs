@@ -1,7 +1,7 @@
 use super::Connection;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 pub enum Status {
     #[serde(rename = "ONLINE")]
     Online,
@@ -11,7 +11,7 @@ pub enum Status {
     Error,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Node {
     pub name: String,
     pub version: String,
@@ -1,8 +1,9 @@
 use super::Connection;
+use crate::Secret;
 use chrono::{DateTime, Utc};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 pub enum Usage {
     #[serde(rename = "PERSONAL")]
     Personal,
@@ -10,7 +11,7 @@ pub enum Usage {
     Widerange,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 pub enum AntennaType {
     #[serde(rename = "OMNI")]
     Omnidirectional,
@@ -18,7 +19,7 @@ pub enum AntennaType {
     Directional,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 pub enum Status {
     #[serde(rename = "OFFLINE")]
     Offline,
@@ -28,7 +29,7 @@ pub enum Status {
     Error,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Transmitter {
     pub name: String,
     pub usage: Usage,
@@ -56,9 +57,9 @@ pub struct Transmitter {
     pub node: Option<String>,
 
     /// Key to be used for authentication by transmitter/modem
-    /// Only present when the API user is the owner of the transmitter
-    #[serde(rename = "authKey")]
-    pub auth_key: Option<String>,
+    /// Only present when the API user is the owner of the transmitter, never serialized
+    #[serde(rename = "authKey", skip_serializing)]
+    pub auth_key: Option<Secret>,
 
     #[serde(rename = "deviceType")]
     pub device_type: Option<String>,
@@ -0,0 +1,466 @@
+use crate::{
+    Callsign, Client, NumericAlphabet, OutgoingCall, Transmitter, TransmitterGroup,
+    TransmitterStatus,
+};
+use reqwest::StatusCode;
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// A problem found when validating a call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallIssue {
+    /// The API rejected the credentials of the client.
+    Unauthorized,
+
+    /// The text does not start with the sender prefix required by the client.
+    MissingSenderPrefix(String),
+
+    NoRecipients,
+    UnknownRecipient(String),
+
+    /// The recipient has a numeric pager and the text is not valid numeric text.
+    NotNumeric(String),
+
+    NoTransmitterGroups,
+    UnknownTransmitterGroup(String),
+
+    /// A transmitter group lists a transmitter that does not exist.
+    UnknownTransmitter {
+        group: String,
+        transmitter: String,
+    },
+
+    /// None of the transmitters in the group are online, so the call will not be transmitted.
+    NoOnlineTransmitters(String),
+
+    /// Some of the transmitters in the group are not online.
+    OfflineTransmitters {
+        group: String,
+        transmitters: Vec<String>,
+    },
+}
+
+impl std::fmt::Display for CallIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unauthorized => write!(f, "credentials were rejected by the API"),
+            Self::MissingSenderPrefix(prefix) => {
+                write!(f, "text does not start with the sender prefix \"{prefix}\"")
+            }
+            Self::NoRecipients => write!(f, "call has no recipients"),
+            Self::UnknownRecipient(name) => write!(f, "callsign {name} does not exist"),
+            Self::NotNumeric(name) => write!(
+                f,
+                "callsign {name} has a numeric pager which cannot display the text"
+            ),
+            Self::NoTransmitterGroups => write!(f, "call has no transmitter groups"),
+            Self::UnknownTransmitterGroup(name) => {
+                write!(f, "transmitter group {name} does not exist")
+            }
+            Self::UnknownTransmitter { group, transmitter } => write!(
+                f,
+                "transmitter {transmitter} in group {group} does not exist"
+            ),
+            Self::NoOnlineTransmitters(group) => {
+                write!(f, "transmitter group {group} has no online transmitters")
+            }
+            Self::OfflineTransmitters {
+                group,
+                transmitters,
+            } => write!(
+                f,
+                "transmitter(s) {} in group {group} are not online",
+                transmitters.join(", ")
+            ),
+        }
+    }
+}
+
+/// The result of validating a call, see [`Client::validate_call`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CallValidationReport {
+    /// Problems that will stop the call from being accepted or delivered.
+    pub errors: Vec<CallIssue>,
+
+    /// Problems that may stop the call from reaching some pagers.
+    pub warnings: Vec<CallIssue>,
+}
+
+impl CallValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Checks a call against the metadata fetched for it.
+fn evaluate(
+    text: &str,
+    callsigns: &[(String, Option<Callsign>)],
+    groups: &[(String, Option<TransmitterGroup>)],
+    transmitters: &[Transmitter],
+) -> CallValidationReport {
+    let mut report = CallValidationReport::default();
+
+    if callsigns.is_empty() {
+        report.errors.push(CallIssue::NoRecipients);
+    }
+
+    for (name, callsign) in callsigns {
+        match callsign {
+            None => report
+                .errors
+                .push(CallIssue::UnknownRecipient(name.clone())),
+            Some(callsign) if callsign.numeric && !NumericAlphabet::is_valid(text) => {
+                report.errors.push(CallIssue::NotNumeric(name.clone()))
+            }
+            Some(_) => {}
+        }
+    }
+
+    if groups.is_empty() {
+        report.errors.push(CallIssue::NoTransmitterGroups);
+    }
+
+    for (name, group) in groups {
+        let Some(group) = group else {
+            report
+                .errors
+                .push(CallIssue::UnknownTransmitterGroup(name.clone()));
+            continue;
+        };
+
+        let mut online = 0;
+        let mut offline = Vec::new();
+        for transmitter_name in &group.transmitters {
+            match transmitters
+                .iter()
+                .find(|t| t.name.eq_ignore_ascii_case(transmitter_name))
+            {
+                Some(t) if t.status == TransmitterStatus::Online => online += 1,
+                Some(_) => offline.push(transmitter_name.clone()),
+                None => report.warnings.push(CallIssue::UnknownTransmitter {
+                    group: name.clone(),
+                    transmitter: transmitter_name.clone(),
+                }),
+            }
+        }
+
+        if online == 0 {
+            report
+                .errors
+                .push(CallIssue::NoOnlineTransmitters(name.clone()));
+        } else if !offline.is_empty() {
+            report.warnings.push(CallIssue::OfflineTransmitters {
+                group: name.clone(),
+                transmitters: offline,
+            });
+        }
+    }
+
+    report
+}
+
+/// Network metadata cached by a client for validation.
+#[derive(Debug)]
+pub(crate) struct MetadataCache {
+    ttl: Duration,
+    callsigns: CacheEntries<Option<Callsign>>,
+    groups: CacheEntries<Option<TransmitterGroup>>,
+    transmitters: CacheEntries<Vec<Transmitter>>,
+}
+
+impl MetadataCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            callsigns: CacheEntries::default(),
+            groups: CacheEntries::default(),
+            transmitters: CacheEntries::default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CacheEntries<T>(Mutex<HashMap<String, (Instant, T)>>);
+
+impl<T> Default for CacheEntries<T> {
+    fn default() -> Self {
+        Self(Mutex::new(HashMap::new()))
+    }
+}
+
+impl<T: Clone> CacheEntries<T> {
+    fn get(&self, key: &str, ttl: Duration) -> Option<T> {
+        let entries = self.0.lock().unwrap();
+        entries
+            .get(&key.to_lowercase())
+            .filter(|(fetched, _)| fetched.elapsed() < ttl)
+            .map(|(_, value)| value.clone())
+    }
+
+    fn insert(&self, key: &str, value: T) {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(key.to_lowercase(), (Instant::now(), value));
+    }
+}
+
+/// Fetches a value through the cache of `$client`, if it has one.
+macro_rules! cached {
+    ($client:expr, $entries:ident, $key:expr, $fetch:expr) => {{
+        let cache = $client.metadata_cache.as_ref();
+        match cache.and_then(|cache| cache.$entries.get($key, cache.ttl)) {
+            Some(value) => value,
+            None => {
+                let value = $fetch;
+                if let Some(cache) = cache {
+                    cache.$entries.insert($key, value.clone());
+                }
+                value
+            }
+        }
+    }};
+}
+
+impl Client {
+    /// Checks a call against the current state of the network without sending it.
+    ///
+    /// Checks that the recipients and transmitter groups exist, that each group has online
+    /// transmitters, that the text can be displayed by numeric pagers and that the credentials
+    /// are accepted.
+    /// Text is checked as it would be sent, i.e. after the sender prefix has been applied.
+    ///
+    /// Permission to send to particular recipients or transmitter groups is not checked, the API
+    /// has no way to query it and DAPNET currently lets any user send calls to any callsign and
+    /// transmitter group.
+    ///
+    /// Metadata is cached if the client was created with
+    /// [`with_metadata_cache`](Client::with_metadata_cache).
+    ///
+    /// Example:
+    /// ```no_run
+    /// # use dapnet_api::{Client, OutgoingCallBuilder};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let client = Client::new("m0nxn", "my_super_secret_password");
+    /// let call = OutgoingCallBuilder::default()
+    ///     .text("M0NXN: this is a test".to_string())
+    ///     .recipients(vec!["m0nxn".to_string()])
+    ///     .transmitter_groups(vec!["uk-all".to_string()])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let report = client.validate_call(&call).await.unwrap();
+    /// for warning in &report.warnings {
+    ///     println!("warning: {warning}");
+    /// }
+    /// if report.is_valid() {
+    ///     client.new_call(&call).await.unwrap();
+    /// }
+    /// # }
+    /// ```
+    pub async fn validate_call(&self, call: &OutgoingCall) -> crate::Result<CallValidationReport> {
+        let text = match &self.sender_prefix {
+            Some(prefix) if !call.numeric => match prefix.apply(&call.text) {
+                Ok(text) => text,
+                Err(_) => {
+                    return Ok(CallValidationReport {
+                        errors: vec![CallIssue::MissingSenderPrefix(prefix.prefix())],
+                        warnings: Vec::new(),
+                    });
+                }
+            },
+            _ => call.text.clone(),
+        };
+
+        match self.fetch_call_metadata(call).await {
+            Ok((callsigns, groups, transmitters)) => {
+                Ok(evaluate(&text, &callsigns, &groups, &transmitters))
+            }
+            Err(crate::Error::ApiError(StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN)) => {
+                Ok(CallValidationReport {
+                    errors: vec![CallIssue::Unauthorized],
+                    warnings: Vec::new(),
+                })
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    async fn fetch_call_metadata(
+        &self,
+        call: &OutgoingCall,
+    ) -> crate::Result<(
+        Vec<(String, Option<Callsign>)>,
+        Vec<(String, Option<TransmitterGroup>)>,
+        Vec<Transmitter>,
+    )> {
+        let mut callsigns = Vec::new();
+        for name in &call.recipients {
+            let callsign = cached!(self, callsigns, name, self.get_callsign(name).await?);
+            callsigns.push((name.clone(), callsign));
+        }
+
+        let mut groups = Vec::new();
+        for name in &call.transmitter_groups {
+            let group = cached!(self, groups, name, self.get_transmitter_group(name).await?);
+            groups.push((name.clone(), group));
+        }
+
+        let transmitters = if groups.iter().any(|(_, group)| group.is_some()) {
+            cached!(
+                self,
+                transmitters,
+                "",
+                self.get_all_transmitters().await?.unwrap_or_default()
+            )
+        } else {
+            Vec::new()
+        };
+
+        Ok((callsigns, groups, transmitters))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn callsign(name: &str, numeric: bool) -> (String, Option<Callsign>) {
+        (
+            name.to_string(),
+            Some(Callsign {
+                name: name.to_string(),
+                description: String::new(),
+                numeric,
+                owners: vec![],
+            }),
+        )
+    }
+
+    fn group(name: &str, transmitters: &[&str]) -> (String, Option<TransmitterGroup>) {
+        (
+            name.to_string(),
+            Some(TransmitterGroup {
+                name: name.to_string(),
+                description: String::new(),
+                transmitters: transmitters.iter().map(|t| t.to_string()).collect(),
+                owners: vec![],
+            }),
+        )
+    }
+
+    fn transmitter(name: &str, status: TransmitterStatus) -> Transmitter {
+        Transmitter {
+            status,
+            ..crate::test_util::transmitter(name, "key")
+        }
+    }
+
+    fn transmitters() -> Vec<Transmitter> {
+        vec![
+            transmitter("tx-a", TransmitterStatus::Online),
+            transmitter("tx-b", TransmitterStatus::Offline),
+            transmitter("tx-c", TransmitterStatus::Error),
+        ]
+    }
+
+    #[test]
+    fn valid_call() {
+        let report = evaluate(
+            "M0NXN: test",
+            &[callsign("m0nxn", false)],
+            &[group("uk-a", &["tx-a"])],
+            &transmitters(),
+        );
+        assert!(report.is_valid());
+        assert_eq!(report, CallValidationReport::default());
+    }
+
+    #[test]
+    fn unknown_recipients_and_groups() {
+        let report = evaluate(
+            "test",
+            &[callsign("m0nxn", false), ("m0typo".to_string(), None)],
+            &[group("uk-a", &["tx-a"]), ("uk-typo".to_string(), None)],
+            &transmitters(),
+        );
+        assert!(!report.is_valid());
+        assert_eq!(
+            report.errors,
+            vec![
+                CallIssue::UnknownRecipient("m0typo".to_string()),
+                CallIssue::UnknownTransmitterGroup("uk-typo".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_call() {
+        let report = evaluate("test", &[], &[], &transmitters());
+        assert_eq!(
+            report.errors,
+            vec![CallIssue::NoRecipients, CallIssue::NoTransmitterGroups]
+        );
+    }
+
+    #[test]
+    fn numeric_recipient() {
+        let recipients = [callsign("m0nxn", false), callsign("m0num", true)];
+        let groups = [group("uk-a", &["tx-a"])];
+
+        let report = evaluate("M0NXN: test", &recipients, &groups, &transmitters());
+        assert_eq!(
+            report.errors,
+            vec![CallIssue::NotNumeric("m0num".to_string())]
+        );
+
+        let report = evaluate("0161 555", &recipients, &groups, &transmitters());
+        assert!(report.is_valid());
+    }
+
+    #[test]
+    fn offline_transmitters() {
+        let report = evaluate(
+            "test",
+            &[callsign("m0nxn", false)],
+            &[
+                group("uk-all", &["tx-a", "tx-b", "tx-c", "tx-gone"]),
+                group("uk-down", &["tx-b", "tx-c"]),
+            ],
+            &transmitters(),
+        );
+        assert_eq!(
+            report.errors,
+            vec![CallIssue::NoOnlineTransmitters("uk-down".to_string())]
+        );
+        assert_eq!(
+            report.warnings,
+            vec![
+                CallIssue::UnknownTransmitter {
+                    group: "uk-all".to_string(),
+                    transmitter: "tx-gone".to_string(),
+                },
+                CallIssue::OfflineTransmitters {
+                    group: "uk-all".to_string(),
+                    transmitters: vec!["tx-b".to_string(), "tx-c".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn cache_entries_expire() {
+        let entries = CacheEntries::default();
+        entries.insert("M0NXN", 1);
+
+        assert_eq!(entries.get("m0nxn", Duration::from_secs(60)), Some(1));
+        assert_eq!(entries.get("m0nxn", Duration::ZERO), None);
+        assert_eq!(entries.get("m0abc", Duration::from_secs(60)), None);
+    }
+}